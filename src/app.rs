@@ -1,9 +1,98 @@
-use std::path::PathBuf;
+use crate::data::{DuplicatePolicy, DEFAULT_BACKUPS};
+use anyhow::{anyhow, Result};
+use std::path::{Path, PathBuf};
+use std::{fmt, str::FromStr};
 
+/// Storage backend used to keep the memos
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Backend {
+    /// Plain text file, one memo per line
+    #[default]
+    Text,
+    /// Embedded SQLite database
+    Sqlite,
+}
+
+impl Backend {
+    /// Default name of the data file of the backend
+    pub fn data_file(&self) -> &'static str {
+        match self {
+            Backend::Text => "memo.txt",
+            Backend::Sqlite => "memo.db",
+        }
+    }
+
+    /// Extension of the data files of the backend
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Backend::Text => "txt",
+            Backend::Sqlite => "db",
+        }
+    }
+}
+
+impl FromStr for Backend {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "text" | "txt" => Ok(Backend::Text),
+            "sqlite" => Ok(Backend::Sqlite),
+            _ => Err(anyhow!("Invalid backend '{}'. Expected: text or sqlite", s)),
+        }
+    }
+}
+
+impl fmt::Display for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Backend::Text => write!(f, "text"),
+            Backend::Sqlite => write!(f, "sqlite"),
+        }
+    }
+}
+
+/// Name of the notebook kept in the data file itself
+pub const DEFAULT_NOTEBOOK: &str = "default";
+
+/// Name of the project data file created by `memo init --local`
+pub const LOCAL_FILE: &str = ".memo";
+
+/// Returns the project data file of a directory or of its closest parent that has one,
+/// the way git finds its repository
+pub fn find_local(start: &Path) -> Option<PathBuf> {
+    start
+        .ancestors()
+        .map(|dir| dir.join(LOCAL_FILE))
+        .find(|path| path.is_file())
+}
+
+/// Directory next to the data file that holds the other notebooks
+const NOTEBOOKS_DIR: &str = "notebooks";
+
+/// Check that a notebook name only has letters, digits, '-' and '_', e.g. `work` or `on-call`
+pub fn validate_notebook_name(name: &str) -> Result<()> {
+    let valid_chars = name
+        .chars()
+        .all(|c| c.is_alphanumeric() || c == '-' || c == '_');
+    if name.is_empty() || !valid_chars || name.starts_with('-') {
+        return Err(anyhow!(
+            "Invalid notebook name '{}'. Use letters, digits, '-' and '_'",
+            name
+        ));
+    }
+    Ok(())
+}
+
+#[derive(Clone)]
 pub struct AppConfig {
     name: String,
     data_file: String,
     pub data_dir: PathBuf,
+    /// Number of rotating backups kept of the data file
+    pub backups: usize,
+    /// How lines of a text data file that use the same id are loaded
+    pub duplicates: DuplicatePolicy,
 }
 
 impl AppConfig {
@@ -14,6 +103,22 @@ impl AppConfig {
             name: name.to_string(),
             data_file: data_file.to_string(),
             data_dir: system_data_dir.join(name),
+            backups: DEFAULT_BACKUPS,
+            duplicates: DuplicatePolicy::default(),
+        }
+    }
+
+    /// App config of a data file at the given path, relative to the current directory if not absolute
+    pub fn from_path(name: &str, path: &Path) -> Self {
+        AppConfig {
+            name: name.to_string(),
+            data_file: path
+                .file_name()
+                .map(|file| file.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            data_dir: path.parent().map(Path::to_path_buf).unwrap_or_default(),
+            backups: DEFAULT_BACKUPS,
+            duplicates: DuplicatePolicy::default(),
         }
     }
 
@@ -34,6 +139,59 @@ impl AppConfig {
     pub fn data_dir(&self) -> &PathBuf {
         &self.data_dir
     }
+
+    /// Directory of the notebooks other than the default one.
+    /// It is hidden when the data file is, e.g. `.notebooks` next to a `.memo` project file.
+    pub fn notebooks_dir(&self) -> PathBuf {
+        if self.data_file.starts_with('.') {
+            self.data_dir.join(format!(".{}", NOTEBOOKS_DIR))
+        } else {
+            self.data_dir.join(NOTEBOOKS_DIR)
+        }
+    }
+
+    /// App config of a notebook. The default notebook is this data file, the others are
+    /// files with the same extension in `notebooks_dir`, e.g. `notebooks/work.txt`
+    pub fn notebook(&self, notebook: &str) -> Result<AppConfig> {
+        if notebook == DEFAULT_NOTEBOOK {
+            return Ok(self.clone());
+        }
+        validate_notebook_name(notebook)?;
+        let data_file = match Path::new(&self.data_file).extension() {
+            Some(extension) => format!("{}.{}", notebook, extension.to_string_lossy()),
+            None => notebook.to_string(),
+        };
+        Ok(AppConfig {
+            name: self.name.clone(),
+            data_file,
+            data_dir: self.notebooks_dir(),
+            backups: self.backups,
+            duplicates: self.duplicates,
+        })
+    }
+
+    /// Names of the notebooks, the default one first and then the others sorted
+    pub fn notebooks(&self) -> Result<Vec<String>> {
+        let extension = Path::new(&self.data_file).extension();
+        let mut notebooks = Vec::new();
+        if self.notebooks_dir().is_dir() {
+            for entry in std::fs::read_dir(self.notebooks_dir())? {
+                let path = entry?.path();
+                let name = match path.file_stem() {
+                    Some(name) if path.is_file() && path.extension() == extension => {
+                        name.to_string_lossy().to_string()
+                    }
+                    _ => continue,
+                };
+                if validate_notebook_name(&name).is_ok() && name != DEFAULT_NOTEBOOK {
+                    notebooks.push(name);
+                }
+            }
+        }
+        notebooks.sort();
+        notebooks.insert(0, DEFAULT_NOTEBOOK.to_string());
+        Ok(notebooks)
+    }
 }
 
 #[cfg(test)]
@@ -77,6 +235,79 @@ mod tests {
         assert_eq!(app_config.data_dir(), &data_dir);
     }
 
+    #[test]
+    fn test_app_config_from_path() {
+        let app_config = AppConfig::from_path("memo", Path::new("/data/notes.txt"));
+        assert_eq!(app_config.data_file(), "notes.txt");
+        assert_eq!(app_config.data_dir(), &PathBuf::from("/data"));
+        assert_eq!(
+            app_config.data_file_path(),
+            PathBuf::from("/data/notes.txt")
+        );
+
+        let app_config = AppConfig::from_path("memo", Path::new("notes.txt"));
+        assert_eq!(app_config.data_file_path(), PathBuf::from("notes.txt"));
+    }
+
+    #[test]
+    fn test_app_config_notebook() {
+        let dir = tempfile::tempdir().unwrap();
+        let app_config = AppConfig::from_path("memo", &dir.path().join("memo.txt"));
+        assert_eq!(app_config.notebooks().unwrap(), vec!["default"]);
+
+        let work = app_config.notebook("work").unwrap();
+        assert_eq!(
+            work.data_file_path(),
+            dir.path().join("notebooks").join("work.txt")
+        );
+        assert_eq!(
+            app_config.notebook("default").unwrap().data_file_path(),
+            app_config.data_file_path()
+        );
+        assert!(app_config.notebook("../work").is_err());
+        assert!(app_config.notebook("").is_err());
+
+        std::fs::create_dir_all(app_config.notebooks_dir()).unwrap();
+        for file in ["work.txt", "home.txt", "home.txt.trash", "other.db"] {
+            std::fs::File::create(app_config.notebooks_dir().join(file)).unwrap();
+        }
+        assert_eq!(
+            app_config.notebooks().unwrap(),
+            vec!["default", "home", "work"]
+        );
+    }
+
+    #[test]
+    fn test_find_local() {
+        let dir = tempfile::tempdir().unwrap();
+        let nested = dir.path().join("src").join("commands");
+        std::fs::create_dir_all(&nested).unwrap();
+        assert_eq!(find_local(&nested), None);
+
+        std::fs::File::create(dir.path().join(LOCAL_FILE)).unwrap();
+        assert_eq!(find_local(&nested), Some(dir.path().join(LOCAL_FILE)));
+
+        // A directory named like the file is not a project file
+        std::fs::create_dir(nested.join(LOCAL_FILE)).unwrap();
+        assert_eq!(find_local(&nested), Some(dir.path().join(LOCAL_FILE)));
+
+        let local = AppConfig::from_path("memo", &dir.path().join(LOCAL_FILE));
+        assert_eq!(
+            local.notebook("work").unwrap().data_file_path(),
+            dir.path().join(".notebooks").join("work")
+        );
+    }
+
+    #[test]
+    fn test_backend() {
+        assert_eq!(Backend::from_str("text").unwrap(), Backend::Text);
+        assert_eq!(Backend::from_str("SQLite").unwrap(), Backend::Sqlite);
+        assert!(Backend::from_str("csv").is_err());
+        assert_eq!(Backend::Sqlite.data_file(), "memo.db");
+        assert_eq!(Backend::Text.extension(), "txt");
+        assert_eq!(Backend::default().to_string(), "text");
+    }
+
     #[test]
     fn test_app_config_data_file() {
         let app_config = AppConfig::new("memo", "memo.txt");
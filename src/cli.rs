@@ -0,0 +1,1144 @@
+use chrono::{Duration, NaiveDateTime, NaiveTime};
+use clap::builder::PossibleValuesParser;
+use clap::error::ErrorKind;
+use clap::{CommandFactory, Parser, Subcommand};
+use memo::app::Backend;
+use memo::data::{DuplicatePolicy, Format, MemoRef};
+use memo::dates::Postpone;
+use memo::models::Priority;
+use memo::notify::Notifier;
+use memo::output::OutputFormat;
+use memo::recur::Recurrence;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+#[derive(Parser)]
+#[command(name = "memo")]
+#[command(version = "0.5.0")]
+#[command(about="A simple memo app", long_about=None)]
+#[command(author = "Lucas Vieira dos Santos")]
+#[command(author, version, about, long_about=None)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    #[arg(long, global = true, env = "MEMO_BACKEND", value_parser = parse_backend)]
+    /// Storage backend: text or sqlite [default: text]
+    pub backend: Option<Backend>,
+
+    #[arg(long, global = true, env = "MEMO_FILE")]
+    /// Path of the data file, overrides MEMO_DATA_DIR and the configuration file
+    pub file: Option<PathBuf>,
+
+    #[arg(long, global = true)]
+    /// Use the global data file even inside a directory with a .memo project file
+    pub global: bool,
+
+    #[arg(short, long, global = true, env = "MEMO_NOTEBOOK", value_parser = parse_notebook)]
+    /// Notebook to use, e.g. work or home [default: default]
+    pub notebook: Option<String>,
+
+    #[arg(long, global = true, env = "MEMO_TRASH_DAYS")]
+    /// Permanently delete memos that have been in the trash for more than this many days
+    pub trash_days: Option<u32>,
+
+    #[arg(long, global = true, env = "MEMO_DUPLICATES", value_parser = parse_duplicates)]
+    /// How duplicate ids in the data file are loaded: error, keep-first, keep-last or renumber [default: error]
+    pub duplicates: Option<DuplicatePolicy>,
+
+    #[arg(long, global = true)]
+    /// Fail instead of warning when lines of the data file are skipped or have duplicate ids
+    pub strict: bool,
+
+    /// Memo message
+    pub message: Option<Vec<String>>,
+
+    #[arg(short, long, hide = true)]
+    /// List memos grouped by date (deprecated: use `memo ls`)
+    pub list: bool,
+
+    #[arg(short, long, hide = true)]
+    /// Initialize the memo file (deprecated: use `memo init`)
+    pub init: bool,
+
+    #[arg(short, long, hide = true)]
+    /// List memos sorted by ID (deprecated: use `memo ls --sorted`)
+    pub sorted: bool,
+
+    #[arg(short, long, hide = true, value_parser = parse_removed_id, num_args=1..)]
+    /// Remove a memo by ID (deprecated: use `memo rm`)
+    pub remove: Option<Vec<u32>>,
+}
+
+#[derive(Subcommand, Debug, PartialEq)]
+pub enum Command {
+    /// Add a new memo
+    Add {
+        /// Memo message
+        #[arg(required = true)]
+        message: Vec<String>,
+
+        #[arg(short, long, value_parser = parse_date_time)]
+        /// Due date of the memo, e.g. "tomorrow 9am", "next friday", "in 3 days" or "2024-11-01 14:00"
+        due: Option<NaiveDateTime>,
+
+        #[arg(short, long = "tag", value_parser = parse_tag)]
+        /// Tag of the memo, can be repeated. Tags can also be written inline as +tag or #tag
+        tags: Vec<String>,
+
+        #[arg(short, long, value_parser = parse_priority)]
+        /// Priority of the memo: A-Z, high, medium or low
+        priority: Option<Priority>,
+
+        #[arg(short, long, value_parser = parse_recurrence)]
+        /// Repeat the memo, e.g. daily, weekday, "mon,thu", "3 hours", "monthly on 15" or an RRULE.
+        /// The due date is its next occurrence, and completing it moves the due date to the one after
+        every: Option<Recurrence>,
+
+        #[arg(long, requires = "every", value_parser = parse_time_of_day)]
+        /// Time of day of the occurrences of a recurring memo, e.g. 9:30 or 2pm
+        at: Option<NaiveTime>,
+    },
+
+    /// List memos grouped by date
+    #[command(visible_alias = "list")]
+    Ls {
+        #[arg(short, long, conflicts_with = "by_priority")]
+        /// List memos sorted by ID
+        sorted: bool,
+
+        #[arg(short = 'p', long)]
+        /// List memos grouped by priority
+        by_priority: bool,
+
+        #[arg(short, long, conflicts_with = "done")]
+        /// Include memos that are done
+        all: bool,
+
+        #[arg(short, long)]
+        /// Only list memos that are done
+        done: bool,
+
+        #[arg(short, long = "tag", allow_hyphen_values = true, value_parser = parse_tag_filter)]
+        /// Only list memos with this tag, or without it if prefixed with '-'. Can be repeated
+        tags: Vec<String>,
+
+        #[arg(short, long, default_value_t, value_parser = parse_output_format)]
+        /// Output format: pretty, plain, json, jsonl, csv or tsv
+        format: OutputFormat,
+
+        #[arg(long)]
+        /// List the memos of every notebook, labelled with their notebook
+        all_notebooks: bool,
+
+        #[arg(short, long, value_parser = parse_duration)]
+        /// Only list memos due within this time from now, e.g. 7d or 12h, once per occurrence
+        upcoming: Option<Duration>,
+    },
+
+    /// List every tag with the number of memos that have it
+    Tags,
+
+    /// Search the text of every memo, case-insensitively
+    Search {
+        /// Text to search for
+        #[arg(required = true)]
+        query: Vec<String>,
+
+        #[arg(short, long, conflicts_with = "regex")]
+        /// Only match whole words
+        word: bool,
+
+        #[arg(short, long)]
+        /// Treat the query as a regular expression
+        regex: bool,
+
+        #[arg(long)]
+        /// List the results by relevance instead of grouping them by date
+        rank: bool,
+    },
+
+    /// Set the priority of a memo
+    Prio {
+        /// ID of the memo, or a prefix of its UUID
+        id: MemoRef,
+
+        /// Priority level: A-Z, high, medium or low. Omit to clear the priority
+        #[arg(value_parser = parse_priority)]
+        level: Option<Priority>,
+    },
+
+    /// Put off a memo to a later due date
+    Snooze {
+        /// ID of the memo, or a prefix of its UUID
+        id: MemoRef,
+
+        /// Duration, e.g. 15m or 2h, or date, e.g. tomorrow. Defaults to `snooze` in the
+        /// configuration file, or 10m. A duration is counted from the due date if it is still to come
+        #[arg(value_parser = parse_postpone)]
+        when: Option<Postpone>,
+    },
+
+    /// Edit the text of a memo, keeping its ID and creation time
+    Edit {
+        /// ID of the memo, or a prefix of its UUID
+        id: MemoRef,
+
+        /// New text of the memo. Omit to edit it in $VISUAL or $EDITOR
+        text: Vec<String>,
+    },
+
+    /// Undo the last change to the memos
+    Undo,
+
+    /// Redo the last undone change
+    Redo,
+
+    /// Move memos to the trash by ID
+    #[command(visible_alias = "remove")]
+    Rm {
+        /// IDs of the memos to remove, or prefixes of their UUIDs
+        #[arg(required = true)]
+        ids: Vec<MemoRef>,
+    },
+
+    /// Give the memos the IDs 1 to N in the order they were created
+    Renumber,
+
+    /// Give a memo another ID, which must be free
+    Mv {
+        /// ID of the memo to move, or a prefix of its UUID
+        id: MemoRef,
+
+        /// New ID of the memo
+        #[arg(value_parser = clap::value_parser!(u32).range(1..))]
+        new_id: u32,
+    },
+
+    /// Mark memos as done
+    Done {
+        /// IDs of the memos to mark as done, or prefixes of their UUIDs
+        #[arg(required = true)]
+        ids: Vec<MemoRef>,
+    },
+
+    /// Mark memos as not done
+    Undone {
+        /// IDs of the memos to mark as not done, or prefixes of their UUIDs
+        #[arg(required = true)]
+        ids: Vec<MemoRef>,
+    },
+
+    /// Initialize the memo file
+    Init {
+        #[arg(long)]
+        /// Create a .memo project file in the current directory, used from it and its subdirectories
+        local: bool,
+    },
+
+    /// Print the path of the data file in use and where it comes from
+    Where,
+
+    /// Convert the data file to another format, or copy the memos to another storage backend
+    Migrate {
+        #[arg(long, default_value = "jsonl", value_parser = parse_target)]
+        /// Format to migrate to: text, jsonl or sqlite.
+        /// Text files are converted in place and a backup is kept next to them
+        to: (Backend, Format),
+    },
+
+    /// Show a single memo
+    Show {
+        /// ID of the memo to show, or a prefix of its UUID
+        id: MemoRef,
+    },
+
+    /// List, restore or delete removed memos
+    Trash {
+        #[command(subcommand)]
+        command: TrashCommand,
+    },
+
+    /// Check the data file for lines that cannot be read and duplicate ids
+    Doctor {
+        #[arg(long)]
+        /// Move the lines with problems to a quarantine file next to the data file
+        fix: bool,
+    },
+
+    /// List the backups of the data file or restore one of them
+    Backup {
+        #[command(subcommand)]
+        command: BackupCommand,
+    },
+
+    /// Watch the data file and notify memos when they come due, until stopped
+    Daemon {
+        #[arg(long, value_parser = parse_notifier)]
+        /// How to notify due memos: stderr, log:<path> or command:<template>.
+        /// Defaults to `notifier` in the configuration file, or stderr
+        notify: Option<Notifier>,
+        #[arg(long, default_value_t = 5, value_parser = clap::value_parser!(u64).range(1..))]
+        /// Seconds between two checks of the data file for changes
+        interval: u64,
+        #[arg(long)]
+        /// Notify the memos that are due and exit, e.g. from cron
+        once: bool,
+    },
+
+    /// List, create, rename or delete notebooks
+    Notebooks {
+        #[command(subcommand)]
+        command: NotebooksCommand,
+    },
+
+    /// Read or change the configuration file
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+}
+
+#[derive(Subcommand, Debug, PartialEq)]
+pub enum TrashCommand {
+    /// List the memos in the trash
+    Ls,
+
+    /// Move memos back from the trash, keeping their ID if it is free
+    Restore {
+        /// IDs the memos had when they were removed, or prefixes of their UUIDs
+        #[arg(required = true)]
+        ids: Vec<MemoRef>,
+    },
+
+    /// Permanently delete every memo in the trash
+    Empty,
+}
+
+#[derive(Subcommand, Debug, PartialEq)]
+pub enum BackupCommand {
+    /// List the backups, most recent first
+    Ls,
+
+    /// Replace the data file with a backup, the current content is backed up first
+    Restore {
+        /// Number of the backup, 1 being the most recent
+        #[arg(value_parser = clap::value_parser!(u32).range(1..))]
+        n: u32,
+    },
+}
+
+#[derive(Subcommand, Debug, PartialEq)]
+pub enum NotebooksCommand {
+    /// List the notebooks with their number of memos
+    Ls,
+
+    /// Create an empty notebook
+    Create {
+        #[arg(value_parser = parse_notebook)]
+        name: String,
+    },
+
+    /// Rename a notebook, keeping its trash and history
+    Rename {
+        #[arg(value_parser = parse_notebook)]
+        name: String,
+
+        #[arg(value_parser = parse_notebook)]
+        new_name: String,
+    },
+
+    /// Delete a notebook with its trash and history
+    Delete {
+        #[arg(value_parser = parse_notebook)]
+        name: String,
+
+        #[arg(short, long)]
+        /// Delete the notebook even if it has memos
+        force: bool,
+    },
+}
+
+#[derive(Subcommand, Debug, PartialEq)]
+pub enum ConfigCommand {
+    /// Print the value of a setting
+    Get {
+        #[arg(value_parser = PossibleValuesParser::new(memo::config::KEYS))]
+        key: String,
+    },
+
+    /// Change a setting, creating the configuration file if needed
+    Set {
+        #[arg(value_parser = PossibleValuesParser::new(memo::config::KEYS))]
+        key: String,
+
+        /// New value of the setting
+        value: String,
+    },
+
+    /// Remove a setting, going back to its default
+    Unset {
+        #[arg(value_parser = PossibleValuesParser::new(memo::config::KEYS))]
+        key: String,
+    },
+
+    /// List every setting in the configuration file
+    List,
+
+    /// Print the path of the configuration file
+    Path,
+}
+
+/// Parse a date expression given in the command line, e.g. "tomorrow 9am"
+fn parse_date_time(s: &str) -> Result<NaiveDateTime, String> {
+    memo::dates::parse(s).map_err(|e| e.to_string())
+}
+
+/// Parse a duration or date given in the command line, e.g. "2h" or "tomorrow"
+fn parse_postpone(s: &str) -> Result<Postpone, String> {
+    memo::dates::parse_postpone(s, chrono::Local::now().naive_local()).map_err(|e| e.to_string())
+}
+
+/// Parse a recurrence rule given in the command line
+fn parse_recurrence(s: &str) -> Result<Recurrence, String> {
+    Recurrence::from_str(s).map_err(|e| e.to_string())
+}
+
+/// Parse a time of day given in the command line
+fn parse_time_of_day(s: &str) -> Result<NaiveTime, String> {
+    memo::dates::parse_time_of_day(s).map_err(|e| e.to_string())
+}
+
+/// Parse a duration given in the command line
+fn parse_duration(s: &str) -> Result<Duration, String> {
+    memo::dates::parse_duration(s).map_err(|e| e.to_string())
+}
+
+/// Parse a notifier given in the command line
+fn parse_notifier(s: &str) -> Result<Notifier, String> {
+    Notifier::from_str(s).map_err(|e| e.to_string())
+}
+
+/// Parse an id given to the deprecated `--remove` flag. The flag takes every value up to the
+/// next flag, so a command name after the ids, as in `memo -r 1 ls`, is reported as a mix of
+/// the deprecated flags and a command rather than as an invalid id.
+fn parse_removed_id(s: &str) -> Result<u32, String> {
+    if Command::has_subcommand(s) {
+        return Err(format!("'{}' is a command, {}", s, LEGACY_WITH_COMMAND));
+    }
+    match s.parse::<u32>() {
+        Ok(0) => Err("ids start at 1".to_string()),
+        Ok(id) => Ok(id),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Parse a storage backend given in the command line
+fn parse_backend(s: &str) -> Result<Backend, String> {
+    Backend::from_str(s).map_err(|e| e.to_string())
+}
+
+/// Parse a duplicate id policy given in the command line
+fn parse_duplicates(s: &str) -> Result<DuplicatePolicy, String> {
+    DuplicatePolicy::from_str(s).map_err(|e| e.to_string())
+}
+
+/// Parse a notebook name given in the command line
+fn parse_notebook(s: &str) -> Result<String, String> {
+    memo::app::validate_notebook_name(s)
+        .map(|_| s.to_string())
+        .map_err(|e| e.to_string())
+}
+
+/// Parse the target of a migration given in the command line
+fn parse_target(s: &str) -> Result<(Backend, Format), String> {
+    match s.to_lowercase().as_str() {
+        "text" | "txt" => Ok((Backend::Text, Format::Lines)),
+        "jsonl" | "json" => Ok((Backend::Text, Format::JsonLines)),
+        "sqlite" => Ok((Backend::Sqlite, Format::Lines)),
+        _ => Err(format!(
+            "Invalid format '{}'. Expected: text, jsonl or sqlite",
+            s
+        )),
+    }
+}
+
+/// Parse an output format given in the command line
+fn parse_output_format(s: &str) -> Result<OutputFormat, String> {
+    OutputFormat::from_str(s).map_err(|e| e.to_string())
+}
+
+/// Parse a priority given in the command line
+fn parse_priority(s: &str) -> Result<Priority, String> {
+    Priority::from_str(s).map_err(|e| e.to_string())
+}
+
+/// Parse a tag given in the command line
+fn parse_tag(s: &str) -> Result<String, String> {
+    memo::models::normalize_tag(s).ok_or_else(|| format!("invalid tag '{}'", s))
+}
+
+/// Parse a tag filter given in the command line, keeping the '-' prefix of excluded tags
+fn parse_tag_filter(s: &str) -> Result<String, String> {
+    match s.strip_prefix('-') {
+        Some(tag) => parse_tag(tag).map(|tag| format!("-{}", tag)),
+        None => parse_tag(s),
+    }
+}
+
+/// Error given when the deprecated flags are combined with a command
+const LEGACY_WITH_COMMAND: &str =
+    "the deprecated '--list', '--init', '--sorted' and '--remove' flags cannot be combined with a command";
+
+impl Cli {
+    /// Parse the command line, exiting with a usage error if it is invalid, see `try_parse_args`
+    pub fn parse_args() -> Self {
+        Self::try_parse_args(std::env::args_os()).unwrap_or_else(|e| e.exit())
+    }
+
+    /// Parse command line arguments, refusing the deprecated flags along with a command,
+    /// e.g. `memo -l ls`, instead of ignoring the flags
+    pub fn try_parse_args<I, T>(args: I) -> Result<Self, clap::Error>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<std::ffi::OsString> + Clone,
+    {
+        let cli = Self::try_parse_from(args)?;
+        if cli.command.is_some() && cli.uses_legacy_flags() {
+            return Err(Self::command().error(ErrorKind::ArgumentConflict, LEGACY_WITH_COMMAND));
+        }
+        Ok(cli)
+    }
+
+    /// Returns true if any of the deprecated flags was used
+    pub fn uses_legacy_flags(&self) -> bool {
+        self.list || self.init || self.sorted || self.remove.is_some()
+    }
+
+    /// Returns the commands to run, in order.
+    /// The deprecated flag forms are translated into their subcommands and keep their old
+    /// behaviour: `--init` runs alone, otherwise a message is added, ids are removed and the
+    /// list is shown when asked for or when nothing else was requested.
+    pub fn commands(self) -> Vec<Command> {
+        if let Some(command) = self.command {
+            return vec![command];
+        }
+
+        if self.init {
+            return vec![Command::Init { local: false }];
+        }
+
+        let has_no_flags = !self.list && self.message.is_none() && self.remove.is_none();
+        let mut commands = Vec::new();
+
+        if let Some(message) = self.message {
+            commands.push(Command::Add {
+                message,
+                due: None,
+                tags: Vec::new(),
+                priority: None,
+                every: None,
+                at: None,
+            });
+        }
+
+        if let Some(ids) = self.remove {
+            commands.push(Command::Rm {
+                ids: ids.into_iter().map(MemoRef::Id).collect(),
+            });
+        }
+
+        if self.list || has_no_flags {
+            commands.push(Command::Ls {
+                sorted: self.sorted,
+                by_priority: false,
+                all: false,
+                done: false,
+                tags: Vec::new(),
+                format: OutputFormat::Pretty,
+                all_notebooks: false,
+                upcoming: None,
+            });
+        }
+
+        commands
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commands(args: &[&str]) -> Vec<Command> {
+        Cli::try_parse_from(args)
+            .expect("Error parsing args")
+            .commands()
+    }
+
+    #[test]
+    fn test_subcommands() {
+        assert_eq!(
+            commands(&["memo", "add", "buy", "milk"]),
+            vec![Command::Add {
+                message: vec!["buy".to_string(), "milk".to_string()],
+                due: None,
+                tags: vec![],
+                priority: None,
+                every: None,
+                at: None,
+            }]
+        );
+        assert_eq!(
+            commands(&["memo", "ls", "-s"]),
+            vec![Command::Ls {
+                sorted: true,
+                by_priority: false,
+                all: false,
+                done: false,
+                tags: vec![],
+                format: OutputFormat::Pretty,
+                all_notebooks: false,
+                upcoming: None,
+            }]
+        );
+        assert_eq!(
+            commands(&["memo", "rm", "1", "2"]),
+            vec![Command::Rm {
+                ids: vec![MemoRef::Id(1), MemoRef::Id(2)]
+            }]
+        );
+        assert_eq!(
+            commands(&["memo", "init"]),
+            vec![Command::Init { local: false }]
+        );
+        assert_eq!(
+            commands(&["memo", "init", "--local"]),
+            vec![Command::Init { local: true }]
+        );
+        assert_eq!(commands(&["memo", "where"]), vec![Command::Where]);
+        assert!(
+            Cli::try_parse_from(["memo", "--global", "ls"])
+                .unwrap()
+                .global
+        );
+        assert_eq!(
+            commands(&["memo", "show", "3"]),
+            vec![Command::Show { id: MemoRef::Id(3) }]
+        );
+        assert_eq!(
+            commands(&["memo", "show", "3F2A"]),
+            vec![Command::Show {
+                id: MemoRef::Uuid("3f2a".to_string())
+            }]
+        );
+        assert!(Cli::try_parse_from(["memo", "show", "0"]).is_err());
+        assert!(Cli::try_parse_from(["memo", "rm", "1", "milk"]).is_err());
+    }
+
+    #[test]
+    fn test_no_args_lists() {
+        assert_eq!(
+            commands(&["memo"]),
+            vec![Command::Ls {
+                sorted: false,
+                by_priority: false,
+                all: false,
+                done: false,
+                tags: vec![],
+                format: OutputFormat::Pretty,
+                all_notebooks: false,
+                upcoming: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_bare_message_adds() {
+        assert_eq!(
+            commands(&["memo", "buy", "milk"]),
+            vec![Command::Add {
+                message: vec!["buy".to_string(), "milk".to_string()],
+                due: None,
+                tags: vec![],
+                priority: None,
+                every: None,
+                at: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_add_due() {
+        let due =
+            NaiveDateTime::parse_from_str("2024-01-01 09:00:00", memo::DATE_TIME_FORMAT).unwrap();
+        assert_eq!(
+            commands(&["memo", "add", "call", "--due", "2024-01-01 09:00:00"]),
+            vec![Command::Add {
+                message: vec!["call".to_string()],
+                due: Some(due),
+                tags: vec![],
+                priority: None,
+                every: None,
+                at: None,
+            }]
+        );
+        assert!(Cli::try_parse_from(["memo", "add", "call", "--due", "tomorrow"]).is_ok());
+        assert!(Cli::try_parse_from(["memo", "add", "call", "--due", "soon"]).is_err());
+    }
+
+    #[test]
+    fn test_legacy_flags() {
+        let cli = Cli::try_parse_from(["memo", "-r", "1", "2", "-l"]).unwrap();
+        assert!(cli.uses_legacy_flags());
+        assert_eq!(
+            cli.commands(),
+            vec![
+                Command::Rm {
+                    ids: vec![MemoRef::Id(1), MemoRef::Id(2)]
+                },
+                Command::Ls {
+                    sorted: false,
+                    by_priority: false,
+                    all: false,
+                    done: false,
+                    tags: vec![],
+                    format: OutputFormat::Pretty,
+                    all_notebooks: false,
+                    upcoming: None,
+                }
+            ]
+        );
+        assert_eq!(
+            commands(&["memo", "-s"]),
+            vec![Command::Ls {
+                sorted: true,
+                by_priority: false,
+                all: false,
+                done: false,
+                tags: vec![],
+                format: OutputFormat::Pretty,
+                all_notebooks: false,
+                upcoming: None,
+            }]
+        );
+        assert_eq!(
+            commands(&["memo", "-i", "-l"]),
+            vec![Command::Init { local: false }]
+        );
+        assert!(!Cli::try_parse_from(["memo", "ls"])
+            .unwrap()
+            .uses_legacy_flags());
+    }
+
+    #[test]
+    fn test_legacy_flags_with_command() {
+        for args in [
+            &["memo", "-r", "1", "ls"][..],
+            &["memo", "-l", "ls"],
+            &["memo", "-i", "init"],
+            &["memo", "-s", "add", "milk"],
+        ] {
+            let error = Cli::try_parse_args(args).err().unwrap();
+            assert!(
+                error.to_string().contains(LEGACY_WITH_COMMAND),
+                "{:?}: {}",
+                args,
+                error
+            );
+        }
+        assert!(Cli::try_parse_args(["memo", "-r", "0"]).is_err());
+        assert!(Cli::try_parse_args(["memo", "-r", "1", "2", "-l"]).is_ok());
+
+        // Global flags can still come before the command
+        let cli = Cli::try_parse_args(["memo", "--backend", "sqlite", "ls"]).unwrap();
+        assert!(matches!(cli.command, Some(Command::Ls { .. })));
+        assert!(cli.message.is_none());
+    }
+
+    #[test]
+    fn test_done() {
+        assert_eq!(
+            commands(&["memo", "done", "1", "2"]),
+            vec![Command::Done {
+                ids: vec![MemoRef::Id(1), MemoRef::Id(2)]
+            }]
+        );
+        assert_eq!(
+            commands(&["memo", "undone", "1"]),
+            vec![Command::Undone {
+                ids: vec![MemoRef::Id(1)]
+            }]
+        );
+        assert_eq!(
+            commands(&["memo", "ls", "--all"]),
+            vec![Command::Ls {
+                sorted: false,
+                by_priority: false,
+                all: true,
+                done: false,
+                tags: vec![],
+                format: OutputFormat::Pretty,
+                all_notebooks: false,
+                upcoming: None,
+            }]
+        );
+        assert!(Cli::try_parse_from(["memo", "ls", "--all", "--done"]).is_err());
+    }
+
+    #[test]
+    fn test_tags() {
+        assert_eq!(
+            commands(&["memo", "add", "call", "-t", "Work", "--tag", "+home"]),
+            vec![Command::Add {
+                message: vec!["call".to_string()],
+                due: None,
+                tags: vec!["work".to_string(), "home".to_string()],
+                priority: None,
+                every: None,
+                at: None,
+            }]
+        );
+        assert_eq!(
+            commands(&["memo", "ls", "--tag", "work", "--tag", "-personal"]),
+            vec![Command::Ls {
+                sorted: false,
+                by_priority: false,
+                all: false,
+                done: false,
+                tags: vec!["work".to_string(), "-personal".to_string()],
+                format: OutputFormat::Pretty,
+                all_notebooks: false,
+                upcoming: None,
+            }]
+        );
+        assert_eq!(commands(&["memo", "tags"]), vec![Command::Tags]);
+        assert_eq!(
+            commands(&["memo", "search", "buy", "milk", "-w", "--rank"]),
+            vec![Command::Search {
+                query: vec!["buy".to_string(), "milk".to_string()],
+                word: true,
+                regex: false,
+                rank: true,
+            }]
+        );
+        assert!(Cli::try_parse_from(["memo", "search"]).is_err());
+        assert!(Cli::try_parse_from(["memo", "search", "milk", "-w", "-r"]).is_err());
+        assert!(Cli::try_parse_from(["memo", "add", "call", "--tag", "a b"]).is_err());
+    }
+
+    #[test]
+    fn test_priority() {
+        assert_eq!(
+            commands(&["memo", "add", "call", "-p", "high"]),
+            vec![Command::Add {
+                message: vec!["call".to_string()],
+                due: None,
+                tags: vec![],
+                priority: Priority::new('A'),
+                every: None,
+                at: None,
+            }]
+        );
+        assert_eq!(
+            commands(&["memo", "prio", "2", "c"]),
+            vec![Command::Prio {
+                id: MemoRef::Id(2),
+                level: Priority::new('C')
+            }]
+        );
+        assert_eq!(
+            commands(&["memo", "prio", "2"]),
+            vec![Command::Prio {
+                id: MemoRef::Id(2),
+                level: None
+            }]
+        );
+        assert!(Cli::try_parse_from(["memo", "prio", "2", "urgent"]).is_err());
+    }
+
+    #[test]
+    fn test_edit() {
+        assert_eq!(
+            commands(&["memo", "edit", "3", "buy", "oat", "milk"]),
+            vec![Command::Edit {
+                id: MemoRef::Id(3),
+                text: vec!["buy".to_string(), "oat".to_string(), "milk".to_string()]
+            }]
+        );
+        assert_eq!(
+            commands(&["memo", "edit", "3"]),
+            vec![Command::Edit {
+                id: MemoRef::Id(3),
+                text: vec![]
+            }]
+        );
+        assert!(Cli::try_parse_from(["memo", "edit"]).is_err());
+        assert_eq!(commands(&["memo", "undo"]), vec![Command::Undo]);
+        assert_eq!(commands(&["memo", "redo"]), vec![Command::Redo]);
+        assert!(Cli::try_parse_from(["memo", "edit", "0"]).is_err());
+        assert!(Cli::try_parse_from(["memo", "ls", "-s", "-p"]).is_err());
+    }
+
+    #[test]
+    fn test_recurring() {
+        let parsed = commands(&[
+            "memo", "add", "standup", "--every", "weekday", "--at", "9:30",
+        ]);
+        let [Command::Add { every, at, .. }] = parsed.as_slice() else {
+            panic!("Expected an add command");
+        };
+        assert_eq!(every, &Some(Recurrence::from_str("weekday").unwrap()));
+        assert_eq!(at, &NaiveTime::from_hms_opt(9, 30, 0));
+        assert!(
+            Cli::try_parse_from(["memo", "add", "call", "--every", "FREQ=DAILY;INTERVAL=2"])
+                .is_ok()
+        );
+        assert!(Cli::try_parse_from(["memo", "add", "call", "--every", "sometimes"]).is_err());
+        assert!(Cli::try_parse_from(["memo", "add", "call", "--at", "9:30"]).is_err());
+
+        let parsed = commands(&["memo", "ls", "--upcoming", "7d"]);
+        let [Command::Ls { upcoming, .. }] = parsed.as_slice() else {
+            panic!("Expected an ls command");
+        };
+        assert_eq!(upcoming, &Some(Duration::days(7)));
+        assert!(Cli::try_parse_from(["memo", "ls", "--upcoming", "soon"]).is_err());
+    }
+
+    #[test]
+    fn test_output_format() {
+        assert_eq!(
+            commands(&["memo", "ls", "-a", "--format", "json"]),
+            vec![Command::Ls {
+                sorted: false,
+                by_priority: false,
+                all: true,
+                done: false,
+                tags: vec![],
+                format: OutputFormat::Json,
+                all_notebooks: false,
+                upcoming: None,
+            }]
+        );
+        assert!(Cli::try_parse_from(["memo", "ls", "--format", "xml"]).is_err());
+    }
+
+    #[test]
+    fn test_trash() {
+        assert_eq!(
+            commands(&["memo", "trash", "ls"]),
+            vec![Command::Trash {
+                command: TrashCommand::Ls
+            }]
+        );
+        assert_eq!(
+            commands(&["memo", "trash", "restore", "1", "3"]),
+            vec![Command::Trash {
+                command: TrashCommand::Restore {
+                    ids: vec![MemoRef::Id(1), MemoRef::Id(3)]
+                }
+            }]
+        );
+        assert!(Cli::try_parse_from(["memo", "trash", "restore"]).is_err());
+        assert!(Cli::try_parse_from(["memo", "trash"]).is_err());
+
+        let cli = Cli::try_parse_from(["memo", "ls", "--trash-days", "30"]).unwrap();
+        assert_eq!(cli.trash_days, Some(30));
+    }
+
+    #[test]
+    fn test_renumber_and_mv() {
+        assert_eq!(commands(&["memo", "renumber"]), vec![Command::Renumber]);
+        assert_eq!(
+            commands(&["memo", "mv", "100", "4"]),
+            vec![Command::Mv {
+                id: MemoRef::Id(100),
+                new_id: 4
+            }]
+        );
+        assert!(Cli::try_parse_from(["memo", "mv", "1"]).is_err());
+        assert!(Cli::try_parse_from(["memo", "mv", "1", "0"]).is_err());
+    }
+
+    #[test]
+    fn test_doctor() {
+        assert_eq!(
+            commands(&["memo", "doctor"]),
+            vec![Command::Doctor { fix: false }]
+        );
+        assert_eq!(
+            commands(&["memo", "doctor", "--fix"]),
+            vec![Command::Doctor { fix: true }]
+        );
+    }
+
+    #[test]
+    fn test_snooze() {
+        assert_eq!(
+            commands(&["memo", "snooze", "2"]),
+            vec![Command::Snooze {
+                id: MemoRef::Id(2),
+                when: None
+            }]
+        );
+        assert_eq!(
+            commands(&["memo", "snooze", "2", "15m"]),
+            vec![Command::Snooze {
+                id: MemoRef::Id(2),
+                when: Some(Postpone::For(Duration::minutes(15)))
+            }]
+        );
+        assert!(matches!(
+            commands(&["memo", "snooze", "2", "tomorrow 9am"]).as_slice(),
+            [Command::Snooze {
+                when: Some(Postpone::Until(_)),
+                ..
+            }]
+        ));
+        assert!(Cli::try_parse_from(["memo", "snooze", "2", "whenever"]).is_err());
+    }
+
+    #[test]
+    fn test_daemon() {
+        assert_eq!(
+            commands(&["memo", "daemon"]),
+            vec![Command::Daemon {
+                notify: None,
+                interval: 5,
+                once: false,
+            }]
+        );
+        assert_eq!(
+            commands(&["memo", "daemon", "--notify", "log:/tmp/memo.log", "--once"]),
+            vec![Command::Daemon {
+                notify: Some(Notifier::Log(PathBuf::from("/tmp/memo.log"))),
+                interval: 5,
+                once: true,
+            }]
+        );
+        assert!(Cli::try_parse_from(["memo", "daemon", "--notify", "email"]).is_err());
+        assert!(Cli::try_parse_from(["memo", "daemon", "--interval", "0"]).is_err());
+    }
+
+    #[test]
+    fn test_backup() {
+        assert_eq!(
+            commands(&["memo", "backup", "ls"]),
+            vec![Command::Backup {
+                command: BackupCommand::Ls
+            }]
+        );
+        assert_eq!(
+            commands(&["memo", "backup", "restore", "2"]),
+            vec![Command::Backup {
+                command: BackupCommand::Restore { n: 2 }
+            }]
+        );
+        assert!(Cli::try_parse_from(["memo", "backup", "restore", "0"]).is_err());
+        assert!(Cli::try_parse_from(["memo", "backup", "restore"]).is_err());
+    }
+
+    #[test]
+    fn test_duplicates_and_strict() {
+        let cli =
+            Cli::try_parse_from(["memo", "ls", "--duplicates", "keep-last", "--strict"]).unwrap();
+        assert_eq!(cli.duplicates, Some(DuplicatePolicy::KeepLast));
+        assert!(cli.strict);
+        assert!(Cli::try_parse_from(["memo", "ls", "--duplicates", "ignore"]).is_err());
+    }
+
+    #[test]
+    fn test_backend() {
+        let cli = Cli::try_parse_from(["memo", "ls", "--backend", "sqlite"]).unwrap();
+        assert_eq!(cli.backend, Some(Backend::Sqlite));
+        assert_eq!(
+            commands(&["memo", "migrate", "--to", "sqlite"]),
+            vec![Command::Migrate {
+                to: (Backend::Sqlite, Format::Lines)
+            }]
+        );
+        assert_eq!(
+            commands(&["memo", "migrate"]),
+            vec![Command::Migrate {
+                to: (Backend::Text, Format::JsonLines)
+            }]
+        );
+        assert!(Cli::try_parse_from(["memo", "migrate", "--to", "xml"]).is_err());
+        let cli = Cli::try_parse_from(["memo", "--backend", "sqlite", "rm", "1"]).unwrap();
+        assert_eq!(cli.backend, Some(Backend::Sqlite));
+        assert_eq!(
+            cli.commands(),
+            vec![Command::Rm {
+                ids: vec![MemoRef::Id(1)]
+            }]
+        );
+        assert!(Cli::try_parse_from(["memo", "--backend", "csv"]).is_err());
+    }
+
+    #[test]
+    fn test_config() {
+        assert_eq!(
+            commands(&["memo", "config", "set", "theme", "mono"]),
+            vec![Command::Config {
+                command: ConfigCommand::Set {
+                    key: "theme".to_string(),
+                    value: "mono".to_string()
+                }
+            }]
+        );
+        assert_eq!(
+            commands(&["memo", "config", "path"]),
+            vec![Command::Config {
+                command: ConfigCommand::Path
+            }]
+        );
+        assert!(Cli::try_parse_from(["memo", "config", "get", "colour"]).is_err());
+        assert!(Cli::try_parse_from(["memo", "config", "set", "theme"]).is_err());
+
+        let cli = Cli::try_parse_from(["memo", "ls", "--file", "/tmp/notes.txt"]).unwrap();
+        assert_eq!(cli.file, Some(PathBuf::from("/tmp/notes.txt")));
+    }
+
+    #[test]
+    fn test_notebooks() {
+        let cli = Cli::try_parse_from(["memo", "-n", "work", "add", "deploy"]).unwrap();
+        assert_eq!(cli.notebook.as_deref(), Some("work"));
+        assert!(Cli::try_parse_from(["memo", "-n", "../work", "ls"]).is_err());
+        assert_eq!(
+            commands(&["memo", "notebooks", "rename", "work", "job"]),
+            vec![Command::Notebooks {
+                command: NotebooksCommand::Rename {
+                    name: "work".to_string(),
+                    new_name: "job".to_string()
+                }
+            }]
+        );
+        assert_eq!(
+            commands(&["memo", "notebooks", "delete", "work", "-f"]),
+            vec![Command::Notebooks {
+                command: NotebooksCommand::Delete {
+                    name: "work".to_string(),
+                    force: true
+                }
+            }]
+        );
+        assert_eq!(
+            commands(&["memo", "ls", "--all-notebooks"]),
+            vec![Command::Ls {
+                sorted: false,
+                by_priority: false,
+                all: false,
+                done: false,
+                tags: vec![],
+                format: OutputFormat::Pretty,
+                all_notebooks: true,
+                upcoming: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_invalid_id() {
+        assert!(Cli::try_parse_from(["memo", "rm", "0"]).is_err());
+        assert!(Cli::try_parse_from(["memo", "show", "x"]).is_err());
+    }
+}
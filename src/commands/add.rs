@@ -1,24 +1,93 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+use chrono::prelude::*;
 use memo::app;
 use memo::data;
+use memo::dates;
+use memo::history;
+use memo::models;
+use memo::models::Priority;
+use memo::recur::{Frequency, Recurrence};
 
+/// Adds a memo to the data file, with an optional due date, tags, priority and recurrence.
+/// Inline tags (`+tag` or `#tag`) are removed from the text and stored with the other tags.
+/// A recurring memo is due at its first occurrence, at the given time of day if there is one.
 pub fn add(
     d: &mut impl data::DataFile,
     app_config: &app::AppConfig,
     content: String,
+    due: Option<NaiveDateTime>,
+    tags: Vec<String>,
+    priority: Option<Priority>,
+    every: Option<(Recurrence, Option<NaiveTime>)>,
 ) -> Result<()> {
+    let (text, mut inline_tags) = models::split_tags(&content);
+    for tag in tags {
+        if !inline_tags.contains(&tag) {
+            inline_tags.push(tag);
+        }
+    }
+
+    let (due, recurrence) = match every {
+        Some((every, at)) => {
+            let first = first_occurrence(&every, due, at, Local::now().naive_local())?;
+            (Some(first), Some(every.anchored(first)))
+        }
+        None => (due, None),
+    };
+
     let id = d.sorted_ids().last().unwrap_or(&0) + 1;
-    d.add(id, &content)?;
-    // Get lines from format
-    let lines = format!("{}", d);
-    data::write_file(&app_config.data_file_path(), &lines)?;
+    d.add(id, &text)?;
+    if due.is_some() {
+        d.set_due(id, due)?;
+    }
+    if !inline_tags.is_empty() {
+        d.set_tags(id, inline_tags)?;
+    }
+    if priority.is_some() {
+        d.set_priority(id, priority)?;
+    }
+    if recurrence.is_some() {
+        d.set_recurrence(id, recurrence)?;
+    }
+    d.save(app_config)?;
+
+    let mut step = history::Step::new("add");
+    step.push(id, None, d.get(id));
+    history::record(&app_config.data_file_path(), step)?;
     Ok(())
 }
 
+/// Returns the first occurrence of a recurring memo: the one at or after its due date, or
+/// the first one after now. Without a time of day, daily and longer rules use `DEFAULT_TIME`
+/// and hourly rules start now.
+fn first_occurrence(
+    every: &Recurrence,
+    due: Option<NaiveDateTime>,
+    at: Option<NaiveTime>,
+    now: NaiveDateTime,
+) -> Result<NaiveDateTime> {
+    let first = match (due, at) {
+        (Some(due), at) => every.first(at.map_or(due, |at| due.date().and_time(at))),
+        (None, Some(at)) => every.first_after(now.date().and_time(at), now),
+        (None, None) => {
+            let time = match every.frequency {
+                Frequency::Hourly => NaiveTime::from_hms_opt(now.hour(), now.minute(), 0),
+                _ => NaiveTime::from_hms_opt(dates::DEFAULT_TIME.0, dates::DEFAULT_TIME.1, 0),
+            };
+            let time = time.ok_or_else(|| anyhow!("Invalid time of day"))?;
+            every.first_after(now.date().and_time(time), now)
+        }
+    };
+    first.ok_or_else(|| anyhow!("The memo would never be due {}", every.describe()))
+}
+
 #[cfg(test)]
+#[allow(
+    clippy::bool_assert_comparison,
+    clippy::needless_borrows_for_generic_args
+)]
 mod tests {
     use super::*;
-    use memo::models;
 
     #[test]
     fn test_add() {
@@ -32,6 +101,153 @@ mod tests {
         let mut memo_data = models::MemoData::new();
         let content = "test".to_string();
 
-        assert_eq!(add(&mut memo_data, &app_config, content).is_ok(), true);
+        assert_eq!(
+            add(
+                &mut memo_data,
+                &app_config,
+                content,
+                None,
+                vec![],
+                None,
+                None
+            )
+            .is_ok(),
+            true
+        );
+    }
+
+    #[test]
+    fn test_add_due() {
+        let mut app_config = app::AppConfig::new("memo", "memo.txt");
+        let dir = tempfile::tempdir().unwrap();
+        app_config.data_dir = dir.path().to_path_buf();
+
+        // Create file
+        std::fs::File::create(app_config.data_file_path()).unwrap();
+
+        let mut memo_data = models::MemoData::new();
+        let due =
+            NaiveDateTime::parse_from_str("2024-01-01 09:00:00", memo::DATE_TIME_FORMAT).unwrap();
+
+        assert!(add(
+            &mut memo_data,
+            &app_config,
+            "test".to_string(),
+            Some(due),
+            vec![],
+            None,
+            None
+        )
+        .is_ok());
+        assert_eq!(memo_data.get(1).unwrap().due, Some(due));
+
+        let lines = data::read_file(&app_config.data_file_path()).unwrap();
+        assert!(lines.contains("[due:2024-01-01T09:00:00 uuid:"));
+        assert!(lines.ends_with("] test\n"));
+    }
+
+    #[test]
+    fn test_add_tags() {
+        let mut app_config = app::AppConfig::new("memo", "memo.txt");
+        let dir = tempfile::tempdir().unwrap();
+        app_config.data_dir = dir.path().to_path_buf();
+
+        // Create file
+        std::fs::File::create(app_config.data_file_path()).unwrap();
+
+        let mut memo_data = models::MemoData::new();
+        let content = "call bob +work #urgent".to_string();
+        let tags = vec!["work".to_string(), "phone".to_string()];
+
+        assert!(add(&mut memo_data, &app_config, content, None, tags, None, None).is_ok());
+        let content = memo_data.get(1).unwrap();
+        assert_eq!(content.text, "call bob");
+        assert_eq!(content.tags, vec!["work", "urgent", "phone"]);
+    }
+
+    #[test]
+    fn test_add_priority() {
+        let mut app_config = app::AppConfig::new("memo", "memo.txt");
+        let dir = tempfile::tempdir().unwrap();
+        app_config.data_dir = dir.path().to_path_buf();
+
+        // Create file
+        std::fs::File::create(app_config.data_file_path()).unwrap();
+
+        let mut memo_data = models::MemoData::new();
+        let content = "test".to_string();
+
+        assert!(add(
+            &mut memo_data,
+            &app_config,
+            content,
+            None,
+            vec![],
+            Priority::new('A'),
+            None
+        )
+        .is_ok());
+        assert_eq!(memo_data.get(1).unwrap().priority, Priority::new('A'));
+    }
+
+    #[test]
+    fn test_add_recurring() {
+        let mut app_config = app::AppConfig::new("memo", "memo.txt");
+        let dir = tempfile::tempdir().unwrap();
+        app_config.data_dir = dir.path().to_path_buf();
+        std::fs::File::create(app_config.data_file_path()).unwrap();
+
+        let mut memo_data = models::MemoData::new();
+        let every: Recurrence = "weekday".parse().unwrap();
+        let at = NaiveTime::from_hms_opt(9, 30, 0);
+        assert!(add(
+            &mut memo_data,
+            &app_config,
+            "standup".to_string(),
+            None,
+            vec![],
+            None,
+            Some((every.clone(), at))
+        )
+        .is_ok());
+        let content = memo_data.get(1).unwrap();
+        assert_eq!(content.recurrence, Some(every));
+        let due = content.due.unwrap();
+        assert!(due > Local::now().naive_local());
+        assert_eq!(due.time(), at.unwrap());
+        assert!(due.weekday().num_days_from_monday() < 5);
+        assert!(data::read_file(&app_config.data_file_path())
+            .unwrap()
+            .contains("every:FREQ=WEEKLY;BYDAY=MO,TU,WE,TH,FR"));
+    }
+
+    #[test]
+    fn test_first_occurrence() {
+        // Saturday
+        let now = NaiveDate::from_ymd_opt(2026, 10, 17)
+            .unwrap()
+            .and_hms_opt(15, 30, 20)
+            .unwrap();
+        let first = |every: &str, due: Option<&str>, at: Option<(u32, u32)>| {
+            let due = due.map(|due| dates::parse_from(due, now).unwrap());
+            let at = at.and_then(|(hour, minute)| NaiveTime::from_hms_opt(hour, minute, 0));
+            first_occurrence(&every.parse().unwrap(), due, at, now)
+                .unwrap()
+                .format("%Y-%m-%d %H:%M:%S")
+                .to_string()
+        };
+
+        assert_eq!(first("weekday", None, Some((9, 30))), "2026-10-19 09:30:00");
+        assert_eq!(first("daily", None, None), "2026-10-18 09:00:00");
+        assert_eq!(first("daily", None, Some((18, 0))), "2026-10-17 18:00:00");
+        assert_eq!(first("3h", None, None), "2026-10-17 18:30:00");
+        assert_eq!(
+            first("mon", Some("2026-10-20 08:00"), None),
+            "2026-10-26 08:00:00"
+        );
+        assert_eq!(
+            first("daily", Some("2026-10-20 08:00"), Some((7, 0))),
+            "2026-10-20 07:00:00"
+        );
     }
 }
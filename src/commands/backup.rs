@@ -0,0 +1,73 @@
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Local};
+use memo::app;
+use memo::data;
+use memo::style;
+use std::fs;
+
+/// Lists the rotating backups of the data file with their date and size, most recent first.
+pub fn backup_ls(app_config: &app::AppConfig) -> Result<()> {
+    let file_path = app_config.data_file_path();
+    let backups = data::rotated_backups(&file_path);
+    if backups.is_empty() {
+        println!(
+            "{}",
+            style::str("There are no backups", style::Options::Muted)
+        );
+        return Ok(());
+    }
+    for n in backups {
+        let metadata = fs::metadata(data::rotated_backup_path(&file_path, n))?;
+        let modified: DateTime<Local> = metadata.modified()?.into();
+        let details = format!(
+            "{}: {}, {} bytes",
+            n,
+            style::date_time(modified.naive_local()),
+            metadata.len()
+        );
+        println!("{}", style::str(&details, style::Options::Muted));
+    }
+    Ok(())
+}
+
+/// Replaces the data file with one of its backups.
+/// The current content is backed up first, so a restore can be undone by restoring backup 1.
+pub fn backup_restore(app_config: &app::AppConfig, n: usize) -> Result<()> {
+    let file_path = app_config.data_file_path();
+    let backup_path = data::rotated_backup_path(&file_path, n);
+    if !backup_path.exists() {
+        return Err(anyhow!("Backup {} not found", n));
+    }
+    let content = fs::read(&backup_path)
+        .with_context(|| format!("Could not read backup '{}'", backup_path.display()))?;
+    data::rotate_backups(&file_path, app_config.backups)?;
+    data::write_bytes(&file_path, &content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_backup_restore() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("memo.txt");
+        let app_config = app::AppConfig::from_path("memo", &file_path);
+        fs::write(&file_path, "new\n").unwrap();
+        fs::write(data::rotated_backup_path(&file_path, 1), "previous\n").unwrap();
+        fs::write(data::rotated_backup_path(&file_path, 2), "old\n").unwrap();
+
+        backup_restore(&app_config, 2).unwrap();
+        let read = |path: PathBuf| fs::read_to_string(path).unwrap();
+        assert_eq!(read(file_path.clone()), "old\n");
+        assert_eq!(read(data::rotated_backup_path(&file_path, 1)), "new\n");
+        assert_eq!(read(data::rotated_backup_path(&file_path, 2)), "previous\n");
+
+        assert!(backup_restore(&app_config, 7)
+            .unwrap_err()
+            .to_string()
+            .contains("not found"));
+    }
+}
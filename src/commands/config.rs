@@ -0,0 +1,54 @@
+use anyhow::{anyhow, Result};
+use memo::config;
+use memo::style;
+use std::path::Path;
+
+/// Prints the value of a setting in the configuration file.
+pub fn config_get(path: &Path, key: &str) -> Result<()> {
+    let value = config::get(path, key)?.ok_or_else(|| anyhow!("Key '{}' is not set", key))?;
+    println!("{}", value);
+    Ok(())
+}
+
+/// Changes a setting in the configuration file, keeping the rest of the file as it is.
+pub fn config_set(path: &Path, key: &str, value: &str) -> Result<()> {
+    config::set(path, key, value)
+}
+
+/// Removes a setting from the configuration file.
+pub fn config_unset(path: &Path, key: &str) -> Result<()> {
+    config::unset(path, key)
+}
+
+/// Lists every setting in the configuration file.
+pub fn config_list(path: &Path) -> Result<()> {
+    let values = config::list(path)?;
+    if values.is_empty() {
+        let message = format!("No settings in '{}'", path.display());
+        println!("{}", style::str(&message, style::Options::Muted));
+    }
+    for (key, value) in values {
+        println!("{} = {}", style::str(&key, style::Options::Muted), value);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_commands() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+
+        assert!(config_list(&path).is_ok());
+        assert!(config_get(&path, "theme").is_err());
+        assert!(config_set(&path, "theme", "mono").is_ok());
+        assert!(config_get(&path, "theme").is_ok());
+        assert!(config_list(&path).is_ok());
+        assert!(config_set(&path, "display", "table").is_err());
+        assert!(config_unset(&path, "theme").is_ok());
+        assert!(config_unset(&path, "theme").is_err());
+    }
+}
@@ -0,0 +1,174 @@
+use super::migrate::load_memo_data;
+use anyhow::Result;
+use chrono::Local;
+use memo::app::{self, Backend};
+use memo::lock;
+use memo::models::Content;
+use memo::notify::{self, Fired, Notifier};
+use memo::style;
+use std::collections::HashMap;
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+/// Returns the time the data file was last changed, None if it cannot be read
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+/// Loads the memos of the data file, holding its lock only while reading it
+fn load_memos(
+    app_config: &app::AppConfig,
+    backend: Backend,
+    lock_timeout: Duration,
+) -> Result<HashMap<u32, Content>> {
+    let _lock = lock::Lock::acquire(&app_config.data_file_path(), lock_timeout)?;
+    Ok(load_memo_data(app_config, backend)?.contents)
+}
+
+/// Notifies the memos that are due and were not notified yet, and records them in the
+/// firing state. A notifier that fails is reported, and the memo is not notified again.
+/// Returns the number of memos notified.
+fn fire(fired: &mut Fired, memos: &HashMap<u32, Content>, notifier: &Notifier) -> usize {
+    let now = Local::now().naive_local();
+    let reminders = fired.pending(memos, now);
+    for reminder in &reminders {
+        if let Err(err) = notifier.notify(reminder) {
+            eprintln!(
+                "{}: {}",
+                style::str(
+                    &format!("Could not notify memo {}", reminder.id),
+                    style::Options::Muted
+                ),
+                style::str(&err.to_string(), style::Options::Error)
+            );
+        }
+        fired.mark(reminder);
+    }
+    fired.checked(memos, now);
+    reminders.len()
+}
+
+/// Watches the data file and notifies its memos when they come due, until the process is
+/// stopped. The data file is read again when it changes, checking it every `interval`, and
+/// the daemon wakes up when the next memo comes due. Memos that came due while it was not
+/// running are notified when it starts. With `once`, the due memos are notified and it returns.
+pub fn daemon(
+    app_config: &app::AppConfig,
+    backend: Backend,
+    notifier: &Notifier,
+    interval: Duration,
+    lock_timeout: Duration,
+    once: bool,
+) -> Result<()> {
+    let path = app_config.data_file_path();
+    let mut fired = Fired::load(&path)?;
+    let mut memos = load_memos(app_config, backend, lock_timeout)?;
+    let mut last_modified = modified(&path);
+    if !once {
+        eprintln!(
+            "{}",
+            style::str(
+                &format!("Watching '{}', notifying with {}", path.display(), notifier),
+                style::Options::Muted
+            )
+        );
+    }
+
+    let mut reloaded = true;
+    loop {
+        let notified = fire(&mut fired, &memos, notifier);
+        if once || notified > 0 || reloaded {
+            fired.save()?;
+        }
+        if once {
+            return Ok(());
+        }
+
+        let now = Local::now().naive_local();
+        let wait = notify::next_due(&memos, now)
+            .and_then(|due| (due - now).to_std().ok())
+            .map_or(interval, |until_due| until_due.min(interval));
+        thread::sleep(wait);
+
+        reloaded = false;
+        // A data file that cannot be read, e.g. while it is locked, is read at the next check
+        if modified(&path) != last_modified {
+            match load_memos(app_config, backend, lock_timeout) {
+                Ok(loaded) => {
+                    memos = loaded;
+                    last_modified = modified(&path);
+                    reloaded = true;
+                }
+                Err(err) => eprintln!(
+                    "{}: {}",
+                    style::str("Could not read data file", style::Options::Muted),
+                    style::str(&err.to_string(), style::Options::Error)
+                ),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::{add, init};
+    use memo::dates;
+    use memo::notify::fired_path;
+
+    #[test]
+    fn test_daemon_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let app_config = app::AppConfig::from_path("memo", &dir.path().join("memo.txt"));
+        init(&app_config).unwrap();
+        let mut memo_data = memo::models::MemoData::new();
+        memo::data::DataFile::load(&mut memo_data, &app_config).unwrap();
+        let yesterday = dates::parse("yesterday").unwrap();
+        let tomorrow = dates::parse("tomorrow").unwrap();
+        add(
+            &mut memo_data,
+            &app_config,
+            "overdue".to_string(),
+            Some(yesterday),
+            vec![],
+            None,
+            None,
+        )
+        .unwrap();
+        add(
+            &mut memo_data,
+            &app_config,
+            "later".to_string(),
+            Some(tomorrow),
+            vec![],
+            None,
+            None,
+        )
+        .unwrap();
+
+        let log = dir.path().join("memo.log");
+        let notifier = Notifier::Log(log.clone());
+        let run = || {
+            daemon(
+                &app_config,
+                Backend::Text,
+                &notifier,
+                Duration::from_secs(1),
+                lock::DEFAULT_TIMEOUT,
+                true,
+            )
+        };
+        run().unwrap();
+        let data = std::fs::read_to_string(&log).unwrap();
+        assert_eq!(data.lines().count(), 1);
+        assert!(data.contains("Memo 1 was due on"));
+        assert!(fired_path(&app_config.data_file_path()).exists());
+
+        // The firing state is kept across runs, so nothing is notified twice
+        run().unwrap();
+        assert_eq!(std::fs::read_to_string(&log).unwrap(), data);
+    }
+}
@@ -0,0 +1,65 @@
+use anyhow::{anyhow, Result};
+use memo::app::{self, Backend};
+use memo::data;
+use memo::doctor;
+use memo::style;
+
+/// Lists the lines of the data file that cannot be read or whose id is used again.
+/// With `fix`, renumbers them or moves them to the quarantine next to the data file, as the
+/// duplicate id policy says. Returns the number of problems found.
+pub fn doctor(app_config: &app::AppConfig, backend: Backend, fix: bool) -> Result<usize> {
+    if backend == Backend::Sqlite {
+        return Err(anyhow!(
+            "'doctor' checks text data files, the SQLite database keeps its memos consistent"
+        ));
+    }
+    let file_path = app_config.data_file_path();
+    let problems = if fix {
+        doctor::fix(&file_path, app_config.backups, app_config.duplicates)?
+    } else {
+        doctor::diagnose(&data::read_file(&file_path)?, app_config.duplicates)?
+    };
+
+    if problems.is_empty() {
+        println!("No problems found in '{}'", file_path.display());
+        return Ok(0);
+    }
+    for problem in &problems {
+        let number = format!("line {}:", problem.number);
+        println!(
+            "{} {}",
+            style::str(&number, style::Options::Muted),
+            problem.message
+        );
+        println!("    {}", style::str(&problem.line, style::Options::Muted));
+    }
+
+    let quarantine = doctor::quarantine_path(&file_path);
+    let renumbered = problems
+        .iter()
+        .filter(|problem| problem.replacement.is_some())
+        .count();
+    let quarantined = problems.len() - renumbered;
+    let message = if fix {
+        let mut fixes = Vec::new();
+        if quarantined > 0 {
+            fixes.push(format!(
+                "moved {} lines to '{}'",
+                quarantined,
+                quarantine.display()
+            ));
+        }
+        if renumbered > 0 {
+            fixes.push(format!("renumbered {} lines", renumbered));
+        }
+        format!("Fixed the data file: {}", fixes.join(", "))
+    } else {
+        format!(
+            "Found {} problems, run 'memo doctor --fix' to repair them. Lines that are not renumbered are moved to '{}'",
+            problems.len(),
+            quarantine.display()
+        )
+    };
+    eprintln!("{}", style::str(&message, style::Options::Title));
+    Ok(problems.len())
+}
@@ -0,0 +1,127 @@
+use anyhow::{anyhow, Result};
+use chrono::Local;
+use memo::app;
+use memo::data;
+use memo::history;
+use memo::style;
+
+/// Marks memos as done.
+/// A recurring memo stays pending and is due at its next occurrence instead.
+pub fn done(d: &mut impl data::DataFile, app_config: &app::AppConfig, id: Vec<u32>) -> Result<()> {
+    set_done(d, app_config, id, true)
+}
+
+/// Marks memos as not done.
+pub fn undone(
+    d: &mut impl data::DataFile,
+    app_config: &app::AppConfig,
+    id: Vec<u32>,
+) -> Result<()> {
+    set_done(d, app_config, id, false)
+}
+
+fn set_done(
+    d: &mut impl data::DataFile,
+    app_config: &app::AppConfig,
+    id: Vec<u32>,
+    done: bool,
+) -> Result<()> {
+    let mut ids_not_found = String::new();
+    let now = Local::now().naive_local();
+    let mut step = history::Step::new(if done { "done" } else { "undone" });
+    for i in id {
+        let before = d.get(i).cloned();
+        let next = before
+            .as_ref()
+            .filter(|_| done)
+            .and_then(|c| c.next_occurrence(now));
+        let result = match next {
+            Some(next) => d.set_due(i, Some(next)).map(|_| {
+                println!("Memo {} is next due on {}", i, style::date_time(next));
+            }),
+            None => d.set_done(i, done),
+        };
+        match result {
+            Ok(_) => step.push(i, before.as_ref(), d.get(i)),
+            Err(e) => ids_not_found.push_str(&format!("{}: {}\n", i, e)),
+        }
+    }
+
+    if !ids_not_found.is_empty() {
+        return Err(anyhow!("\n{}", ids_not_found));
+    }
+
+    d.save(app_config)?;
+    history::record(&app_config.data_file_path(), step)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use memo::models;
+
+    #[test]
+    fn test_done_and_undone() {
+        let mut app_config = app::AppConfig::new("memo", "memo.txt");
+        let dir = tempfile::tempdir().unwrap();
+        app_config.data_dir = dir.path().to_path_buf();
+
+        // Create file
+        std::fs::File::create(app_config.data_file_path()).unwrap();
+
+        let mut memo_data = models::MemoData::new();
+        data::DataFile::add(&mut memo_data, 1, "test").unwrap();
+        data::DataFile::add(&mut memo_data, 2, "test").unwrap();
+
+        assert!(done(&mut memo_data, &app_config, vec![1, 2]).is_ok());
+        assert!(memo_data.get(1).unwrap().done.is_some());
+        assert!(data::read_file(&app_config.data_file_path())
+            .unwrap()
+            .contains("[done:"));
+
+        assert!(undone(&mut memo_data, &app_config, vec![2]).is_ok());
+        assert!(memo_data.get(1).unwrap().done.is_some());
+        assert!(memo_data.get(2).unwrap().done.is_none());
+    }
+
+    #[test]
+    fn test_done_recurring() {
+        let mut app_config = app::AppConfig::new("memo", "memo.txt");
+        let dir = tempfile::tempdir().unwrap();
+        app_config.data_dir = dir.path().to_path_buf();
+        std::fs::File::create(app_config.data_file_path()).unwrap();
+
+        let mut memo_data = models::MemoData::new();
+        let due = Local::now().naive_local() + chrono::Duration::hours(1);
+        data::DataFile::add(&mut memo_data, 1, "standup").unwrap();
+        data::DataFile::set_due(&mut memo_data, 1, Some(due)).unwrap();
+        data::DataFile::set_recurrence(
+            &mut memo_data,
+            1,
+            Some(std::str::FromStr::from_str("daily").unwrap()),
+        )
+        .unwrap();
+
+        assert!(done(&mut memo_data, &app_config, vec![1]).is_ok());
+        let content = memo_data.get(1).unwrap();
+        assert!(content.done.is_none());
+        assert_eq!(content.due, Some(due + chrono::Duration::days(1)));
+    }
+
+    #[test]
+    fn test_done_invalid() {
+        let mut app_config = app::AppConfig::new("memo", "memo.txt");
+        let dir = tempfile::tempdir().unwrap();
+        app_config.data_dir = dir.path().to_path_buf();
+
+        // Create file
+        std::fs::File::create(app_config.data_file_path()).unwrap();
+
+        let mut memo_data = models::MemoData::new();
+        data::DataFile::add(&mut memo_data, 1, "test").unwrap();
+
+        assert!(done(&mut memo_data, &app_config, vec![2]).is_err());
+        assert!(memo_data.get(1).unwrap().done.is_none());
+    }
+}
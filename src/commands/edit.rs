@@ -0,0 +1,238 @@
+use anyhow::{anyhow, Context, Result};
+use memo::app;
+use memo::data;
+use memo::history;
+use memo::lock;
+use memo::models;
+use std::io::Write;
+use std::process;
+
+/// Editor used when neither `$VISUAL` nor `$EDITOR` is set
+const DEFAULT_EDITOR: &str = "vi";
+
+/// Replaces the text of a memo, keeping its id and creation time.
+/// Without a new text the current one is opened in the editor of the user.
+/// Inline tags in the new text are added to the tags of the memo.
+pub fn edit(
+    d: &mut impl data::DataFile,
+    app_config: &app::AppConfig,
+    lock: &mut lock::Lock,
+    id: u32,
+    text: Option<String>,
+) -> Result<()> {
+    edit_with(d, app_config, lock, id, text, &editor())
+}
+
+/// Edits a memo as `edit` does, with the given editor command.
+/// The data file is unlocked while the editor runs, so other memo commands are not blocked,
+/// and read again afterwards. The edit is discarded if the memo was changed in the meantime.
+fn edit_with(
+    d: &mut impl data::DataFile,
+    app_config: &app::AppConfig,
+    lock: &mut lock::Lock,
+    id: u32,
+    text: Option<String>,
+    editor: &str,
+) -> Result<()> {
+    let mut content = d
+        .get(id)
+        .cloned()
+        .ok_or_else(|| anyhow!("Id '{}' not found", id))?;
+    let new_text = match text {
+        Some(text) => text,
+        None => {
+            let edited = lock.unlocked(|| edit_in_editor(&content.text, editor))??;
+            d.load(app_config)?;
+            content = d
+                .get(id)
+                .filter(|current| format!("{:#}", current) == format!("{:#}", content))
+                .cloned()
+                .ok_or_else(|| {
+                    anyhow!(
+                        "Memo {} was changed while it was edited, the edit was discarded",
+                        id
+                    )
+                })?;
+            edited
+        }
+    };
+
+    let (new_text, inline_tags) = models::split_tags(&new_text);
+    if new_text.is_empty() {
+        return Err(anyhow!("The text of a memo cannot be empty"));
+    }
+    let mut tags = content.tags.clone();
+    for tag in inline_tags {
+        if !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+    if new_text == content.text && tags == content.tags {
+        return Ok(());
+    }
+
+    d.update(id, &new_text)?;
+    d.set_tags(id, tags)?;
+    d.save(app_config)?;
+
+    let mut step = history::Step::new("edit");
+    step.push(id, Some(&content), d.get(id));
+    history::record(&app_config.data_file_path(), step)?;
+    Ok(())
+}
+
+/// Returns the editor command of the user, from `$VISUAL` or `$EDITOR`
+fn editor() -> String {
+    ["VISUAL", "EDITOR"]
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|editor| !editor.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_EDITOR.to_string())
+}
+
+/// Opens the text in the editor on a temporary file and returns the saved text.
+/// The editor command may include arguments, e.g. `code --wait`.
+fn edit_in_editor(text: &str, editor: &str) -> Result<String> {
+    let mut file = tempfile::Builder::new()
+        .prefix("memo-")
+        .suffix(".txt")
+        .tempfile()?;
+    writeln!(file, "{}", text)?;
+    file.flush()?;
+
+    let mut parts = editor.split_whitespace();
+    let program = parts.next().ok_or_else(|| anyhow!("No editor set"))?;
+    let status = process::Command::new(program)
+        .args(parts)
+        .arg(file.path())
+        .status()
+        .with_context(|| format!("Could not start editor '{}'", editor))?;
+    if !status.success() {
+        return Err(anyhow!("Editor '{}' exited with {}", editor, status));
+    }
+
+    std::fs::read_to_string(file.path()).context("Could not read the edited text")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup() -> (
+        tempfile::TempDir,
+        app::AppConfig,
+        lock::Lock,
+        models::MemoData,
+    ) {
+        let mut app_config = app::AppConfig::new("memo", "memo.txt");
+        let dir = tempfile::tempdir().unwrap();
+        app_config.data_dir = dir.path().to_path_buf();
+
+        // Create file
+        std::fs::File::create(app_config.data_file_path()).unwrap();
+
+        let lock =
+            lock::Lock::acquire(&app_config.data_file_path(), lock::DEFAULT_TIMEOUT).unwrap();
+        let mut memo_data = models::MemoData::new();
+        data::DataFile::add(&mut memo_data, 1, "tset").unwrap();
+        (dir, app_config, lock, memo_data)
+    }
+
+    #[test]
+    fn test_edit() {
+        let (_dir, app_config, mut lock, mut memo_data) = setup();
+        let date_time = memo_data.get(1).unwrap().date_time;
+
+        let text = Some("test +work".to_string());
+        assert!(edit(&mut memo_data, &app_config, &mut lock, 1, text).is_ok());
+        let content = memo_data.get(1).unwrap();
+        assert_eq!(content.text, "test");
+        assert_eq!(content.tags, vec!["work"]);
+        assert_eq!(content.date_time, date_time);
+        assert!(content.updated_at.is_some());
+        assert!(data::read_file(&app_config.data_file_path())
+            .unwrap()
+            .contains("tags:work updated:"));
+    }
+
+    #[test]
+    fn test_edit_invalid() {
+        let (_dir, app_config, mut lock, mut memo_data) = setup();
+        assert!(edit(
+            &mut memo_data,
+            &app_config,
+            &mut lock,
+            2,
+            Some("test".to_string())
+        )
+        .is_err());
+        assert!(edit(
+            &mut memo_data,
+            &app_config,
+            &mut lock,
+            1,
+            Some(" ".to_string())
+        )
+        .is_err());
+        assert_eq!(memo_data.get(1).unwrap().text, "tset");
+    }
+
+    #[test]
+    fn test_edit_unchanged() {
+        let (_dir, app_config, mut lock, mut memo_data) = setup();
+        assert!(edit(
+            &mut memo_data,
+            &app_config,
+            &mut lock,
+            1,
+            Some("tset".to_string())
+        )
+        .is_ok());
+        assert!(memo_data.get(1).unwrap().updated_at.is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_edit_in_editor() {
+        assert_eq!(
+            edit_in_editor("tset", "sed -i s/tset/test/").unwrap(),
+            "test\n"
+        );
+        assert!(edit_in_editor("tset", "false").is_err());
+        assert!(edit_in_editor("tset", "memo-missing-editor").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_edit_unlocked() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let (dir, app_config, mut lock, mut memo_data) = setup();
+        data::DataFile::save(&mut memo_data, &app_config).unwrap();
+        let path = app_config.data_file_path();
+        let lock_path = lock::lock_path(&path);
+
+        // The editor runs with the data file unlocked
+        let editor = dir.path().join("editor.sh");
+        let script = format!(
+            "#!/bin/sh\ntest ! -e '{}' || exit 1\nsed -i s/tset/test/ \"$1\"\n",
+            lock_path.display()
+        );
+        std::fs::write(&editor, script).unwrap();
+        std::fs::set_permissions(&editor, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let editor = editor.display().to_string();
+        edit_with(&mut memo_data, &app_config, &mut lock, 1, None, &editor).unwrap();
+        assert_eq!(memo_data.get(1).unwrap().text, "test");
+        assert!(lock_path.exists());
+
+        // A memo changed by another process while it was edited is left as it is
+        let script = format!(
+            "#!/bin/sh\nsed -i s/test/tested/ \"$1\"\nsed -i s/test/changed/ '{}'\n",
+            path.display()
+        );
+        std::fs::write(&editor, script).unwrap();
+        assert!(edit_with(&mut memo_data, &app_config, &mut lock, 1, None, &editor).is_err());
+        assert_eq!(memo_data.get(1).unwrap().text, "changed");
+        assert!(data::read_file(&path).unwrap().contains("] changed"));
+    }
+}
@@ -1,5 +1,9 @@
 use anyhow::{anyhow, Context, Result};
 use memo::app;
+use std::path::Path;
+
+/// Pattern of the files kept next to a project data file: backups, history, trash and lock
+const SIDECAR_PATTERN: &str = "/.memo.*";
 
 pub fn init(app_config: &app::AppConfig) -> Result<()> {
     // Check if file exist
@@ -28,7 +32,36 @@ pub fn init(app_config: &app::AppConfig) -> Result<()> {
     Ok(())
 }
 
+/// Creates a project data file, see `init`, and adds the files kept next to it to the
+/// `.gitignore` of its directory, so only the data file is committed.
+pub fn init_local(app_config: &app::AppConfig) -> Result<()> {
+    init(app_config)?;
+    ignore_sidecars(&app_config.data_dir.join(".gitignore"))
+}
+
+/// Adds `SIDECAR_PATTERN` to a `.gitignore` file, creating it if needed
+fn ignore_sidecars(gitignore: &Path) -> Result<()> {
+    let mut content = match std::fs::read_to_string(gitignore) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => {
+            return Err(e).with_context(|| format!("Could not read '{}'", gitignore.display()))
+        }
+    };
+    if content.lines().any(|line| line.trim() == SIDECAR_PATTERN) {
+        return Ok(());
+    }
+    if !content.is_empty() && !content.ends_with('\n') {
+        content.push('\n');
+    }
+    content.push_str(SIDECAR_PATTERN);
+    content.push('\n');
+    std::fs::write(gitignore, content)
+        .with_context(|| format!("Could not write '{}'", gitignore.display()))
+}
+
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
 mod tests {
 
     use super::*;
@@ -42,6 +75,28 @@ mod tests {
         assert_eq!(init(&app_config).is_ok(), true);
     }
 
+    #[test]
+    fn test_init_local() {
+        let dir = tempfile::tempdir().unwrap();
+        let app_config = app::AppConfig::from_path("memo", &dir.path().join(app::LOCAL_FILE));
+        let gitignore = dir.path().join(".gitignore");
+        std::fs::write(&gitignore, "target").unwrap();
+
+        assert!(init_local(&app_config).is_ok());
+        assert!(app_config.data_file_path().exists());
+        assert_eq!(
+            std::fs::read_to_string(&gitignore).unwrap(),
+            "target\n/.memo.*\n"
+        );
+
+        // The pattern is added once
+        ignore_sidecars(&gitignore).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&gitignore).unwrap(),
+            "target\n/.memo.*\n"
+        );
+    }
+
     #[test]
     fn test_init_file_exists() {
         let mut app_config = app::AppConfig::new("memo", "memo.txt");
@@ -1,12 +1,19 @@
 use anyhow::Result;
 use memo::data;
+use memo::output::OutputFormat;
 
-pub fn list(d: &impl data::DataFile, mode: data::DisplayMode) -> Result<()> {
-    d.display(mode)?;
+pub fn list(
+    d: &impl data::DataFile,
+    mode: data::DisplayMode,
+    filter: &data::Filter,
+    format: OutputFormat,
+) -> Result<()> {
+    d.display(mode, filter, format)?;
     Ok(())
 }
 
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
 mod tests {
     use super::*;
     use memo::models;
@@ -14,20 +21,86 @@ mod tests {
     #[test]
     fn test_list() {
         let memo_data = models::MemoData::new();
+        let filter = data::Filter::default();
         assert_eq!(
-            list(&memo_data, data::DisplayMode::GroupByDate).is_ok(),
+            list(
+                &memo_data,
+                data::DisplayMode::GroupByDate,
+                &filter,
+                OutputFormat::Pretty
+            )
+            .is_ok(),
+            true
+        );
+        assert_eq!(
+            list(
+                &memo_data,
+                data::DisplayMode::Sorted,
+                &filter,
+                OutputFormat::Pretty
+            )
+            .is_ok(),
             true
         );
-        assert_eq!(list(&memo_data, data::DisplayMode::Sorted).is_ok(), true);
     }
 
     #[test]
     fn test_list_empty() {
         let memo_data = models::MemoData::new();
+        let filter = data::Filter::default();
+        assert_eq!(
+            list(
+                &memo_data,
+                data::DisplayMode::GroupByDate,
+                &filter,
+                OutputFormat::Pretty
+            )
+            .is_ok(),
+            true
+        );
         assert_eq!(
-            list(&memo_data, data::DisplayMode::GroupByDate).is_ok(),
+            list(
+                &memo_data,
+                data::DisplayMode::Sorted,
+                &filter,
+                OutputFormat::Pretty
+            )
+            .is_ok(),
             true
         );
-        assert_eq!(list(&memo_data, data::DisplayMode::Sorted).is_ok(), true);
+    }
+
+    #[test]
+    fn test_list_done() {
+        let mut memo_data = models::MemoData::new();
+        data::DataFile::add(&mut memo_data, 1, "test").unwrap();
+        data::DataFile::set_done(&mut memo_data, 1, true).unwrap();
+        let filter = data::Filter {
+            status: data::Status::Done,
+            ..Default::default()
+        };
+        assert!(list(
+            &memo_data,
+            data::DisplayMode::GroupByDate,
+            &filter,
+            OutputFormat::Pretty
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn test_list_formats() {
+        let mut memo_data = models::MemoData::new();
+        data::DataFile::add(&mut memo_data, 1, "test").unwrap();
+        let filter = data::Filter::default();
+        for format in [
+            OutputFormat::Plain,
+            OutputFormat::Json,
+            OutputFormat::JsonLines,
+            OutputFormat::Csv,
+            OutputFormat::Tsv,
+        ] {
+            assert!(list(&memo_data, data::DisplayMode::ByPriority, &filter, format).is_ok());
+        }
     }
 }
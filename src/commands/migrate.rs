@@ -0,0 +1,172 @@
+use anyhow::{anyhow, Result};
+use memo::app::{self, Backend};
+use memo::data::{self, DataFile, Format};
+use memo::models;
+use memo::sqlite;
+
+/// Copies the memos to a data file of another backend or format. Returns the number of memos.
+/// Migrating between formats of the text backend converts the data file in place, after keeping
+/// a copy of the original one next to it. Migrating to another backend writes a new data file
+/// and keeps the original untouched.
+pub fn migrate(
+    app_config: &app::AppConfig,
+    from: Backend,
+    to: Backend,
+    format: Format,
+) -> Result<usize> {
+    let mut memo_data = load_memo_data(app_config, from)?;
+    memo_data.check_bad_lines()?;
+    let count = memo_data.contents.len();
+
+    if from == to {
+        if to == Backend::Sqlite || memo_data.format == format {
+            return Err(anyhow!(
+                "Memos are already stored as {}",
+                format_name(to, format)
+            ));
+        }
+        let path = app_config.data_file_path();
+        std::fs::copy(&path, data::backup_path(&path))?;
+        memo_data.format = format;
+        memo_data.save(app_config)?;
+        return Ok(count);
+    }
+
+    // Keep the name of the data file, e.g. notebooks/work.txt becomes notebooks/work.db
+    let target_path = app_config.data_file_path().with_extension(to.extension());
+    let target = app::AppConfig::from_path(app_config.name(), &target_path);
+    if target.data_file_path().exists() {
+        return Err(anyhow!(
+            "File '{}' already exists",
+            target.data_file_path().display()
+        ));
+    }
+
+    std::fs::File::create(target.data_file_path())?;
+    let result = match to {
+        Backend::Text => {
+            memo_data.format = format;
+            memo_data.save(&target)
+        }
+        Backend::Sqlite => sqlite::SqliteData::from_memo_data(memo_data).save(&target),
+    };
+
+    if let Err(e) = result {
+        // Do not leave a half written data file behind
+        let _ = std::fs::remove_file(target.data_file_path());
+        let _ = std::fs::remove_file(data::trash_path(&target.data_file_path()));
+        return Err(e);
+    }
+    Ok(count)
+}
+
+/// Loads the data file of any backend into a `MemoData`
+pub fn load_memo_data(app_config: &app::AppConfig, backend: Backend) -> Result<models::MemoData> {
+    match backend {
+        Backend::Text => {
+            let mut memo_data = models::MemoData::new();
+            memo_data.load(app_config)?;
+            Ok(memo_data)
+        }
+        Backend::Sqlite => {
+            let mut sqlite_data = sqlite::SqliteData::new();
+            sqlite_data.load(app_config)?;
+            Ok(sqlite_data.into_memo_data())
+        }
+    }
+}
+
+/// Returns the name of a backend and format, as accepted by `memo migrate --to`
+pub fn format_name(backend: Backend, format: Format) -> &'static str {
+    match (backend, format) {
+        (Backend::Sqlite, _) => "sqlite",
+        (Backend::Text, Format::Lines) => "text",
+        (Backend::Text, Format::JsonLines) => "jsonl",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LINES: &str = "1: 2001-01-01 01:01:01 [due:2001-01-02T09:00:00 tags:work prio:A uuid:67e55044-10b1-426f-9247-bb680e5fe0c8] one\n3: 2003-03-03 03:03:03 [uuid:a1a2a3a4-b1b2-4c1c-8d1d-e1e2e3e4e5e6] three\n";
+
+    fn app_config(dir: &std::path::Path) -> app::AppConfig {
+        let mut app_config = app::AppConfig::new("memo", Backend::Text.data_file());
+        app_config.data_dir = dir.to_path_buf();
+        std::fs::write(app_config.data_file_path(), LINES).unwrap();
+        app_config
+    }
+
+    #[test]
+    fn test_migrate_text_to_sqlite_and_back() {
+        let dir = tempfile::tempdir().unwrap();
+        let app_config = app_config(dir.path());
+
+        assert_eq!(
+            migrate(&app_config, Backend::Text, Backend::Sqlite, Format::Lines).unwrap(),
+            2
+        );
+
+        let mut sqlite_config = app::AppConfig::new("memo", Backend::Sqlite.data_file());
+        sqlite_config.data_dir = app_config.data_dir.clone();
+        let mut sqlite_data = sqlite::SqliteData::new();
+        sqlite_data.load(&sqlite_config).unwrap();
+        assert_eq!(sqlite_data.sorted_ids(), vec![1, 3]);
+        assert_eq!(format!("{}", sqlite_data), LINES);
+
+        // Migrating back needs the text file to be out of the way
+        assert!(migrate(
+            &sqlite_config,
+            Backend::Sqlite,
+            Backend::Text,
+            Format::Lines
+        )
+        .is_err());
+        std::fs::remove_file(app_config.data_file_path()).unwrap();
+        assert_eq!(
+            migrate(
+                &sqlite_config,
+                Backend::Sqlite,
+                Backend::Text,
+                Format::Lines
+            )
+            .unwrap(),
+            2
+        );
+        assert_eq!(
+            data::read_file(&app_config.data_file_path()).unwrap(),
+            LINES
+        );
+    }
+
+    #[test]
+    fn test_migrate_in_place() {
+        let dir = tempfile::tempdir().unwrap();
+        let app_config = app_config(dir.path());
+        let path = app_config.data_file_path();
+
+        assert_eq!(
+            migrate(&app_config, Backend::Text, Backend::Text, Format::JsonLines).unwrap(),
+            2
+        );
+        assert_eq!(data::read_file(&data::backup_path(&path)).unwrap(), LINES);
+        assert!(memo::jsonl::is_jsonl(&data::read_file(&path).unwrap()));
+
+        let mut memo_data = models::MemoData::new();
+        memo_data.load(&app_config).unwrap();
+        assert_eq!(memo_data.format, Format::JsonLines);
+        assert_eq!(format!("{}", memo_data), LINES);
+
+        assert!(migrate(&app_config, Backend::Text, Backend::Text, Format::JsonLines).is_err());
+        assert!(migrate(&app_config, Backend::Text, Backend::Text, Format::Lines).is_ok());
+        assert_eq!(data::read_file(&path).unwrap(), LINES);
+    }
+
+    #[test]
+    fn test_migrate_same_backend() {
+        let dir = tempfile::tempdir().unwrap();
+        let app_config = app_config(dir.path());
+        assert!(migrate(&app_config, Backend::Text, Backend::Text, Format::Lines).is_err());
+    }
+}
@@ -1,10 +1,43 @@
 mod add;
+mod backup;
+mod config;
+mod daemon;
+mod doctor;
+mod done;
+mod edit;
 mod init;
 mod list;
+mod migrate;
+mod notebooks;
+mod prio;
 mod remove;
+mod renumber;
+mod search;
+mod show;
+mod snooze;
+mod tags;
+mod trash;
+mod undo;
 
 pub use add::add;
-pub use init::init;
+pub use backup::{backup_ls, backup_restore};
+pub use config::{config_get, config_list, config_set, config_unset};
+pub use daemon::daemon;
+pub use doctor::doctor;
+pub use done::{done, undone};
+pub use edit::edit;
+pub use init::{init, init_local};
 pub use list::list;
+pub use migrate::{format_name, migrate};
+pub use notebooks::{
+    list_all_notebooks, notebooks_create, notebooks_delete, notebooks_ls, notebooks_rename,
+};
+pub use prio::prio;
 pub use remove::remove;
-
+pub use renumber::{mv, renumber};
+pub use search::search;
+pub use show::show;
+pub use snooze::{snooze, DEFAULT_SNOOZE_MINUTES};
+pub use tags::tags;
+pub use trash::{purge_trash, trash_empty, trash_ls, trash_restore};
+pub use undo::{redo, undo};
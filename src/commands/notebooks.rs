@@ -0,0 +1,245 @@
+use super::init::init;
+use super::migrate::load_memo_data;
+use anyhow::{anyhow, Result};
+use memo::app::{self, Backend, DEFAULT_NOTEBOOK};
+use memo::config;
+use memo::data;
+use memo::doctor;
+use memo::history;
+use memo::models;
+use memo::notify;
+use memo::output::OutputFormat;
+use memo::style;
+use std::path::{Path, PathBuf};
+
+/// Returns the data file of a notebook followed by the files kept next to it,
+/// with the rotating backups numbered `backups`
+fn notebook_files(path: &Path, backups: &[usize]) -> Vec<PathBuf> {
+    let mut files = vec![
+        path.to_path_buf(),
+        data::backup_path(path),
+        data::trash_path(path),
+        history::history_path(path),
+        doctor::quarantine_path(path),
+        notify::fired_path(path),
+    ];
+    files.extend(backups.iter().map(|n| data::rotated_backup_path(path, *n)));
+    files
+}
+
+/// Returns an error for the default notebook, which cannot be created, renamed or deleted
+fn check_not_default(notebook: &str) -> Result<()> {
+    if notebook == DEFAULT_NOTEBOOK {
+        return Err(anyhow!(
+            "The '{}' notebook is the main data file and cannot be changed",
+            DEFAULT_NOTEBOOK
+        ));
+    }
+    Ok(())
+}
+
+/// Returns the app config of an existing notebook
+fn existing_notebook(app_config: &app::AppConfig, notebook: &str) -> Result<app::AppConfig> {
+    let notebook_config = app_config.notebook(notebook)?;
+    if !notebook_config.data_file_path().exists() {
+        return Err(anyhow!("Notebook '{}' not found", notebook));
+    }
+    Ok(notebook_config)
+}
+
+/// Points the default notebook of the configuration file to the new name of a notebook,
+/// or removes it if the notebook was deleted
+fn update_config(config_path: &Path, notebook: &str, new_name: Option<&str>) -> Result<()> {
+    if config::get(config_path, "notebook")?.as_deref() != Some(notebook) {
+        return Ok(());
+    }
+    match new_name {
+        Some(new_name) => config::set(config_path, "notebook", new_name),
+        None => config::unset(config_path, "notebook"),
+    }
+}
+
+/// Lists the notebooks with their number of memos, marking the current one.
+pub fn notebooks_ls(app_config: &app::AppConfig, backend: Backend, current: &str) -> Result<()> {
+    for notebook in app_config.notebooks()? {
+        let notebook_config = app_config.notebook(&notebook)?;
+        let count = if notebook_config.data_file_path().exists() {
+            load_memo_data(&notebook_config, backend)?.contents.len()
+        } else {
+            0
+        };
+        let marker = if notebook == current { "*" } else { " " };
+        println!(
+            "{} {} {}",
+            marker,
+            style::str(&notebook, style::Options::Title),
+            style::str(&format!("{} memos", count), style::Options::Muted)
+        );
+    }
+    Ok(())
+}
+
+/// Creates an empty notebook.
+pub fn notebooks_create(app_config: &app::AppConfig, notebook: &str) -> Result<()> {
+    check_not_default(notebook)?;
+    let notebook_config = app_config.notebook(notebook)?;
+    if notebook_config.data_file_path().exists() {
+        return Err(anyhow!("Notebook '{}' already exists", notebook));
+    }
+    init(&notebook_config)?;
+    println!("Created notebook '{}'", notebook);
+    Ok(())
+}
+
+/// Renames a notebook, moving its trash, history, quarantine and backups along with it.
+/// The configuration file follows the rename if the notebook is its default one.
+pub fn notebooks_rename(
+    app_config: &app::AppConfig,
+    config_path: &Path,
+    notebook: &str,
+    new_name: &str,
+) -> Result<()> {
+    check_not_default(notebook)?;
+    check_not_default(new_name)?;
+    let from = existing_notebook(app_config, notebook)?.data_file_path();
+    let to = app_config.notebook(new_name)?.data_file_path();
+    if to.exists() {
+        return Err(anyhow!("Notebook '{}' already exists", new_name));
+    }
+
+    let backups = data::rotated_backups(&from);
+    for (from, to) in notebook_files(&from, &backups)
+        .iter()
+        .zip(notebook_files(&to, &backups))
+    {
+        if from.exists() {
+            std::fs::rename(from, to)?;
+        }
+    }
+    update_config(config_path, notebook, Some(new_name))?;
+    println!("Renamed notebook '{}' to '{}'", notebook, new_name);
+    Ok(())
+}
+
+/// Deletes a notebook with its trash, history, quarantine and backups.
+/// A notebook that still has memos is only deleted when forced.
+/// If it is the default notebook of the configuration file, it is removed from there.
+pub fn notebooks_delete(
+    app_config: &app::AppConfig,
+    config_path: &Path,
+    backend: Backend,
+    notebook: &str,
+    force: bool,
+) -> Result<()> {
+    check_not_default(notebook)?;
+    let notebook_config = existing_notebook(app_config, notebook)?;
+    let count = load_memo_data(&notebook_config, backend)?.contents.len();
+    if count > 0 && !force {
+        return Err(anyhow!(
+            "Notebook '{}' has {} memos, use --force to delete it anyway",
+            notebook,
+            count
+        ));
+    }
+
+    let path = notebook_config.data_file_path();
+    for path in notebook_files(&path, &data::rotated_backups(&path)) {
+        if path.exists() {
+            std::fs::remove_file(path)?;
+        }
+    }
+    update_config(config_path, notebook, None)?;
+    println!("Deleted notebook '{}'", notebook);
+    Ok(())
+}
+
+/// Lists the memos of every notebook together, each labelled with its notebook.
+/// Notebooks whose data file has not been created yet are skipped.
+pub fn list_all_notebooks(
+    app_config: &app::AppConfig,
+    backend: Backend,
+    mode: data::DisplayMode,
+    filter: &data::Filter,
+    format: OutputFormat,
+) -> Result<()> {
+    let mut notebooks = Vec::new();
+    for notebook in app_config.notebooks()? {
+        let notebook_config = app_config.notebook(&notebook)?;
+        if notebook_config.data_file_path().exists() {
+            notebooks.push((notebook, load_memo_data(&notebook_config, backend)?));
+        }
+    }
+    let combined = models::MemoData::combine(&notebooks);
+    data::DataFile::display(&combined, mode, filter, format)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use memo::data::DataFile;
+
+    fn setup() -> (tempfile::TempDir, app::AppConfig, PathBuf) {
+        let dir = tempfile::tempdir().unwrap();
+        let app_config = app::AppConfig::from_path("memo", &dir.path().join("memo.txt"));
+        init(&app_config).unwrap();
+        let config_path = dir.path().join("config.toml");
+        (dir, app_config, config_path)
+    }
+
+    #[test]
+    fn test_notebooks_create_rename_delete() {
+        let (_dir, app_config, config_path) = setup();
+        config::set(&config_path, "notebook", "work").unwrap();
+        assert!(notebooks_create(&app_config, "work").is_ok());
+        assert!(notebooks_create(&app_config, "work").is_err());
+        assert!(notebooks_create(&app_config, DEFAULT_NOTEBOOK).is_err());
+        assert_eq!(app_config.notebooks().unwrap(), vec!["default", "work"]);
+
+        let work = app_config.notebook("work").unwrap();
+        let mut memo_data = load_memo_data(&work, Backend::Text).unwrap();
+        memo_data.add(1, "deploy").unwrap();
+        memo_data.remove(1).unwrap();
+        memo_data.add(2, "review").unwrap();
+        memo_data.save(&work).unwrap();
+
+        assert!(notebooks_rename(&app_config, &config_path, "work", "job").is_ok());
+        assert!(notebooks_rename(&app_config, &config_path, "work", "job").is_err());
+        assert_eq!(
+            config::get(&config_path, "notebook").unwrap().as_deref(),
+            Some("job")
+        );
+        assert_eq!(app_config.notebooks().unwrap(), vec!["default", "job"]);
+        let job = app_config.notebook("job").unwrap();
+        let memo_data = load_memo_data(&job, Backend::Text).unwrap();
+        assert_eq!(memo_data.get(2).unwrap().text, "review");
+        assert_eq!(memo_data.trash().len(), 1);
+        assert!(!data::trash_path(&work.data_file_path()).exists());
+        assert!(data::rotated_backup_path(&job.data_file_path(), 1).exists());
+        assert!(data::rotated_backups(&work.data_file_path()).is_empty());
+
+        let delete =
+            |force| notebooks_delete(&app_config, &config_path, Backend::Text, "job", force);
+        assert!(delete(false).is_err());
+        assert!(delete(true).is_ok());
+        assert!(!data::trash_path(&job.data_file_path()).exists());
+        assert!(data::rotated_backups(&job.data_file_path()).is_empty());
+        assert_eq!(app_config.notebooks().unwrap(), vec!["default"]);
+        assert_eq!(config::get(&config_path, "notebook").unwrap(), None);
+        assert!(delete(true).is_err());
+    }
+
+    #[test]
+    fn test_list_all_notebooks() {
+        let (_dir, app_config, _config_path) = setup();
+        notebooks_create(&app_config, "home").unwrap();
+        assert!(notebooks_ls(&app_config, Backend::Text, "home").is_ok());
+        assert!(list_all_notebooks(
+            &app_config,
+            Backend::Text,
+            data::DisplayMode::GroupByDate,
+            &data::Filter::default(),
+            OutputFormat::Csv
+        )
+        .is_ok());
+    }
+}
@@ -0,0 +1,70 @@
+use anyhow::{anyhow, Result};
+use memo::app;
+use memo::data;
+use memo::history;
+use memo::models::Priority;
+
+/// Sets or clears the priority of a memo.
+pub fn prio(
+    d: &mut impl data::DataFile,
+    app_config: &app::AppConfig,
+    id: u32,
+    priority: Option<Priority>,
+) -> Result<()> {
+    let before = d
+        .get(id)
+        .cloned()
+        .ok_or_else(|| anyhow!("Id '{}' not found", id))?;
+    d.set_priority(id, priority)?;
+    d.save(app_config)?;
+
+    let mut step = history::Step::new("prio");
+    step.push(id, Some(&before), d.get(id));
+    history::record(&app_config.data_file_path(), step)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use memo::models;
+
+    #[test]
+    fn test_prio() {
+        let mut app_config = app::AppConfig::new("memo", "memo.txt");
+        let dir = tempfile::tempdir().unwrap();
+        app_config.data_dir = dir.path().to_path_buf();
+
+        // Create file
+        std::fs::File::create(app_config.data_file_path()).unwrap();
+
+        let mut memo_data = models::MemoData::new();
+        data::DataFile::add(&mut memo_data, 1, "test").unwrap();
+
+        assert!(prio(&mut memo_data, &app_config, 1, Priority::new('A')).is_ok());
+        assert_eq!(memo_data.get(1).unwrap().priority, Priority::new('A'));
+        assert!(data::read_file(&app_config.data_file_path())
+            .unwrap()
+            .contains(&format!(
+                "[prio:A uuid:{}] test",
+                memo_data.get(1).unwrap().uuid
+            )));
+
+        assert!(prio(&mut memo_data, &app_config, 1, None).is_ok());
+        assert!(memo_data.get(1).unwrap().priority.is_none());
+    }
+
+    #[test]
+    fn test_prio_invalid() {
+        let mut app_config = app::AppConfig::new("memo", "memo.txt");
+        let dir = tempfile::tempdir().unwrap();
+        app_config.data_dir = dir.path().to_path_buf();
+
+        // Create file
+        std::fs::File::create(app_config.data_file_path()).unwrap();
+
+        let mut memo_data = models::MemoData::new();
+
+        assert!(prio(&mut memo_data, &app_config, 1, Priority::new('A')).is_err());
+    }
+}
@@ -1,17 +1,22 @@
 use anyhow::{anyhow, Result};
 use memo::app;
 use memo::data;
+use memo::history;
 
 /// Removes memos from data file.
+/// The removal is recorded in the history as a single step, so it can be undone at once.
 pub fn remove(
     d: &mut impl data::DataFile,
     app_config: &app::AppConfig,
     id: Vec<u32>,
 ) -> Result<()> {
     let mut ids_not_found = String::new();
+    let mut step = history::Step::new("remove");
     for i in id {
-        if let Err(e) = d.remove(i) {
-            ids_not_found.push_str(&format!("{}: {}\n", i, e));
+        let before = d.get(i).cloned();
+        match d.remove(i) {
+            Ok(()) => step.push(i, before.as_ref(), None),
+            Err(e) => ids_not_found.push_str(&format!("{}: {}\n", i, e)),
         }
     }
 
@@ -19,12 +24,16 @@ pub fn remove(
         return Err(anyhow!("\n{}", ids_not_found));
     }
 
-    let lines = format!("{}", d);
-    data::write_file(&app_config.data_file_path(), &lines)?;
+    d.save(app_config)?;
+    history::record(&app_config.data_file_path(), step)?;
     Ok(())
 }
 
 #[cfg(test)]
+#[allow(
+    clippy::bool_assert_comparison,
+    clippy::needless_borrows_for_generic_args
+)]
 mod tests {
     use super::*;
     use memo::models;
@@ -0,0 +1,105 @@
+use anyhow::{anyhow, Result};
+use memo::app;
+use memo::data;
+use memo::history;
+
+/// Gives the memos the ids 1 to N in the order they were created, and prints the ids that
+/// changed. The old ids are recorded in the history, so the change can be undone.
+pub fn renumber(d: &mut impl data::DataFile, app_config: &app::AppConfig) -> Result<()> {
+    let mut ids = d.sorted_ids();
+    ids.sort_by_key(|id| d.get(*id).map(|content| content.date_time));
+    let moves: Vec<(u32, u32)> = ids
+        .into_iter()
+        .zip(1..)
+        .filter(|(from, to)| from != to)
+        .collect();
+    if moves.is_empty() {
+        println!("Ids are already numbered 1 to {}", d.sorted_ids().len());
+        return Ok(());
+    }
+
+    move_ids(d, app_config, "renumber", &moves)?;
+    for (from, to) in moves {
+        println!("{} → {}", from, to);
+    }
+    Ok(())
+}
+
+/// Gives a memo a new id, which must be free.
+pub fn mv(
+    d: &mut impl data::DataFile,
+    app_config: &app::AppConfig,
+    id: u32,
+    new_id: u32,
+) -> Result<()> {
+    if d.get(id).is_none() {
+        return Err(anyhow!("Id '{}' not found", id));
+    }
+    if id == new_id {
+        return Ok(());
+    }
+    move_ids(d, app_config, "move", &[(id, new_id)])?;
+    println!("Moved memo {} to {}", id, new_id);
+    Ok(())
+}
+
+/// Moves the memos to their new ids, saves them and records the moves in the history
+fn move_ids(
+    d: &mut impl data::DataFile,
+    app_config: &app::AppConfig,
+    action: &str,
+    moves: &[(u32, u32)],
+) -> Result<()> {
+    d.renumber(moves)?;
+    d.save(app_config)?;
+
+    let mut step = history::Step::new(action);
+    for (from, to) in moves {
+        step.push_move(*from, *to);
+    }
+    history::record(&app_config.data_file_path(), step)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands;
+    use memo::models;
+
+    #[test]
+    fn test_renumber_and_mv() {
+        let mut app_config = app::AppConfig::new("memo", "memo.txt");
+        let dir = tempfile::tempdir().unwrap();
+        app_config.data_dir = dir.path().to_path_buf();
+        std::fs::File::create(app_config.data_file_path()).unwrap();
+
+        let mut memo_data = models::MemoData::new();
+        for text in ["one", "two", "three"] {
+            commands::add(
+                &mut memo_data,
+                &app_config,
+                text.to_string(),
+                None,
+                vec![],
+                None,
+                None,
+            )
+            .unwrap();
+        }
+        assert!(mv(&mut memo_data, &app_config, 1, 3).is_err());
+        assert!(mv(&mut memo_data, &app_config, 4, 5).is_err());
+        mv(&mut memo_data, &app_config, 1, 100).unwrap();
+        assert_eq!(memo_data.sorted_ids(), vec![2, 3, 100]);
+
+        renumber(&mut memo_data, &app_config).unwrap();
+        assert_eq!(memo_data.get(1).unwrap().text, "one");
+        assert_eq!(memo_data.sorted_ids(), vec![1, 2, 3]);
+
+        let mut loaded = models::MemoData::new();
+        data::DataFile::load(&mut loaded, &app_config).unwrap();
+        assert_eq!(loaded.get(1).unwrap().text, "one");
+
+        commands::undo(&mut memo_data, &app_config).unwrap();
+        assert_eq!(memo_data.get(100).unwrap().text, "one");
+    }
+}
@@ -0,0 +1,32 @@
+use anyhow::Result;
+use memo::data;
+use memo::search::{Query, SearchMode};
+use memo::style;
+
+/// Searches the text of every memo and prints the matches highlighted.
+pub fn search(d: &impl data::DataFile, query: &str, mode: SearchMode, ranked: bool) -> Result<()> {
+    let result = d.search(&Query::new(query, mode)?, ranked)?;
+    if result.is_empty() {
+        let message = format!("No memos match '{}'", query);
+        println!("{}", style::str(&message, style::Options::Muted));
+    } else {
+        println!("{}", result);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use memo::models;
+
+    #[test]
+    fn test_search() {
+        let mut memo_data = models::MemoData::new();
+        data::DataFile::add(&mut memo_data, 1, "buy milk").unwrap();
+        data::DataFile::add(&mut memo_data, 2, "call bob").unwrap();
+        assert!(search(&memo_data, "milk", SearchMode::Substring, false).is_ok());
+        assert!(search(&memo_data, "mi", SearchMode::Word, true).is_ok());
+        assert!(search(&memo_data, "(", SearchMode::Regex, false).is_err());
+    }
+}
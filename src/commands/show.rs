@@ -0,0 +1,69 @@
+use anyhow::{anyhow, Result};
+use memo::data;
+use memo::style;
+
+/// Shows a single memo given its ID.
+pub fn show(d: &impl data::DataFile, id: u32) -> Result<()> {
+    let content = d.get(id).ok_or_else(|| anyhow!("Id '{}' not found", id))?;
+    let id_and_date = format!("{}: {}", id, style::date_time(content.date_time));
+    println!(
+        "{} {}",
+        style::str(&id_and_date, style::Options::Muted),
+        content.text
+    );
+    if let Some(due) = content.due {
+        let due = format!("due: {}", style::date_time(due));
+        println!("{}", style::str(&due, style::Options::Muted));
+    }
+    if let Some(recurrence) = &content.recurrence {
+        let repeats = format!("repeats: {}", recurrence.describe());
+        println!("{}", style::str(&repeats, style::Options::Muted));
+    }
+    if content.snoozed > 0 {
+        let snoozed = format!("snoozed: {}", content.snoozed);
+        println!("{}", style::str(&snoozed, style::Options::Muted));
+    }
+    if let Some(priority) = content.priority {
+        let priority = format!("priority: {}", priority);
+        println!("{}", style::str(&priority, style::Options::Muted));
+    }
+    if !content.tags.is_empty() {
+        let tags: Vec<_> = content.tags.iter().map(|tag| format!("+{}", tag)).collect();
+        println!("{}", style::str(&tags.join(" "), style::Options::Tag));
+    }
+    if let Some(done) = content.done {
+        let done = format!("done: {}", style::date_time(done));
+        println!("{}", style::str(&done, style::Options::Muted));
+    }
+    if let Some(updated_at) = content.updated_at {
+        let updated = format!("updated: {}", style::date_time(updated_at));
+        println!("{}", style::str(&updated, style::Options::Muted));
+    }
+    let uuid = format!("uuid: {}", content.uuid);
+    println!("{}", style::str(&uuid, style::Options::Muted));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use memo::models;
+
+    #[test]
+    fn test_show() {
+        let mut memo_data = models::MemoData::new();
+        data::DataFile::add(&mut memo_data, 1, "test").unwrap();
+
+        assert!(show(&memo_data, 1).is_ok());
+    }
+
+    #[test]
+    fn test_show_invalid() {
+        let memo_data = models::MemoData::new();
+
+        assert_eq!(
+            show(&memo_data, 1).unwrap_err().to_string(),
+            "Id '1' not found"
+        );
+    }
+}
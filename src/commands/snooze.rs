@@ -0,0 +1,158 @@
+use anyhow::{anyhow, Result};
+use chrono::Local;
+use memo::app;
+use memo::data;
+use memo::dates::Postpone;
+use memo::history;
+use memo::style;
+
+/// Minutes a memo is put off by when `memo snooze` is given no duration or date and
+/// `snooze` is not set in the configuration file
+pub const DEFAULT_SNOOZE_MINUTES: i64 = 10;
+
+/// Puts off a memo to a later due date and counts the snooze.
+/// A duration is counted from the due date of the memo if it is still to come, or from now.
+pub fn snooze(
+    d: &mut impl data::DataFile,
+    app_config: &app::AppConfig,
+    id: u32,
+    postpone: Postpone,
+) -> Result<()> {
+    let content = d.get(id).ok_or_else(|| anyhow!("Id '{}' not found", id))?;
+    if content.done.is_some() {
+        return Err(anyhow!("Memo {} is done", id));
+    }
+    let now = Local::now().naive_local();
+    let due = postpone
+        .from(content.due.map_or(now, |due| due.max(now)))
+        .ok_or_else(|| anyhow!("Cannot snooze memo {}: the date is out of range", id))?;
+    if due <= now {
+        return Err(anyhow!(
+            "Cannot snooze memo {} to {}, which is already past",
+            id,
+            style::date_time(due)
+        ));
+    }
+
+    let before = content.clone();
+    d.snooze(id, due)?;
+    d.save(app_config)?;
+
+    let mut step = history::Step::new("snooze");
+    step.push(id, Some(&before), d.get(id));
+    history::record(&app_config.data_file_path(), step)?;
+    println!("Memo {} is snoozed until {}", id, style::date_time(due));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use memo::models;
+
+    fn setup() -> (tempfile::TempDir, app::AppConfig, models::MemoData) {
+        let dir = tempfile::tempdir().unwrap();
+        let app_config = app::AppConfig::from_path("memo", &dir.path().join("memo.txt"));
+        std::fs::File::create(app_config.data_file_path()).unwrap();
+        let mut memo_data = models::MemoData::new();
+        data::DataFile::add(&mut memo_data, 1, "call mom").unwrap();
+        (dir, app_config, memo_data)
+    }
+
+    #[test]
+    fn test_snooze() {
+        let (_dir, app_config, mut memo_data) = setup();
+        let now = Local::now().naive_local();
+
+        // A memo without a due date is put off from now
+        snooze(
+            &mut memo_data,
+            &app_config,
+            1,
+            Postpone::For(Duration::minutes(15)),
+        )
+        .unwrap();
+        let due = memo_data.get(1).unwrap().due.unwrap();
+        assert!(due >= now + Duration::minutes(15));
+        assert!(due < now + Duration::minutes(16));
+
+        // A due date still to come is put off from itself
+        snooze(
+            &mut memo_data,
+            &app_config,
+            1,
+            Postpone::For(Duration::hours(1)),
+        )
+        .unwrap();
+        let content = memo_data.get(1).unwrap();
+        assert_eq!(content.due, Some(due + Duration::hours(1)));
+        assert_eq!(content.snoozed, 2);
+        assert!(data::read_file(&app_config.data_file_path())
+            .unwrap()
+            .contains("snoozed:2"));
+
+        let tomorrow = memo::dates::parse("tomorrow").unwrap();
+        snooze(&mut memo_data, &app_config, 1, Postpone::Until(tomorrow)).unwrap();
+        assert_eq!(memo_data.get(1).unwrap().due, Some(tomorrow));
+        assert_eq!(memo_data.get(1).unwrap().snoozed, 3);
+    }
+
+    #[test]
+    fn test_snooze_recurring() {
+        let (_dir, app_config, mut memo_data) = setup();
+        let nine = (Local::now() + Duration::days(1))
+            .date_naive()
+            .and_hms_opt(9, 0, 0)
+            .unwrap();
+        data::DataFile::set_due(&mut memo_data, 1, Some(nine)).unwrap();
+        let daily = "daily".parse().unwrap();
+        data::DataFile::set_recurrence(&mut memo_data, 1, Some(daily)).unwrap();
+
+        snooze(
+            &mut memo_data,
+            &app_config,
+            1,
+            Postpone::For(Duration::minutes(15)),
+        )
+        .unwrap();
+        assert_eq!(
+            memo_data.get(1).unwrap().due,
+            Some(nine + Duration::minutes(15))
+        );
+
+        // Completing the snoozed memo puts the series back on schedule, from the data file too
+        let mut memo_data = models::MemoData::new();
+        data::DataFile::load(&mut memo_data, &app_config).unwrap();
+        crate::commands::done(&mut memo_data, &app_config, vec![1]).unwrap();
+        let content = memo_data.get(1).unwrap();
+        assert_eq!(content.due, Some(nine + Duration::days(1)));
+        assert!(content.recurrence.as_ref().unwrap().anchor.is_none());
+    }
+
+    #[test]
+    fn test_snooze_invalid() {
+        let (_dir, app_config, mut memo_data) = setup();
+        let yesterday = memo::dates::parse("yesterday").unwrap();
+        assert!(snooze(&mut memo_data, &app_config, 1, Postpone::Until(yesterday)).is_err());
+        assert!(snooze(&mut memo_data, &app_config, 2, Postpone::Until(yesterday)).is_err());
+
+        assert!(snooze(
+            &mut memo_data,
+            &app_config,
+            1,
+            Postpone::For(Duration::weeks(15_000_000))
+        )
+        .is_err());
+
+        data::DataFile::set_done(&mut memo_data, 1, true).unwrap();
+        assert!(snooze(
+            &mut memo_data,
+            &app_config,
+            1,
+            Postpone::For(Duration::minutes(10))
+        )
+        .is_err());
+        assert_eq!(memo_data.get(1).unwrap().snoozed, 0);
+    }
+}
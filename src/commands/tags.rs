@@ -0,0 +1,56 @@
+use anyhow::Result;
+use memo::data;
+use memo::style;
+use std::collections::BTreeMap;
+
+/// Counts how many memos have each tag.
+pub fn count_tags(d: &impl data::DataFile) -> BTreeMap<String, usize> {
+    let mut counts = BTreeMap::new();
+    for id in d.sorted_ids() {
+        if let Some(content) = d.get(id) {
+            for tag in &content.tags {
+                *counts.entry(tag.clone()).or_insert(0) += 1;
+            }
+        }
+    }
+    counts
+}
+
+/// Lists every tag with the number of memos that have it.
+pub fn tags(d: &impl data::DataFile) -> Result<()> {
+    for (tag, count) in count_tags(d) {
+        println!(
+            "{} {}",
+            style::str(&format!("+{}", tag), style::Options::Tag),
+            style::str(&count.to_string(), style::Options::Muted)
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use memo::models;
+
+    #[test]
+    fn test_count_tags() {
+        let mut memo_data = models::MemoData::new();
+        data::DataFile::add(&mut memo_data, 1, "one").unwrap();
+        data::DataFile::add(&mut memo_data, 2, "two").unwrap();
+        data::DataFile::add(&mut memo_data, 3, "three").unwrap();
+        data::DataFile::set_tags(&mut memo_data, 1, vec!["work".to_string()]).unwrap();
+        data::DataFile::set_tags(
+            &mut memo_data,
+            2,
+            vec!["work".to_string(), "home".to_string()],
+        )
+        .unwrap();
+
+        let counts = count_tags(&memo_data);
+        assert_eq!(counts.len(), 2);
+        assert_eq!(counts["work"], 2);
+        assert_eq!(counts["home"], 1);
+        assert!(tags(&memo_data).is_ok());
+    }
+}
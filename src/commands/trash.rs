@@ -0,0 +1,172 @@
+use anyhow::{anyhow, Result};
+use chrono::{Duration, Local};
+use memo::app;
+use memo::data;
+use memo::history;
+use memo::style;
+
+/// Lists the memos in the trash, most recently removed first.
+pub fn trash_ls(d: &impl data::DataFile) -> Result<()> {
+    if d.trash().is_empty() {
+        println!(
+            "{}",
+            style::str("The trash is empty", style::Options::Muted)
+        );
+        return Ok(());
+    }
+    for trashed in d.trash().iter().rev() {
+        let id_and_date = format!(
+            "{:0>#2}: removed {}",
+            trashed.id,
+            style::date_time(trashed.removed_at)
+        );
+        println!(
+            "{} {}",
+            style::str(&id_and_date, style::Options::Muted),
+            trashed.content.text
+        );
+    }
+    Ok(())
+}
+
+/// Moves memos back from the trash, keeping their id if it is still free.
+/// The restore is recorded in the history, undoing it moves the memos to the trash again.
+pub fn trash_restore(
+    d: &mut impl data::DataFile,
+    app_config: &app::AppConfig,
+    id: Vec<u32>,
+) -> Result<()> {
+    let mut ids_not_found = String::new();
+    let mut restored = Vec::new();
+    let mut step = history::Step::new("restore");
+    for i in id {
+        match d.restore(i) {
+            Ok(new_id) => {
+                step.push(new_id, None, d.get(new_id));
+                restored.push((i, new_id));
+            }
+            Err(e) => ids_not_found.push_str(&format!("{}: {}\n", i, e)),
+        }
+    }
+
+    if !ids_not_found.is_empty() {
+        return Err(anyhow!("\n{}", ids_not_found));
+    }
+
+    d.save(app_config)?;
+    history::record(&app_config.data_file_path(), step)?;
+    for (id, new_id) in restored {
+        if id == new_id {
+            println!("Restored memo {}", id);
+        } else {
+            println!("Restored memo {} as {}, its id is in use", id, new_id);
+        }
+    }
+    Ok(())
+}
+
+/// Permanently deletes every memo in the trash.
+pub fn trash_empty(d: &mut impl data::DataFile, app_config: &app::AppConfig) -> Result<()> {
+    let count = d.purge(None)?;
+    d.save(app_config)?;
+    println!("Deleted {} memos from the trash", count);
+    Ok(())
+}
+
+/// Permanently deletes the memos that have been in the trash for more than `days` days.
+/// The data file is only written when something was deleted. Returns the number of memos deleted.
+/// Nothing is deleted when `days` reaches back past the earliest date that can be represented.
+pub fn purge_trash(
+    d: &mut impl data::DataFile,
+    app_config: &app::AppConfig,
+    days: u32,
+) -> Result<usize> {
+    let removed_before = Duration::try_days(days.into())
+        .and_then(|age| Local::now().naive_local().checked_sub_signed(age));
+    let Some(removed_before) = removed_before else {
+        return Ok(0);
+    };
+    let count = d.purge(Some(removed_before))?;
+    if count > 0 {
+        d.save(app_config)?;
+    }
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use memo::models;
+
+    fn setup() -> (tempfile::TempDir, app::AppConfig, models::MemoData) {
+        let mut app_config = app::AppConfig::new("memo", "memo.txt");
+        let dir = tempfile::tempdir().unwrap();
+        app_config.data_dir = dir.path().to_path_buf();
+
+        // Create file
+        std::fs::File::create(app_config.data_file_path()).unwrap();
+
+        let mut memo_data = models::MemoData::new();
+        data::DataFile::add(&mut memo_data, 1, "one").unwrap();
+        data::DataFile::add(&mut memo_data, 2, "two").unwrap();
+        data::DataFile::remove(&mut memo_data, 1).unwrap();
+        (dir, app_config, memo_data)
+    }
+
+    #[test]
+    fn test_trash_ls() {
+        let (_dir, _app_config, memo_data) = setup();
+        assert!(trash_ls(&memo_data).is_ok());
+        assert!(trash_ls(&models::MemoData::new()).is_ok());
+    }
+
+    #[test]
+    fn test_trash_restore() {
+        let (_dir, app_config, mut memo_data) = setup();
+        assert!(trash_restore(&mut memo_data, &app_config, vec![1]).is_ok());
+        assert_eq!(memo_data.sorted_ids(), vec![1, 2]);
+        assert!(trash_restore(&mut memo_data, &app_config, vec![1]).is_err());
+    }
+
+    #[test]
+    fn test_trash_restore_undo() {
+        let (_dir, app_config, mut memo_data) = setup();
+        crate::commands::remove(&mut memo_data, &app_config, vec![2]).unwrap();
+        trash_restore(&mut memo_data, &app_config, vec![1]).unwrap();
+
+        // The restore is undone, not the remove before it
+        crate::commands::undo(&mut memo_data, &app_config).unwrap();
+        assert!(memo_data.sorted_ids().is_empty());
+        assert!(memo_data.trash.iter().any(|trashed| trashed.id == 1));
+        crate::commands::redo(&mut memo_data, &app_config).unwrap();
+        assert_eq!(memo_data.sorted_ids(), vec![1]);
+    }
+
+    #[test]
+    fn test_trash_empty() {
+        let (_dir, app_config, mut memo_data) = setup();
+        assert!(trash_empty(&mut memo_data, &app_config).is_ok());
+        assert!(data::DataFile::trash(&memo_data).is_empty());
+        assert_eq!(memo_data.sorted_ids(), vec![2]);
+    }
+
+    #[test]
+    fn test_purge_trash() {
+        let (_dir, app_config, mut memo_data) = setup();
+        assert_eq!(purge_trash(&mut memo_data, &app_config, 30).unwrap(), 0);
+        memo_data.trash[0].removed_at -= Duration::days(31);
+        assert_eq!(purge_trash(&mut memo_data, &app_config, 30).unwrap(), 1);
+        assert!(data::DataFile::trash(&memo_data).is_empty());
+    }
+
+    #[test]
+    fn test_purge_trash_overflow() {
+        let (_dir, app_config, mut memo_data) = setup();
+        memo_data.trash[0].removed_at -= Duration::days(31);
+        assert_eq!(
+            purge_trash(&mut memo_data, &app_config, u32::MAX).unwrap(),
+            0
+        );
+        assert_eq!(data::DataFile::trash(&memo_data).len(), 1);
+    }
+}
@@ -0,0 +1,153 @@
+use anyhow::{anyhow, Result};
+use memo::app;
+use memo::data;
+use memo::history;
+use memo::style;
+
+/// Undoes the last change recorded in the history.
+/// A memo changed since, e.g. by editing the data file by hand, is left as it is.
+pub fn undo(d: &mut impl data::DataFile, app_config: &app::AppConfig) -> Result<()> {
+    step(d, app_config, true)
+}
+
+/// Redoes the last change undone with `undo`.
+pub fn redo(d: &mut impl data::DataFile, app_config: &app::AppConfig) -> Result<()> {
+    step(d, app_config, false)
+}
+
+fn step(d: &mut impl data::DataFile, app_config: &app::AppConfig, undo: bool) -> Result<()> {
+    let mut journal = history::Journal::load(&app_config.data_file_path())?;
+    let step = if undo {
+        journal.undo(d)?
+    } else {
+        journal.redo(d)?
+    };
+    let step = step.ok_or_else(|| anyhow!("Nothing to {}", if undo { "undo" } else { "redo" }))?;
+
+    let ids: Vec<String> = step.ids().iter().map(|id| id.to_string()).collect();
+    let message = format!(
+        "{} {} of {} {} ({})",
+        if undo { "Undid" } else { "Redid" },
+        step.action,
+        if ids.len() == 1 { "memo" } else { "memos" },
+        ids.join(", "),
+        style::date_time(step.time)
+    );
+    d.save(app_config)?;
+    journal.save()?;
+    println!("{}", message);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands;
+    use memo::models;
+
+    #[test]
+    fn test_undo_redo_remove() {
+        let mut app_config = app::AppConfig::new("memo", "memo.txt");
+        let dir = tempfile::tempdir().unwrap();
+        app_config.data_dir = dir.path().to_path_buf();
+
+        // Create file
+        std::fs::File::create(app_config.data_file_path()).unwrap();
+
+        let mut memo_data = models::MemoData::new();
+        for text in ["one", "two", "three"] {
+            commands::add(
+                &mut memo_data,
+                &app_config,
+                text.to_string(),
+                None,
+                vec![],
+                None,
+                None,
+            )
+            .unwrap();
+        }
+        commands::remove(&mut memo_data, &app_config, vec![1, 3]).unwrap();
+        assert_eq!(memo_data.sorted_ids(), vec![2]);
+
+        assert!(undo(&mut memo_data, &app_config).is_ok());
+        assert_eq!(memo_data.sorted_ids(), vec![1, 2, 3]);
+        assert_eq!(memo_data.get(3).unwrap().text, "three");
+
+        let mut loaded = models::MemoData::new();
+        data::DataFile::load(&mut loaded, &app_config).unwrap();
+        assert_eq!(loaded.sorted_ids(), vec![1, 2, 3]);
+
+        assert!(redo(&mut memo_data, &app_config).is_ok());
+        assert_eq!(memo_data.sorted_ids(), vec![2]);
+        assert!(redo(&mut memo_data, &app_config).is_err());
+
+        for _ in 0..4 {
+            assert!(undo(&mut memo_data, &app_config).is_ok());
+        }
+        assert!(memo_data.sorted_ids().is_empty());
+        assert!(undo(&mut memo_data, &app_config).is_err());
+    }
+
+    #[test]
+    fn test_undo_redo_prio_and_done() {
+        let mut app_config = app::AppConfig::new("memo", "memo.txt");
+        let dir = tempfile::tempdir().unwrap();
+        app_config.data_dir = dir.path().to_path_buf();
+        std::fs::File::create(app_config.data_file_path()).unwrap();
+
+        let mut memo_data = models::MemoData::new();
+        commands::add(
+            &mut memo_data,
+            &app_config,
+            "one".to_string(),
+            None,
+            vec![],
+            None,
+            None,
+        )
+        .unwrap();
+        let mut lock =
+            memo::lock::Lock::acquire(&app_config.data_file_path(), memo::lock::DEFAULT_TIMEOUT)
+                .unwrap();
+        commands::edit(
+            &mut memo_data,
+            &app_config,
+            &mut lock,
+            1,
+            Some("uno".to_string()),
+        )
+        .unwrap();
+        commands::prio(&mut memo_data, &app_config, 1, models::Priority::new('A')).unwrap();
+        commands::done(&mut memo_data, &app_config, vec![1]).unwrap();
+
+        // Each step is undone from the data file as written, in order
+        let load = || {
+            let mut loaded = models::MemoData::new();
+            data::DataFile::load(&mut loaded, &app_config).unwrap();
+            loaded
+        };
+        let mut loaded = load();
+        undo(&mut loaded, &app_config).unwrap();
+        let content = load().get(1).cloned().unwrap();
+        assert!(content.done.is_none());
+        assert_eq!(content.priority, models::Priority::new('A'));
+        assert_eq!(content.text, "uno");
+
+        let mut loaded = load();
+        undo(&mut loaded, &app_config).unwrap();
+        undo(&mut loaded, &app_config).unwrap();
+        let content = load().get(1).cloned().unwrap();
+        assert!(content.priority.is_none());
+        assert_eq!(content.text, "one");
+
+        let mut loaded = load();
+        for _ in 0..3 {
+            redo(&mut loaded, &app_config).unwrap();
+        }
+        let content = load().get(1).cloned().unwrap();
+        assert!(content.done.is_some());
+        assert_eq!(content.priority, models::Priority::new('A'));
+        assert_eq!(content.text, "uno");
+    }
+}
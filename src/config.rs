@@ -0,0 +1,465 @@
+use crate::app::{self, AppConfig, Backend};
+use crate::data::{DisplayMode, DuplicatePolicy, MAX_BACKUPS};
+use crate::dates;
+use crate::notify::Notifier;
+use crate::style::{self, Theme};
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Deserializer};
+use std::fmt::Display;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Keys that can be set in the configuration file
+pub const KEYS: [&str; 12] = [
+    "file",
+    "notebook",
+    "backend",
+    "display",
+    "date_format",
+    "theme",
+    "trash_days",
+    "lock_timeout",
+    "backups",
+    "duplicates",
+    "notifier",
+    "snooze",
+];
+
+/// Settings read from the configuration file, every one of them is optional.
+///
+/// The data file is looked up in this order, the first one that is set wins:
+/// 1. the `--file` flag
+/// 2. the `MEMO_FILE` environment variable
+/// 3. the `MEMO_DATA_DIR` environment variable, with the default file name of the backend
+/// 4. a `.memo` project file in the current directory or one of its parents, unless `--global` is given
+/// 5. `file` in the configuration file
+/// 6. the default file of the backend in the data directory of the system, e.g. `~/.local/share/memo`
+///
+/// That file is the default notebook, the other notebooks are kept in a `notebooks` directory next to it.
+///
+/// The `--backend`, `--notebook` and `--duplicates` flags and the `MEMO_BACKEND`,
+/// `MEMO_NOTEBOOK`, `MEMO_TRASH_DAYS` and `MEMO_DUPLICATES` environment variables also take
+/// precedence over the configuration file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Path of the data file, `~` is expanded to the home directory
+    pub file: Option<PathBuf>,
+    /// Notebook used when none is given with `--notebook`
+    pub notebook: Option<String>,
+    /// Storage backend: text or sqlite
+    #[serde(default, deserialize_with = "from_str")]
+    pub backend: Option<Backend>,
+    /// Layout of `memo ls`: date, sorted or priority
+    #[serde(default, deserialize_with = "from_str")]
+    pub display: Option<DisplayMode>,
+    /// `strftime` format used to show dates, e.g. `%d/%m/%Y %H:%M`
+    pub date_format: Option<String>,
+    /// Color theme: default, mono or none
+    #[serde(default, deserialize_with = "from_str")]
+    pub theme: Option<Theme>,
+    /// Days after which memos are deleted from the trash
+    pub trash_days: Option<u32>,
+    /// Seconds to wait for another memo process to release the data file
+    pub lock_timeout: Option<u32>,
+    /// Number of rotating backups kept of the data file, 0 disables them
+    pub backups: Option<u32>,
+    /// How duplicate ids in the data file are loaded: error, keep-first, keep-last or renumber
+    #[serde(default, deserialize_with = "from_str")]
+    pub duplicates: Option<DuplicatePolicy>,
+    /// How `memo daemon` notifies due memos: stderr, log:<path> or command:<template>
+    #[serde(default, deserialize_with = "from_str")]
+    pub notifier: Option<Notifier>,
+    /// Time `memo snooze` puts a memo off by when none is given, e.g. `10m` or `1h`
+    pub snooze: Option<String>,
+}
+
+/// Where the data file in use was found, see `Config` for the order of precedence
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Source {
+    /// The `--file` flag or `MEMO_FILE`
+    File,
+    /// `MEMO_DATA_DIR`
+    DataDir,
+    /// A `.memo` project file
+    Local,
+    /// `file` in the configuration file
+    Config,
+    /// The data directory of the system
+    Default,
+}
+
+impl Display for Source {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let source = match self {
+            Source::File => "set with --file or MEMO_FILE",
+            Source::DataDir => "set with MEMO_DATA_DIR",
+            Source::Local => "project file",
+            Source::Config => "set in the configuration file",
+            Source::Default => "default location",
+        };
+        write!(f, "{}", source)
+    }
+}
+
+/// Deserialize an optional value from its string representation
+fn from_str<'de, D, T>(deserializer: D) -> std::result::Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+    T::Err: Display,
+{
+    Option::<String>::deserialize(deserializer)?
+        .map(|value| T::from_str(&value).map_err(serde::de::Error::custom))
+        .transpose()
+}
+
+/// Path of the configuration file: `$XDG_CONFIG_HOME/memo/config.toml`, falling back to
+/// the configuration directory of the system when `XDG_CONFIG_HOME` is not set
+pub fn config_path() -> PathBuf {
+    let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .or_else(dirs::config_dir)
+        .unwrap_or_else(|| PathBuf::from("."));
+    config_dir.join("memo").join("config.toml")
+}
+
+/// Replace a leading `~` in a path with the home directory
+pub(crate) fn expand_home(path: &Path) -> PathBuf {
+    match (path.strip_prefix("~"), dirs::home_dir()) {
+        (Ok(rest), Some(home)) => home.join(rest),
+        _ => path.to_path_buf(),
+    }
+}
+
+impl Config {
+    /// Parse the content of a configuration file
+    pub fn parse(data: &str) -> Result<Self> {
+        let config: Config = toml::from_str(data)?;
+        if let Some(date_format) = &config.date_format {
+            style::validate_date_format(date_format)?;
+        }
+        if let Some(notebook) = &config.notebook {
+            app::validate_notebook_name(notebook)?;
+        }
+        if let Some(snooze) = &config.snooze {
+            dates::parse_duration(snooze)?;
+        }
+        if let Some(backups) = config.backups {
+            if backups > MAX_BACKUPS {
+                return Err(anyhow!(
+                    "Invalid number of backups '{}', at most {} are kept",
+                    backups,
+                    MAX_BACKUPS
+                ));
+            }
+        }
+        Ok(config)
+    }
+
+    /// Load the configuration file, the default configuration if it does not exist
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Config::default());
+        }
+        let data = fs::read_to_string(path)?;
+        Config::parse(&data).map_err(|e| {
+            anyhow!(
+                "Invalid configuration file '{}': {}",
+                path.display(),
+                e.to_string().trim()
+            )
+        })
+    }
+
+    /// Returns the app config of the data file and where it was found, see `Config` for the
+    /// order of precedence. `file` comes from the command line or `MEMO_FILE`, `data_dir` from
+    /// `MEMO_DATA_DIR` and `local` is the project file found from the current directory.
+    pub fn app_config(
+        &self,
+        name: &str,
+        backend: Backend,
+        file: Option<PathBuf>,
+        data_dir: Option<PathBuf>,
+        local: Option<PathBuf>,
+    ) -> (AppConfig, Source) {
+        let path = file
+            .map(|file| (file, Source::File))
+            .or_else(|| {
+                data_dir.map(|data_dir| (data_dir.join(backend.data_file()), Source::DataDir))
+            })
+            .or_else(|| local.map(|local| (local, Source::Local)))
+            .or_else(|| {
+                self.file
+                    .as_deref()
+                    .map(|file| (expand_home(file), Source::Config))
+            });
+        let (mut app_config, source) = match path {
+            Some((path, source)) => (AppConfig::from_path(name, &path), source),
+            None => (AppConfig::new(name, backend.data_file()), Source::Default),
+        };
+        if let Some(backups) = self.backups {
+            app_config.backups = backups as usize;
+        }
+        if let Some(duplicates) = self.duplicates {
+            app_config.duplicates = duplicates;
+        }
+        (app_config, source)
+    }
+}
+
+/// Check that a key can be set in the configuration file
+fn check_key(key: &str) -> Result<()> {
+    if !KEYS.contains(&key) {
+        return Err(anyhow!(
+            "Unknown key '{}'. Expected one of: {}",
+            key,
+            KEYS.join(", ")
+        ));
+    }
+    Ok(())
+}
+
+/// Read the configuration file as an editable document, empty if it does not exist
+fn read_document(path: &Path) -> Result<toml_edit::DocumentMut> {
+    if !path.exists() {
+        return Ok(toml_edit::DocumentMut::new());
+    }
+    fs::read_to_string(path)?
+        .parse()
+        .with_context(|| format!("Invalid configuration file '{}'", path.display()))
+}
+
+/// Write the document to the configuration file if it is a valid configuration
+fn write_document(path: &Path, document: &toml_edit::DocumentMut) -> Result<()> {
+    let data = document.to_string();
+    Config::parse(&data)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Could not create directory '{}'", parent.display()))?;
+    }
+    fs::write(path, data).with_context(|| format!("Could not write file '{}'", path.display()))
+}
+
+/// Returns the value of a key in the configuration file, None if it is not set
+pub fn get(path: &Path, key: &str) -> Result<Option<String>> {
+    check_key(key)?;
+    let document = read_document(path)?;
+    Ok(document.get(key).and_then(|item| match item.as_str() {
+        Some(value) => Some(value.to_string()),
+        None => item
+            .as_value()
+            .map(|value| value.to_string().trim().to_string()),
+    }))
+}
+
+/// Returns every key set in the configuration file with its value, in the order of `KEYS`
+pub fn list(path: &Path) -> Result<Vec<(String, String)>> {
+    let mut values = Vec::new();
+    for key in KEYS {
+        if let Some(value) = get(path, key)? {
+            values.push((key.to_string(), value));
+        }
+    }
+    Ok(values)
+}
+
+/// Set a key in the configuration file, creating it if needed.
+/// The value is checked before writing, and the rest of the file, comments included, is kept.
+pub fn set(path: &Path, key: &str, value: &str) -> Result<()> {
+    check_key(key)?;
+    let mut document = read_document(path)?;
+    document[key] = match key {
+        "trash_days" => {
+            let days: u32 = value
+                .parse()
+                .map_err(|_| anyhow!("Invalid number of days '{}'", value))?;
+            toml_edit::value(i64::from(days))
+        }
+        "lock_timeout" => {
+            let seconds: u32 = value
+                .parse()
+                .map_err(|_| anyhow!("Invalid number of seconds '{}'", value))?;
+            toml_edit::value(i64::from(seconds))
+        }
+        "backups" => {
+            let count: u32 = value
+                .parse()
+                .map_err(|_| anyhow!("Invalid number of backups '{}'", value))?;
+            toml_edit::value(i64::from(count))
+        }
+        "backend" => toml_edit::value(Backend::from_str(value)?.to_string()),
+        "display" => toml_edit::value(DisplayMode::from_str(value)?.to_string()),
+        "theme" => toml_edit::value(Theme::from_str(value)?.to_string()),
+        "duplicates" => toml_edit::value(DuplicatePolicy::from_str(value)?.to_string()),
+        "snooze" => {
+            dates::parse_duration(value)?;
+            toml_edit::value(value)
+        }
+        "notifier" => {
+            Notifier::from_str(value)?;
+            toml_edit::value(value)
+        }
+        "date_format" => {
+            style::validate_date_format(value)?;
+            toml_edit::value(value)
+        }
+        "notebook" => {
+            app::validate_notebook_name(value)?;
+            toml_edit::value(value)
+        }
+        _ => toml_edit::value(value),
+    };
+    write_document(path, &document)
+}
+
+/// Remove a key from the configuration file
+pub fn unset(path: &Path, key: &str) -> Result<()> {
+    check_key(key)?;
+    let mut document = read_document(path)?;
+    if document.remove(key).is_none() {
+        return Err(anyhow!("Key '{}' is not set", key));
+    }
+    write_document(path, &document)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_config_parse() {
+        let config = Config::parse(
+            "file = \"/data/notes.txt\"\nbackend = \"sqlite\"\ndisplay = \"priority\"\ndate_format = \"%d/%m/%Y\"\ntheme = \"mono\"\ntrash_days = 30\nlock_timeout = 2\nbackups = 5\nduplicates = \"renumber\"\nnotifier = \"log:/data/memo.log\"\nsnooze = \"1h\"\n",
+        )
+        .unwrap();
+        assert_eq!(config.file, Some(PathBuf::from("/data/notes.txt")));
+        assert_eq!(config.backend, Some(Backend::Sqlite));
+        assert_eq!(config.display, Some(DisplayMode::ByPriority));
+        assert_eq!(config.date_format.as_deref(), Some("%d/%m/%Y"));
+        assert_eq!(config.theme, Some(Theme::Mono));
+        assert_eq!(config.trash_days, Some(30));
+        assert_eq!(config.lock_timeout, Some(2));
+        assert_eq!(config.backups, Some(5));
+        assert_eq!(config.duplicates, Some(DuplicatePolicy::Renumber));
+        assert_eq!(
+            config.notifier,
+            Some(Notifier::Log(PathBuf::from("/data/memo.log")))
+        );
+        assert_eq!(config.snooze.as_deref(), Some("1h"));
+        assert!(Config::parse("snooze = \"soon\"").is_err());
+
+        assert!(Config::parse("").unwrap().file.is_none());
+        assert!(Config::parse("backend = \"csv\"").is_err());
+        assert!(Config::parse("date_format = \"%Q\"").is_err());
+        assert!(Config::parse("colour = \"red\"").is_err());
+        assert!(Config::parse("notebook = \"../work\"").is_err());
+        assert_eq!(
+            Config::parse("notebook = \"work\"")
+                .unwrap()
+                .notebook
+                .as_deref(),
+            Some("work")
+        );
+    }
+
+    #[test]
+    fn test_config_load_missing() {
+        let dir = tempdir().unwrap();
+        let config = Config::load(&dir.path().join("config.toml")).unwrap();
+        assert!(config.backend.is_none());
+    }
+
+    #[test]
+    fn test_config_app_config() {
+        let config = Config {
+            file: Some(PathBuf::from("/config/notes.txt")),
+            ..Default::default()
+        };
+        let app_config = |file: Option<&str>, data_dir: Option<&str>, local: Option<&str>| {
+            let (app_config, source) = config.app_config(
+                "memo",
+                Backend::Text,
+                file.map(PathBuf::from),
+                data_dir.map(PathBuf::from),
+                local.map(PathBuf::from),
+            );
+            (app_config.data_file_path(), source)
+        };
+        assert_eq!(
+            app_config(Some("/flag/memo.txt"), Some("/env"), Some("/repo/.memo")),
+            (PathBuf::from("/flag/memo.txt"), Source::File)
+        );
+        assert_eq!(
+            app_config(None, Some("/env"), Some("/repo/.memo")),
+            (PathBuf::from("/env/memo.txt"), Source::DataDir)
+        );
+        assert_eq!(
+            app_config(None, None, Some("/repo/.memo")),
+            (PathBuf::from("/repo/.memo"), Source::Local)
+        );
+        assert_eq!(
+            app_config(None, None, None),
+            (PathBuf::from("/config/notes.txt"), Source::Config)
+        );
+
+        let (app_config, source) =
+            Config::default().app_config("memo", Backend::Sqlite, None, None, None);
+        assert_eq!(app_config.data_file(), "memo.db");
+        assert_eq!(app_config.backups, crate::data::DEFAULT_BACKUPS);
+        assert_eq!(source, Source::Default);
+
+        let config = Config::parse("backups = 0").unwrap();
+        let (app_config, _) = config.app_config("memo", Backend::Text, None, None, None);
+        assert_eq!(app_config.backups, 0);
+        assert!(Config::parse("backups = 4000000000").is_err());
+    }
+
+    #[test]
+    fn test_config_set_get_unset() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("memo").join("config.toml");
+
+        assert_eq!(get(&path, "theme").unwrap(), None);
+        set(&path, "theme", "none").unwrap();
+        set(&path, "trash_days", "7").unwrap();
+        assert_eq!(get(&path, "theme").unwrap().as_deref(), Some("none"));
+        assert_eq!(get(&path, "trash_days").unwrap().as_deref(), Some("7"));
+        assert_eq!(
+            list(&path).unwrap(),
+            vec![
+                ("theme".to_string(), "none".to_string()),
+                ("trash_days".to_string(), "7".to_string())
+            ]
+        );
+
+        assert!(set(&path, "theme", "dark").is_err());
+        assert!(set(&path, "trash_days", "-1").is_err());
+        assert!(set(&path, "backups", "many").is_err());
+        assert!(set(&path, "backups", "1000").is_err());
+        assert!(set(&path, "duplicates", "ignore").is_err());
+        assert!(set(&path, "notifier", "email").is_err());
+        assert!(set(&path, "snooze", "tomorrow").is_err());
+        assert!(set(&path, "colour", "red").is_err());
+        assert_eq!(get(&path, "theme").unwrap().as_deref(), Some("none"));
+
+        unset(&path, "theme").unwrap();
+        assert_eq!(get(&path, "theme").unwrap(), None);
+        assert!(unset(&path, "theme").is_err());
+        assert_eq!(Config::load(&path).unwrap().trash_days, Some(7));
+    }
+
+    #[test]
+    fn test_config_set_keeps_comments() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        fs::write(&path, "# my settings\ntheme = \"mono\"\n").unwrap();
+        set(&path, "backend", "sqlite").unwrap();
+        let data = fs::read_to_string(&path).unwrap();
+        assert!(data.starts_with("# my settings\ntheme = \"mono\"\n"));
+        assert!(data.contains("backend = \"sqlite\""));
+    }
+}
@@ -1,26 +1,281 @@
 use crate::app;
-use anyhow::{anyhow, Result};
+use crate::models::{BadLine, Content, DuplicateLine, Priority, Trashed};
+use crate::output::OutputFormat;
+use crate::recur::Recurrence;
+use crate::search::Query;
+use anyhow::{anyhow, Context, Result};
+use chrono::{Duration, NaiveDateTime};
 use std::io::prelude::*;
 use std::io::BufReader;
 use std::path::Path;
-use std::{fmt, fs, path::PathBuf};
+use std::{fmt, fs, path::PathBuf, str::FromStr};
+use uuid::Uuid;
 
+/// Layout of the memos when displaying them in the terminal
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum DisplayMode {
     Sorted,
     GroupByDate,
+    ByPriority,
+}
+
+impl FromStr for DisplayMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "date" => Ok(DisplayMode::GroupByDate),
+            "sorted" => Ok(DisplayMode::Sorted),
+            "priority" => Ok(DisplayMode::ByPriority),
+            _ => Err(anyhow!(
+                "Invalid display mode '{}'. Expected: date, sorted or priority",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for DisplayMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DisplayMode::GroupByDate => write!(f, "date"),
+            DisplayMode::Sorted => write!(f, "sorted"),
+            DisplayMode::ByPriority => write!(f, "priority"),
+        }
+    }
+}
+
+/// How lines of a text data file that use an id already used by another line are loaded
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum DuplicatePolicy {
+    /// Skip the later lines like lines that cannot be read, so the file is not changed until
+    /// they are fixed
+    #[default]
+    Error,
+    /// Keep the first line and drop the later ones
+    KeepFirst,
+    /// Keep the last line and drop the earlier ones
+    KeepLast,
+    /// Keep the first line and give the later ones new ids
+    Renumber,
+}
+
+impl FromStr for DuplicatePolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "error" => Ok(DuplicatePolicy::Error),
+            "keep-first" => Ok(DuplicatePolicy::KeepFirst),
+            "keep-last" => Ok(DuplicatePolicy::KeepLast),
+            "renumber" => Ok(DuplicatePolicy::Renumber),
+            _ => Err(anyhow!(
+                "Invalid duplicate id policy '{}'. Expected: error, keep-first, keep-last or renumber",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for DuplicatePolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DuplicatePolicy::Error => write!(f, "error"),
+            DuplicatePolicy::KeepFirst => write!(f, "keep-first"),
+            DuplicatePolicy::KeepLast => write!(f, "keep-last"),
+            DuplicatePolicy::Renumber => write!(f, "renumber"),
+        }
+    }
+}
+
+/// Format of the lines of a text data file
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Format {
+    /// One memo per line: `id: yyyy-mm-dd hh:mm:ss [attributes] text`
+    #[default]
+    Lines,
+    /// A versioned header followed by one JSON object per memo
+    JsonLines,
+}
+
+/// Which memos to show based on their completion state
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Status {
+    #[default]
+    Pending,
+    Done,
+    All,
+}
+
+/// Filter applied to the memos when displaying them
+/// A memo passes if its status matches, it has all the included tags and none of the excluded ones,
+/// and it is due within the window if one is given.
+#[derive(Debug, Default)]
+pub struct Filter {
+    pub status: Status,
+    pub include_tags: Vec<String>,
+    pub exclude_tags: Vec<String>,
+    /// Only memos with a due date or an occurrence from the first to the second date time
+    pub due_within: Option<(NaiveDateTime, NaiveDateTime)>,
+}
+
+impl Filter {
+    /// Returns true if the content passes the filter
+    pub fn matches(&self, content: &Content) -> bool {
+        let status = match self.status {
+            Status::Pending => content.done.is_none(),
+            Status::Done => content.done.is_some(),
+            Status::All => true,
+        };
+        status
+            && self.include_tags.iter().all(|tag| content.has_tag(tag))
+            && !self.exclude_tags.iter().any(|tag| content.has_tag(tag))
+            && self
+                .due_within
+                .is_none_or(|(from, until)| !content.occurrences(from, until).is_empty())
+    }
+
+    /// Returns the window of `ls --upcoming`, from `now` until `upcoming` later
+    pub fn upcoming(
+        now: NaiveDateTime,
+        upcoming: Duration,
+    ) -> Result<(NaiveDateTime, NaiveDateTime)> {
+        let until = now
+            .checked_add_signed(upcoming)
+            .ok_or_else(|| anyhow!("Cannot list upcoming memos: the date is out of range"))?;
+        Ok((now, until))
+    }
+}
+
+/// A memo given on the command line, by its id or by a prefix of its UUID
+/// A value of only digits is an id, so a UUID prefix needs at least one letter or hyphen.
+#[derive(Clone, Debug, PartialEq)]
+pub enum MemoRef {
+    Id(u32),
+    /// Lowercase prefix of a UUID, without hyphens
+    Uuid(String),
+}
+
+impl MemoRef {
+    /// Returns the id of the only memo whose UUID starts with the prefix, or the id itself.
+    /// `memos` are the ids and UUIDs to look the prefix up in.
+    pub fn resolve<'a>(&self, memos: impl Iterator<Item = (u32, &'a Uuid)>) -> Result<u32> {
+        let prefix = match self {
+            MemoRef::Id(id) => return Ok(*id),
+            MemoRef::Uuid(prefix) => prefix,
+        };
+        let mut ids: Vec<u32> = memos
+            .filter(|(_, uuid)| uuid.simple().to_string().starts_with(prefix.as_str()))
+            .map(|(id, _)| id)
+            .collect();
+        ids.sort();
+        match ids[..] {
+            [id] => Ok(id),
+            [] => Err(anyhow!("No memo with a UUID starting with '{}'", self)),
+            _ => Err(anyhow!(
+                "UUID prefix '{}' matches memos {}",
+                self,
+                ids.iter()
+                    .map(|id| id.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )),
+        }
+    }
+}
+
+impl FromStr for MemoRef {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        if !s.is_empty() && s.chars().all(|c| c.is_ascii_digit()) {
+            return match s.parse() {
+                Ok(id) if id > 0 => Ok(MemoRef::Id(id)),
+                _ => Err(anyhow!("Invalid id '{}'. Ids start at 1", s)),
+            };
+        }
+        let prefix: String = s.chars().filter(|c| *c != '-').collect();
+        if prefix.is_empty() || prefix.len() > 32 || !prefix.chars().all(|c| c.is_ascii_hexdigit())
+        {
+            return Err(anyhow!("Invalid id or UUID prefix '{}'", s));
+        }
+        Ok(MemoRef::Uuid(prefix.to_lowercase()))
+    }
+}
+
+impl fmt::Display for MemoRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MemoRef::Id(id) => write!(f, "{}", id),
+            MemoRef::Uuid(prefix) => write!(f, "{}", prefix),
+        }
+    }
 }
 
 /// DataFile trait is used to define the methods that a data file must implement.
 pub trait DataFile: fmt::Display {
     fn load(&mut self, app: &app::AppConfig) -> Result<()>;
+    fn save(&mut self, app: &app::AppConfig) -> Result<()>;
     fn sorted_ids(&self) -> Vec<u32>;
+    fn get(&self, id: u32) -> Option<&Content>;
     fn add(&mut self, id: u32, name: &str) -> Result<()>;
+    fn set_due(&mut self, id: u32, due: Option<NaiveDateTime>) -> Result<()>;
+    fn set_done(&mut self, id: u32, done: bool) -> Result<()>;
+    fn set_tags(&mut self, id: u32, tags: Vec<String>) -> Result<()>;
+    fn set_priority(&mut self, id: u32, priority: Option<Priority>) -> Result<()>;
+    fn set_recurrence(&mut self, id: u32, recurrence: Option<Recurrence>) -> Result<()>;
+    fn snooze(&mut self, id: u32, due: NaiveDateTime) -> Result<()>;
+    fn update(&mut self, id: u32, text: &str) -> Result<()>;
+    fn insert(&mut self, id: u32, content: Content) -> Result<()>;
     fn remove(&mut self, id: u32) -> Result<()>;
-    fn display(&self, mode: DisplayMode) -> Result<()>;
+    fn renumber(&mut self, moves: &[(u32, u32)]) -> Result<()>;
+    fn trash(&self) -> &[Trashed];
+    fn restore(&mut self, id: u32) -> Result<u32>;
+    fn purge(&mut self, removed_before: Option<NaiveDateTime>) -> Result<usize>;
+    fn render(&self, mode: DisplayMode, filter: &Filter, format: OutputFormat) -> Result<String>;
+    fn search(&self, query: &Query, ranked: bool) -> Result<String>;
+
+    /// Returns the id of a memo given by its id or a prefix of its UUID
+    fn resolve(&self, memo: &MemoRef) -> Result<u32> {
+        memo.resolve(
+            self.sorted_ids()
+                .into_iter()
+                .filter_map(|id| self.get(id).map(|content| (id, &content.uuid))),
+        )
+    }
+
+    /// Returns the id of a memo in the trash given by its id or a prefix of its UUID
+    fn resolve_trashed(&self, memo: &MemoRef) -> Result<u32> {
+        memo.resolve(
+            self.trash()
+                .iter()
+                .map(|trashed| (trashed.id, &trashed.content.uuid)),
+        )
+    }
+
+    /// Lines of the data file that were skipped because they could not be read
+    fn bad_lines(&self) -> &[BadLine] {
+        &[]
+    }
+
+    /// Lines of the data file whose id was already used, resolved by the duplicate id policy
+    fn duplicates(&self) -> &[DuplicateLine] {
+        &[]
+    }
+
+    /// Print the memos rendered with `render`.
+    /// A closed stdout, e.g. when piped to `head`, is not an error.
+    fn display(&self, mode: DisplayMode, filter: &Filter, format: OutputFormat) -> Result<()> {
+        let output = self.render(mode, filter, format)?;
+        match writeln!(std::io::stdout(), "{}", output) {
+            Err(e) if e.kind() != std::io::ErrorKind::BrokenPipe => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
 }
 
 /// Get file path and file name and check if it exists
-fn file_exist(file_path: &Path) -> Result<bool> {
+pub fn file_exist(file_path: &Path) -> Result<bool> {
     if file_path.exists() {
         Ok(true)
     } else {
@@ -40,25 +295,273 @@ pub fn read_file(file_path: &PathBuf) -> Result<String> {
     Ok(contents)
 }
 
-/// Write content to a file given its path and name
-pub fn write_file(file_path: &PathBuf, content: &str) -> Result<()> {
+/// Path of the backup copy of a file, the file path with `.bak` appended
+pub fn backup_path(file_path: &Path) -> PathBuf {
+    let mut path = file_path.as_os_str().to_owned();
+    path.push(".bak");
+    PathBuf::from(path)
+}
+
+/// Path of the trash of a data file, the file path with `.trash` appended
+pub fn trash_path(file_path: &Path) -> PathBuf {
+    let mut path = file_path.as_os_str().to_owned();
+    path.push(".trash");
+    PathBuf::from(path)
+}
+
+/// Number of rotating backups kept of a data file when none is configured
+pub const DEFAULT_BACKUPS: usize = 3;
+
+/// Largest number of rotating backups that can be configured
+pub const MAX_BACKUPS: u32 = 100;
+
+/// Path of a rotating backup of a data file, the file path with `.<n>` appended.
+/// Backup 1 is the most recent one.
+pub fn rotated_backup_path(file_path: &Path, n: usize) -> PathBuf {
+    let mut path = file_path.as_os_str().to_owned();
+    path.push(format!(".{}", n));
+    PathBuf::from(path)
+}
+
+/// Returns the numbers of the rotating backups of a data file that exist, most recent first
+pub fn rotated_backups(file_path: &Path) -> Vec<usize> {
+    let (Some(dir), Some(name)) = (file_path.parent(), file_path.file_name()) else {
+        return Vec::new();
+    };
+    let prefix = format!("{}.", name.to_string_lossy());
+    let dir = if dir.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        dir
+    };
+    let mut numbers: Vec<usize> = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .filter_map(|entry| {
+                    let file_name = entry.file_name().to_string_lossy().to_string();
+                    file_name.strip_prefix(&prefix)?.parse().ok()
+                })
+                .filter(|n| *n > 0)
+                .collect()
+        })
+        .unwrap_or_default();
+    numbers.sort();
+    numbers
+}
+
+/// Copy the data file to backup 1, after shifting the existing backups by one.
+/// Only the `count` most recent backups are kept, none when `count` is 0.
+/// The backup is synced to disk like the data file, see `write_bytes`.
+pub fn rotate_backups(file_path: &Path, count: usize) -> Result<()> {
+    let (kept, removed): (Vec<usize>, Vec<usize>) = rotated_backups(file_path)
+        .into_iter()
+        .partition(|n| *n < count);
+    for n in removed {
+        fs::remove_file(rotated_backup_path(file_path, n))?;
+    }
+    if count == 0 || !file_path.exists() {
+        return Ok(());
+    }
+    for n in kept.into_iter().rev() {
+        fs::rename(
+            rotated_backup_path(file_path, n),
+            rotated_backup_path(file_path, n + 1),
+        )?;
+    }
+    let backup = || -> Result<()> {
+        let backup_path = rotated_backup_path(file_path, 1);
+        fs::File::create(&backup_path)?;
+        fs::set_permissions(&backup_path, fs::metadata(file_path)?.permissions())?;
+        write_bytes(&backup_path, &fs::read(file_path)?)
+    };
+    backup().with_context(|| format!("Could not back up '{}'", file_path.display()))
+}
+
+/// Write content to a file given its path and name, see `write_bytes`
+pub fn write_file(file_path: &Path, content: &str) -> Result<()> {
+    write_bytes(file_path, content.as_bytes())
+}
+
+/// Replace the content of an existing file so that a crash leaves either the old or the new
+/// content. The content is written to a temporary file with a unique name next to it, with the
+/// permissions of the file, synced to disk and renamed over the file. The directory is synced
+/// too so the rename is durable.
+pub fn write_bytes(file_path: &Path, content: &[u8]) -> Result<()> {
     file_exist(file_path)?;
-    let mut temp_file_path = file_path.clone();
-    temp_file_path.set_extension("tmp");
-    let mut temp_file = fs::File::create(&temp_file_path)?;
-    temp_file.write_all(content.as_bytes())?;
+    let dir = match file_path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let name = file_path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    let mut temp_file = tempfile::Builder::new()
+        .prefix(&format!(".{}.", name))
+        .suffix(".tmp")
+        .tempfile_in(dir)
+        .with_context(|| format!("Could not create a temporary file in '{}'", dir.display()))?;
+    temp_file.write_all(content)?;
+    fs::set_permissions(temp_file.path(), fs::metadata(file_path)?.permissions())?;
+    temp_file.as_file().sync_all()?;
+    temp_file
+        .persist(file_path)
+        .with_context(|| format!("Could not write file '{}'", file_path.display()))?;
+    sync_dir(dir)
+}
 
-    fs::rename(&temp_file_path, file_path)?;
+/// Flush the entries of a directory to disk, e.g. after renaming a file in it
+pub fn sync_dir(dir: &Path) -> Result<()> {
+    #[cfg(unix)]
+    fs::File::open(dir)?.sync_all()?;
+    #[cfg(not(unix))]
+    let _ = dir;
     Ok(())
 }
 
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
 mod tests {
     use super::*;
     use std::fs;
     use std::io::Write;
     use tempfile::tempdir;
 
+    #[test]
+    fn test_filter_matches() {
+        let date_time = chrono::NaiveDate::from_ymd_opt(2001, 1, 1)
+            .unwrap()
+            .and_hms_opt(1, 1, 1)
+            .unwrap();
+        let pending = Content::new("one", date_time);
+        let mut done = Content::new("two", date_time);
+        done.done = Some(date_time);
+
+        let filter = Filter::default();
+        assert!(filter.matches(&pending));
+        assert!(!filter.matches(&done));
+
+        let filter = Filter {
+            status: Status::Done,
+            ..Default::default()
+        };
+        assert!(!filter.matches(&pending));
+        assert!(filter.matches(&done));
+
+        let filter = Filter {
+            status: Status::All,
+            ..Default::default()
+        };
+        assert!(filter.matches(&pending));
+        assert!(filter.matches(&done));
+    }
+
+    #[test]
+    fn test_filter_matches_tags() {
+        let date_time = chrono::NaiveDate::from_ymd_opt(2001, 1, 1)
+            .unwrap()
+            .and_hms_opt(1, 1, 1)
+            .unwrap();
+        let mut work = Content::new("one", date_time);
+        work.tags = vec!["work".to_string(), "urgent".to_string()];
+        let mut personal = Content::new("two", date_time);
+        personal.tags = vec!["work".to_string(), "personal".to_string()];
+
+        let filter = Filter {
+            include_tags: vec!["work".to_string()],
+            exclude_tags: vec!["personal".to_string()],
+            ..Default::default()
+        };
+        assert!(filter.matches(&work));
+        assert!(!filter.matches(&personal));
+
+        let filter = Filter {
+            include_tags: vec!["work".to_string(), "personal".to_string()],
+            ..Default::default()
+        };
+        assert!(!filter.matches(&work));
+        assert!(filter.matches(&personal));
+    }
+
+    #[test]
+    fn test_filter_upcoming() {
+        let now = chrono::NaiveDate::from_ymd_opt(2001, 1, 1)
+            .unwrap()
+            .and_hms_opt(1, 1, 1)
+            .unwrap();
+        let (from, until) = Filter::upcoming(now, Duration::days(7)).unwrap();
+        assert_eq!(from, now);
+        assert_eq!(until, now + Duration::days(7));
+        assert!(Filter::upcoming(now, Duration::weeks(15000000)).is_err());
+    }
+
+    #[test]
+    fn test_display_mode_from_str() {
+        assert_eq!(
+            DisplayMode::from_str("Priority").unwrap(),
+            DisplayMode::ByPriority
+        );
+        assert_eq!(
+            DisplayMode::from_str("date").unwrap(),
+            DisplayMode::GroupByDate
+        );
+        assert!(DisplayMode::from_str("table").is_err());
+        assert_eq!(DisplayMode::Sorted.to_string(), "sorted");
+    }
+
+    #[test]
+    fn test_duplicate_policy_from_str() {
+        for policy in ["error", "keep-first", "keep-last", "renumber"] {
+            assert_eq!(
+                DuplicatePolicy::from_str(policy).unwrap().to_string(),
+                policy
+            );
+        }
+        assert!(DuplicatePolicy::from_str("ignore").is_err());
+    }
+
+    #[test]
+    fn test_memo_ref_from_str() {
+        assert_eq!(MemoRef::from_str("12").unwrap(), MemoRef::Id(12));
+        assert_eq!(
+            MemoRef::from_str("3F2A-01").unwrap(),
+            MemoRef::Uuid("3f2a01".to_string())
+        );
+        assert!(MemoRef::from_str("0").is_err());
+        assert!(MemoRef::from_str("").is_err());
+        assert!(MemoRef::from_str("-").is_err());
+        assert_eq!(
+            MemoRef::from_str("milk").unwrap_err().to_string(),
+            "Invalid id or UUID prefix 'milk'"
+        );
+    }
+
+    #[test]
+    fn test_memo_ref_resolve() {
+        let uuids = [
+            Uuid::parse_str("3f2a0000-0000-4000-8000-000000000001").unwrap(),
+            Uuid::parse_str("3f2b0000-0000-4000-8000-000000000002").unwrap(),
+            Uuid::parse_str("3f2b1000-0000-4000-8000-000000000003").unwrap(),
+        ];
+        let memos = || [7, 3, 5].into_iter().zip(uuids.iter());
+        let resolve = |s: &str| MemoRef::from_str(s).unwrap().resolve(memos());
+
+        assert_eq!(resolve("3f2a").unwrap(), 7);
+        assert_eq!(resolve("3f2a0000-0000-4000-8000-000000000001").unwrap(), 7);
+        assert_eq!(resolve("3F2B1").unwrap(), 5);
+        assert_eq!(resolve("9").unwrap(), 9);
+        assert_eq!(
+            resolve("3f2b").unwrap_err().to_string(),
+            "UUID prefix '3f2b' matches memos 3, 5"
+        );
+        assert_eq!(
+            resolve("abc").unwrap_err().to_string(),
+            "No memo with a UUID starting with 'abc'"
+        );
+    }
+
     #[test]
     fn test_file_exist() {
         let file_name = "test.txt";
@@ -81,6 +584,22 @@ mod tests {
         assert_eq!(read_file(&file_path).unwrap(), "test\n");
     }
 
+    #[test]
+    fn test_backup_path() {
+        assert_eq!(
+            backup_path(Path::new("/data/memo.txt")),
+            PathBuf::from("/data/memo.txt.bak")
+        );
+    }
+
+    #[test]
+    fn test_trash_path() {
+        assert_eq!(
+            trash_path(Path::new("/data/memo.txt")),
+            PathBuf::from("/data/memo.txt.trash")
+        );
+    }
+
     #[test]
     fn test_write_file() {
         let file_name = "test.txt";
@@ -94,5 +613,54 @@ mod tests {
         let content = "test2\n";
         write_file(&file_path, content).unwrap();
         assert_eq!(read_file(&file_path).unwrap(), content);
+
+        // Only the file itself is left, the temporary file was renamed over it
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+        assert!(write_file(&dir.path().join("missing.txt"), content).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_file_keeps_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("test.txt");
+        fs::write(&file_path, "test").unwrap();
+        fs::set_permissions(&file_path, fs::Permissions::from_mode(0o600)).unwrap();
+
+        write_file(&file_path, "test2").unwrap();
+        let mode = fs::metadata(&file_path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    #[test]
+    fn test_rotate_backups() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("memo.txt");
+        for content in ["one", "two", "three", "four"] {
+            fs::write(&file_path, content).unwrap();
+            rotate_backups(&file_path, 3).unwrap();
+        }
+        fs::write(trash_path(&file_path), "").unwrap();
+
+        assert_eq!(rotated_backups(&file_path), vec![1, 2, 3]);
+        assert_eq!(
+            fs::read_to_string(rotated_backup_path(&file_path, 1)).unwrap(),
+            "four"
+        );
+        assert_eq!(
+            fs::read_to_string(rotated_backup_path(&file_path, 3)).unwrap(),
+            "two"
+        );
+
+        rotate_backups(&file_path, 1).unwrap();
+        assert_eq!(rotated_backups(&file_path), vec![1]);
+        rotate_backups(&file_path, 0).unwrap();
+        assert!(rotated_backups(&file_path).is_empty());
+
+        // Only the existing backups are shifted, whatever the count
+        rotate_backups(&file_path, usize::MAX).unwrap();
+        rotate_backups(&file_path, usize::MAX).unwrap();
+        assert_eq!(rotated_backups(&file_path), vec![1, 2]);
     }
 }
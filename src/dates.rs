@@ -0,0 +1,398 @@
+use anyhow::{anyhow, Context, Result};
+use chrono::prelude::*;
+use chrono::Duration;
+
+/// Time used when an expression gives a date but no time, e.g. `tomorrow`
+pub const DEFAULT_TIME: (u32, u32) = (9, 0);
+
+/// Parse a date expression relative to the current local time.
+/// See `parse_from` for the accepted expressions.
+pub fn parse(expr: &str) -> Result<NaiveDateTime> {
+    parse_from(expr, Local::now().naive_local())
+}
+
+/// Parse a date expression relative to `now`.
+///
+/// Accepted expressions are made of the following tokens, in any combination that makes sense:
+/// - absolute dates and times: `2026-11-01`, `14:00`, `14:00:00`, `9am`, `9:30 pm`
+/// - named days: `now`, `today`, `tomorrow`, `yesterday`, `eod` (end of today), `noon`, `midnight`
+/// - weekdays: `friday`, `next fri` (always the next one after today)
+/// - offsets: `in 3 days`, `2h`, `15 minutes`, `in 1w`
+///
+/// The fillers `at` and `on` are ignored. When a date is given without a time, `DEFAULT_TIME` is used.
+pub fn parse_from(expr: &str, now: NaiveDateTime) -> Result<NaiveDateTime> {
+    let lowered = expr.trim().to_lowercase();
+    let tokens: Vec<&str> = lowered.split_whitespace().collect();
+    if tokens.is_empty() {
+        return Err(anyhow!("Empty date expression"));
+    }
+
+    let invalid = |token: &str| anyhow!("Invalid date '{}': unexpected '{}'", expr.trim(), token);
+
+    let mut date: Option<NaiveDate> = None;
+    let mut time: Option<NaiveTime> = None;
+    let mut offset: Option<Duration> = None;
+    let mut i = 0;
+
+    while i < tokens.len() {
+        let token = tokens[i];
+        let next = tokens.get(i + 1).copied();
+        i += 1;
+
+        match token {
+            "at" | "on" => continue,
+            "now" => {
+                date = Some(now.date());
+                time = Some(now.time());
+            }
+            "today" => date = Some(now.date()),
+            "tomorrow" => date = Some(now.date() + Duration::days(1)),
+            "yesterday" => date = Some(now.date() - Duration::days(1)),
+            "eod" => {
+                date = Some(now.date());
+                time = NaiveTime::from_hms_opt(23, 59, 59);
+            }
+            "noon" => time = NaiveTime::from_hms_opt(12, 0, 0),
+            "midnight" => time = NaiveTime::from_hms_opt(0, 0, 0),
+            "next" => {
+                let weekday = next
+                    .and_then(parse_weekday)
+                    .ok_or_else(|| invalid(next.unwrap_or(token)))?;
+                date = Some(next_weekday(now.date(), weekday));
+                i += 1;
+            }
+            "in" => {
+                let (duration, used) =
+                    parse_offset(&tokens[i..]).ok_or_else(|| invalid(next.unwrap_or(token)))?;
+                offset = offset
+                    .unwrap_or_else(Duration::zero)
+                    .checked_add(&duration)
+                    .map(Some)
+                    .ok_or_else(|| invalid(next.unwrap_or(token)))?;
+                i += used;
+            }
+            _ => {
+                if let Some(weekday) = parse_weekday(token) {
+                    date = Some(next_weekday(now.date(), weekday));
+                } else if let Ok(d) = NaiveDate::parse_from_str(token, "%Y-%m-%d") {
+                    date = Some(d);
+                } else if let Some((t, used)) = parse_time(&tokens[i - 1..]) {
+                    time = Some(t);
+                    i += used - 1;
+                } else if let Some((duration, used)) = parse_offset(&tokens[i - 1..]) {
+                    offset = offset
+                        .unwrap_or_else(Duration::zero)
+                        .checked_add(&duration)
+                        .map(Some)
+                        .ok_or_else(|| invalid(token))?;
+                    i += used - 1;
+                } else {
+                    return Err(invalid(token));
+                }
+            }
+        }
+    }
+
+    if let Some(offset) = offset {
+        if date.is_some() {
+            return Err(anyhow!(
+                "Invalid date '{}': cannot combine a date with an offset",
+                expr.trim()
+            ));
+        }
+        let moved = now
+            .checked_add_signed(offset)
+            .with_context(|| format!("Invalid date '{}': out of range", expr.trim()))?;
+        return Ok(match time {
+            Some(time) => moved.date().and_time(time),
+            None => moved,
+        });
+    }
+
+    let time = match (date, time) {
+        (_, Some(time)) => time,
+        (Some(_), None) => NaiveTime::from_hms_opt(DEFAULT_TIME.0, DEFAULT_TIME.1, 0)
+            .context("Invalid default time")?,
+        (None, None) => now.time(),
+    };
+    Ok(date.unwrap_or(now.date()).and_time(time))
+}
+
+/// Parse a duration such as `15m`, `2h`, `7d` or `1 week 2 days`, with the units of the
+/// offsets accepted by `parse_from`
+pub fn parse_duration(expr: &str) -> Result<Duration> {
+    let lowered = expr.trim().to_lowercase();
+    let tokens: Vec<&str> = lowered.split_whitespace().collect();
+    if tokens.is_empty() {
+        return Err(anyhow!("Empty duration"));
+    }
+
+    let mut duration = Duration::zero();
+    let mut i = 0;
+    while i < tokens.len() {
+        let invalid = || {
+            anyhow!(
+                "Invalid duration '{}': unexpected '{}'",
+                expr.trim(),
+                tokens[i]
+            )
+        };
+        let (offset, used) = parse_offset(&tokens[i..]).ok_or_else(invalid)?;
+        duration = duration.checked_add(&offset).ok_or_else(invalid)?;
+        i += used;
+    }
+    Ok(duration)
+}
+
+/// How long a memo is put off by `memo snooze`: for a duration, or until a date and time
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Postpone {
+    For(Duration),
+    Until(NaiveDateTime),
+}
+
+impl Postpone {
+    /// Returns the date and time the memo is put off to, a duration being counted from `from`.
+    /// None if it is out of the range of dates.
+    pub fn from(&self, from: NaiveDateTime) -> Option<NaiveDateTime> {
+        match self {
+            Postpone::For(duration) => from.checked_add_signed(*duration),
+            Postpone::Until(date_time) => Some(*date_time),
+        }
+    }
+}
+
+/// Parse a duration such as `15m` or `2h`, or else a date expression such as `tomorrow`
+/// relative to `now`, see `parse_duration` and `parse_from`
+pub fn parse_postpone(expr: &str, now: NaiveDateTime) -> Result<Postpone> {
+    if let Ok(duration) = parse_duration(expr) {
+        return Ok(Postpone::For(duration));
+    }
+    parse_from(expr, now).map(Postpone::Until).map_err(|_| {
+        anyhow!(
+            "Invalid duration or date '{}'. Expected e.g. 15m, 2h or tomorrow",
+            expr.trim()
+        )
+    })
+}
+
+/// Parse a time of day such as `9:30`, `14:00` or `9am`
+pub fn parse_time_of_day(expr: &str) -> Result<NaiveTime> {
+    let lowered = expr.trim().to_lowercase();
+    let tokens: Vec<&str> = lowered.split_whitespace().collect();
+    match parse_time(&tokens) {
+        Some((time, used)) if used == tokens.len() => Ok(time),
+        _ => Err(anyhow!(
+            "Invalid time '{}'. Expected e.g. 9:30, 14:00 or 9am",
+            expr.trim()
+        )),
+    }
+}
+
+/// Parse a weekday name, full or abbreviated
+pub(crate) fn parse_weekday(token: &str) -> Option<Weekday> {
+    match token {
+        "monday" | "mon" => Some(Weekday::Mon),
+        "tuesday" | "tue" | "tues" => Some(Weekday::Tue),
+        "wednesday" | "wed" => Some(Weekday::Wed),
+        "thursday" | "thu" | "thurs" => Some(Weekday::Thu),
+        "friday" | "fri" => Some(Weekday::Fri),
+        "saturday" | "sat" => Some(Weekday::Sat),
+        "sunday" | "sun" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+/// Returns the first date after `date` that falls on `weekday`
+fn next_weekday(date: NaiveDate, weekday: Weekday) -> NaiveDate {
+    let days = (7 + weekday.num_days_from_monday() - date.weekday().num_days_from_monday()) % 7;
+    date + Duration::days(if days == 0 { 7 } else { days as i64 })
+}
+
+/// Parse a time at the start of `tokens`, returning it with the number of tokens used
+/// Accepts `14:00`, `14:00:00`, `9am`, `9:30pm` and `9 am`.
+fn parse_time(tokens: &[&str]) -> Option<(NaiveTime, usize)> {
+    let token = tokens.first()?;
+    let (clock, meridiem, used) = if let Some(clock) = token.strip_suffix("am") {
+        (clock, Some(false), 1)
+    } else if let Some(clock) = token.strip_suffix("pm") {
+        (clock, Some(true), 1)
+    } else {
+        match tokens.get(1) {
+            Some(&"am") => (*token, Some(false), 2),
+            Some(&"pm") => (*token, Some(true), 2),
+            _ => (*token, None, 1),
+        }
+    };
+
+    let mut parts = clock.split(':');
+    let hour: u32 = parts.next()?.parse().ok()?;
+    let minute: u32 = parts.next().map_or(Some(0), |m| m.parse().ok())?;
+    let second: u32 = parts.next().map_or(Some(0), |s| s.parse().ok())?;
+    if parts.next().is_some() || (meridiem.is_none() && !clock.contains(':')) {
+        return None;
+    }
+
+    let hour = match meridiem {
+        Some(_) if !(1..=12).contains(&hour) => return None,
+        Some(false) => hour % 12,
+        Some(true) => hour % 12 + 12,
+        None => hour,
+    };
+    Some((NaiveTime::from_hms_opt(hour, minute, second)?, used))
+}
+
+/// Parse an offset at the start of `tokens`, returning it with the number of tokens used
+/// Accepts `3 days`, `3d`, `2h`, `15 minutes` and `1w`.
+fn parse_offset(tokens: &[&str]) -> Option<(Duration, usize)> {
+    let token = tokens.first()?;
+    let split = token
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(token.len());
+    let (amount, unit, used) = match token.split_at(split) {
+        (amount, "") => (amount, *tokens.get(1)?, 2),
+        (amount, unit) => (amount, unit, 1),
+    };
+    let amount: i64 = amount.parse().ok()?;
+
+    let duration = match unit {
+        "m" | "min" | "mins" | "minute" | "minutes" => Duration::try_minutes(amount)?,
+        "h" | "hr" | "hrs" | "hour" | "hours" => Duration::try_hours(amount)?,
+        "d" | "day" | "days" => Duration::try_days(amount)?,
+        "w" | "week" | "weeks" => Duration::try_weeks(amount)?,
+        _ => return None,
+    };
+    Some((duration, used))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Saturday, 2026-10-17 15:30:00
+    fn now() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 10, 17)
+            .unwrap()
+            .and_hms_opt(15, 30, 0)
+            .unwrap()
+    }
+
+    fn parse_str(expr: &str) -> String {
+        parse_from(expr, now())
+            .expect("Error parsing date expression")
+            .format("%Y-%m-%d %H:%M:%S")
+            .to_string()
+    }
+
+    #[test]
+    fn test_parse_absolute() {
+        assert_eq!(parse_str("2026-11-01 14:00"), "2026-11-01 14:00:00");
+        assert_eq!(parse_str("2026-11-01 14:00:30"), "2026-11-01 14:00:30");
+        assert_eq!(parse_str("2026-11-01"), "2026-11-01 09:00:00");
+        assert_eq!(parse_str("14:00"), "2026-10-17 14:00:00");
+    }
+
+    #[test]
+    fn test_parse_named_days() {
+        assert_eq!(parse_str("now"), "2026-10-17 15:30:00");
+        assert_eq!(parse_str("today"), "2026-10-17 09:00:00");
+        assert_eq!(parse_str("tomorrow 9am"), "2026-10-18 09:00:00");
+        assert_eq!(parse_str("Tomorrow at 9:30 pm"), "2026-10-18 21:30:00");
+        assert_eq!(parse_str("yesterday noon"), "2026-10-16 12:00:00");
+        assert_eq!(parse_str("eod"), "2026-10-17 23:59:59");
+        assert_eq!(parse_str("12am"), "2026-10-17 00:00:00");
+    }
+
+    #[test]
+    fn test_parse_weekdays() {
+        assert_eq!(parse_str("next friday"), "2026-10-23 09:00:00");
+        assert_eq!(parse_str("mon 8am"), "2026-10-19 08:00:00");
+        assert_eq!(parse_str("saturday"), "2026-10-24 09:00:00");
+    }
+
+    #[test]
+    fn test_parse_offsets() {
+        assert_eq!(parse_str("in 3 days"), "2026-10-20 15:30:00");
+        assert_eq!(parse_str("in 2h"), "2026-10-17 17:30:00");
+        assert_eq!(parse_str("15m"), "2026-10-17 15:45:00");
+        assert_eq!(parse_str("1w"), "2026-10-24 15:30:00");
+        assert_eq!(parse_str("in 1 day at 9am"), "2026-10-18 09:00:00");
+
+        assert_eq!(
+            parse_from("in 99999999999999 days", now())
+                .unwrap_err()
+                .to_string(),
+            "Invalid date 'in 99999999999999 days': unexpected '99999999999999'"
+        );
+        assert!(parse_from("99999999999w", now()).is_err());
+        assert!(parse_from("in 15000000000w 15000000000w", now()).is_err());
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("15m").unwrap(), Duration::minutes(15));
+        assert_eq!(parse_duration("7d").unwrap(), Duration::days(7));
+        assert_eq!(parse_duration("1 week 2 days").unwrap(), Duration::days(9));
+        assert!(parse_duration("").is_err());
+        assert_eq!(
+            parse_duration("2 fortnights").unwrap_err().to_string(),
+            "Invalid duration '2 fortnights': unexpected '2'"
+        );
+    }
+
+    #[test]
+    fn test_parse_postpone() {
+        let now = NaiveDate::from_ymd_opt(2024, 1, 10)
+            .unwrap()
+            .and_hms_opt(15, 0, 0)
+            .unwrap();
+        let postpone = parse_postpone("2h", now).unwrap();
+        assert_eq!(postpone, Postpone::For(Duration::hours(2)));
+        assert_eq!(postpone.from(now), Some(now + Duration::hours(2)));
+        assert_eq!(
+            parse_postpone("tomorrow", now).unwrap().from(now),
+            NaiveDate::from_ymd_opt(2024, 1, 11)
+                .unwrap()
+                .and_hms_opt(9, 0, 0)
+        );
+        let postpone = parse_postpone("15000000w", now).unwrap();
+        assert_eq!(postpone.from(now), None);
+        assert!(parse_duration("15000000000w 15000000000w").is_err());
+        assert_eq!(
+            parse_postpone("later", now).unwrap_err().to_string(),
+            "Invalid duration or date 'later'. Expected e.g. 15m, 2h or tomorrow"
+        );
+    }
+
+    #[test]
+    fn test_parse_time_of_day() {
+        assert_eq!(
+            parse_time_of_day("9:30").unwrap(),
+            NaiveTime::from_hms_opt(9, 30, 0).unwrap()
+        );
+        assert_eq!(
+            parse_time_of_day("9 pm").unwrap(),
+            NaiveTime::from_hms_opt(21, 0, 0).unwrap()
+        );
+        assert!(parse_time_of_day("9").is_err());
+        assert!(parse_time_of_day("9:30 tomorrow").is_err());
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        let err = parse_from("tomorrow at nine", now()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid date 'tomorrow at nine': unexpected 'nine'"
+        );
+        let err = parse_from("next week", now()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid date 'next week': unexpected 'week'"
+        );
+        assert!(parse_from("", now()).is_err());
+        assert!(parse_from("13pm", now()).is_err());
+        assert!(parse_from("in 3 fortnights", now()).is_err());
+        assert!(parse_from("2026-11-01 in 2h", now()).is_err());
+    }
+}
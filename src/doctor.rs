@@ -0,0 +1,240 @@
+use crate::data::{read_file, rotate_backups, sync_dir, write_file, DuplicatePolicy};
+use crate::jsonl;
+use crate::models::MemoData;
+use crate::ATTRIBUTE_DATE_TIME_FORMAT;
+use anyhow::{Context, Result};
+use chrono::Local;
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Path of the quarantine of a data file, the file path with `.quarantine` appended.
+/// Lines moved out of the data file by `memo doctor --fix` are appended to it.
+pub fn quarantine_path(file_path: &Path) -> PathBuf {
+    let mut path = file_path.as_os_str().to_owned();
+    path.push(".quarantine");
+    PathBuf::from(path)
+}
+
+/// A problem found on a line of a data file, numbered from 1
+#[derive(Clone, Debug, PartialEq)]
+pub struct Problem {
+    pub number: usize,
+    pub line: String,
+    pub message: String,
+    /// Line written instead by `fix`, e.g. with a new id. None to move it to the quarantine.
+    pub replacement: Option<String>,
+}
+
+/// Returns the problems of the data of a text data file, in the order of the lines:
+/// lines that cannot be read, e.g. with a bad date or a missing content, and lines whose id is
+/// already used by another line, which are dropped or renumbered as the policy says.
+pub fn diagnose(data: &str, policy: DuplicatePolicy) -> Result<Vec<Problem>> {
+    let is_jsonl = jsonl::is_jsonl(data);
+    let mut memo_data = MemoData::new();
+    if is_jsonl {
+        memo_data.set_lines(jsonl::parse_lines(data)?, policy);
+    } else {
+        memo_data.set_lines(MemoData::parse_lines(data), policy);
+    }
+
+    let mut problems: Vec<Problem> = memo_data
+        .bad_lines
+        .into_iter()
+        .map(|bad_line| Problem {
+            number: bad_line.number,
+            line: bad_line.line,
+            message: bad_line.reason,
+            replacement: None,
+        })
+        .collect();
+    for duplicate in memo_data.duplicates {
+        let replacement = match duplicate.new_id {
+            Some(id) if is_jsonl => Some(jsonl::with_id(&duplicate.line, id)?),
+            Some(id) => duplicate
+                .line
+                .split_once(':')
+                .map(|(_, rest)| format!("{}:{}", id, rest)),
+            None => None,
+        };
+        problems.push(Problem {
+            number: duplicate.number,
+            message: duplicate.to_string(),
+            line: duplicate.line,
+            replacement,
+        });
+    }
+    problems.sort_by_key(|problem| problem.number);
+    Ok(problems)
+}
+
+/// Returns the data with the lines of the problems replaced, or removed when they have no
+/// replacement
+pub fn without_problems(data: &str, problems: &[Problem]) -> String {
+    let problems: HashMap<usize, &Problem> = problems
+        .iter()
+        .map(|problem| (problem.number, problem))
+        .collect();
+    let mut result = String::new();
+    for (number, line) in data.lines().enumerate() {
+        let line = match problems.get(&(number + 1)) {
+            Some(problem) => match &problem.replacement {
+                Some(replacement) => replacement,
+                None => continue,
+            },
+            None => line,
+        };
+        result.push_str(line);
+        result.push('\n');
+    }
+    result
+}
+
+/// Fix the lines of a data file that have problems, and return them. Renumbered lines are
+/// rewritten in place, the others are moved to the quarantine of the data file.
+/// Each line is appended to the quarantine after a comment with the date and the problem,
+/// before the data file is written without them, so a line is never lost.
+pub fn fix(file_path: &Path, backups: usize, policy: DuplicatePolicy) -> Result<Vec<Problem>> {
+    let data = read_file(&file_path.to_path_buf())?;
+    let problems = diagnose(&data, policy)?;
+    if problems.is_empty() {
+        return Ok(problems);
+    }
+
+    let now = Local::now().naive_local();
+    let mut quarantined = String::new();
+    for problem in problems
+        .iter()
+        .filter(|problem| problem.replacement.is_none())
+    {
+        quarantined.push_str(&format!(
+            "# {} line {}: {}\n{}\n",
+            now.format(ATTRIBUTE_DATE_TIME_FORMAT),
+            problem.number,
+            problem.message,
+            problem.line
+        ));
+    }
+    if !quarantined.is_empty() {
+        append_quarantine(file_path, &quarantined)?;
+    }
+
+    rotate_backups(file_path, backups)?;
+    write_file(file_path, &without_problems(&data, &problems))?;
+    Ok(problems)
+}
+
+/// Append lines to the quarantine of a data file and sync it to disk
+fn append_quarantine(file_path: &Path, lines: &str) -> Result<()> {
+    let path = quarantine_path(file_path);
+    let mut quarantine = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .with_context(|| format!("Could not open quarantine '{}'", path.display()))?;
+    quarantine.write_all(lines.as_bytes())?;
+    quarantine.sync_all()?;
+    match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => sync_dir(dir),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    const DATA: &str = "1: 2001-01-01 01:01:01 one\n2: 2001-13-01 01:01:01 bad date\n\n3:\n1: 2003-03-03 03:03:03 one again\nfour\n";
+
+    fn numbers(problems: &[Problem]) -> Vec<usize> {
+        problems.iter().map(|problem| problem.number).collect()
+    }
+
+    #[test]
+    fn test_diagnose() {
+        let problems = diagnose(DATA, DuplicatePolicy::Error).unwrap();
+        assert_eq!(numbers(&problems), vec![2, 4, 5, 6]);
+        assert!(problems[0].message.contains("invalid date time"));
+        assert_eq!(problems[1].message, "Missing content in line");
+        assert_eq!(
+            problems[2].message,
+            "Duplicate id 1, already used on line 1"
+        );
+        assert!(problems[3].message.contains("Invalid id"));
+
+        let problems = diagnose(DATA, DuplicatePolicy::KeepLast).unwrap();
+        assert_eq!(numbers(&problems), vec![1, 2, 4, 6]);
+        assert_eq!(
+            problems[0].message,
+            "Duplicate id 1 dropped, line 5 keeps it"
+        );
+
+        let problems = diagnose(DATA, DuplicatePolicy::Renumber).unwrap();
+        assert_eq!(
+            problems[2].message,
+            "Duplicate id 1 renumbered to 2, line 1 keeps it"
+        );
+        assert_eq!(
+            problems[2].replacement.as_deref(),
+            Some("2: 2003-03-03 03:03:03 one again")
+        );
+
+        assert!(
+            diagnose("1: 2001-01-01 01:01:01 one\n", DuplicatePolicy::Error)
+                .unwrap()
+                .is_empty()
+        );
+
+        let jsonl = "{\"format\":\"memo\",\"version\":1}\n{\"id\":1,\"text\":\"one\",\"created\":\"2001-01-01T01:01:01\"}\n{\"id\":2}\n{\"id\":1,\"text\":\"two\",\"created\":\"2001-01-01T01:01:01\"}\n";
+        let problems = diagnose(jsonl, DuplicatePolicy::Renumber).unwrap();
+        assert_eq!(numbers(&problems), vec![3, 4]);
+        assert!(problems[1]
+            .replacement
+            .as_deref()
+            .unwrap()
+            .starts_with("{\"id\":2,"));
+    }
+
+    #[test]
+    fn test_fix() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("memo.txt");
+        fs::write(&path, DATA).unwrap();
+
+        assert_eq!(fix(&path, 1, DuplicatePolicy::KeepLast).unwrap().len(), 4);
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "\n1: 2003-03-03 03:03:03 one again\n"
+        );
+        let quarantine = fs::read_to_string(quarantine_path(&path)).unwrap();
+        assert!(quarantine.contains("line 2: invalid date time"));
+        assert!(quarantine.contains("\n2: 2001-13-01 01:01:01 bad date\n"));
+        assert!(quarantine.contains("\nfour\n"));
+        assert_eq!(
+            fs::read_to_string(crate::data::rotated_backup_path(&path, 1)).unwrap(),
+            DATA
+        );
+
+        assert!(fix(&path, 1, DuplicatePolicy::KeepLast).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_fix_renumber() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("memo.txt");
+        fs::write(
+            &path,
+            "1: 2001-01-01 01:01:01 one\n1: 2002-02-02 02:02:02 two\n",
+        )
+        .unwrap();
+
+        assert_eq!(fix(&path, 0, DuplicatePolicy::Renumber).unwrap().len(), 1);
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "1: 2001-01-01 01:01:01 one\n2: 2002-02-02 02:02:02 two\n"
+        );
+        assert!(!quarantine_path(&path).exists());
+    }
+}
@@ -0,0 +1,375 @@
+use crate::data::{write_file, DataFile};
+use crate::jsonl::Record;
+use crate::models::Content;
+use anyhow::{anyhow, Context, Result};
+use chrono::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Number of steps kept in the history, older steps are dropped
+pub const MAX_STEPS: usize = 100;
+
+/// Path of the history of a data file, the file path with `.history` appended
+pub fn history_path(file_path: &Path) -> PathBuf {
+    let mut path = file_path.as_os_str().to_owned();
+    path.push(".history");
+    PathBuf::from(path)
+}
+
+/// A memo before and after a change, None if it did not exist
+#[derive(Serialize, Deserialize)]
+struct Change {
+    id: u32,
+    before: Option<Record>,
+    after: Option<Record>,
+}
+
+/// A memo given another id, e.g. by `memo renumber`
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Move {
+    pub from: u32,
+    pub to: u32,
+}
+
+/// A change made by a single command, e.g. removing several memos.
+/// The ids given to memos are kept in `moves`, so the old ids can be translated.
+#[derive(Serialize, Deserialize)]
+pub struct Step {
+    pub action: String,
+    pub time: NaiveDateTime,
+    changes: Vec<Change>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    moves: Vec<Move>,
+}
+
+impl Step {
+    /// Create an empty step for an action, e.g. "add" or "remove"
+    pub fn new(action: &str) -> Self {
+        Step {
+            action: action.to_string(),
+            time: Local::now().naive_local(),
+            changes: Vec::new(),
+            moves: Vec::new(),
+        }
+    }
+
+    /// Record the change of a memo, given a copy of it before and after the change
+    pub fn push(&mut self, id: u32, before: Option<&Content>, after: Option<&Content>) {
+        self.changes.push(Change {
+            id,
+            before: before.map(|content| Record::new(id, content)),
+            after: after.map(|content| Record::new(id, content)),
+        });
+    }
+
+    /// Record that a memo was given another id
+    pub fn push_move(&mut self, from: u32, to: u32) {
+        self.moves.push(Move { from, to });
+    }
+
+    /// Returns the ids given to memos in the step
+    pub fn moves(&self) -> &[Move] {
+        &self.moves
+    }
+
+    /// Returns the ids changed in the step, in the order they were changed,
+    /// followed by the old ids of the memos that were moved
+    pub fn ids(&self) -> Vec<u32> {
+        self.changes
+            .iter()
+            .map(|change| change.id)
+            .chain(self.moves.iter().map(|item| item.from))
+            .collect()
+    }
+
+    /// Returns true if the step has no changes
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty() && self.moves.is_empty()
+    }
+}
+
+/// History of the changes made to a data file, stored next to it.
+/// The steps before `position` are done and can be undone, the ones after it were undone
+/// and can be redone. Recording a new step drops the steps that can be redone.
+#[derive(Serialize, Deserialize)]
+pub struct Journal {
+    #[serde(skip)]
+    path: PathBuf,
+    position: usize,
+    steps: Vec<Step>,
+}
+
+impl Journal {
+    /// Load the history of a data file, empty if it has none yet
+    pub fn load(file_path: &Path) -> Result<Self> {
+        let path = history_path(file_path);
+        let mut journal = if path.exists() {
+            let data = fs::read_to_string(&path)?;
+            serde_json::from_str::<Journal>(&data)
+                .with_context(|| format!("Invalid history file '{}'", path.display()))?
+        } else {
+            Journal {
+                path: PathBuf::new(),
+                position: 0,
+                steps: Vec::new(),
+            }
+        };
+        journal.position = journal.position.min(journal.steps.len());
+        journal.path = path;
+        Ok(journal)
+    }
+
+    /// Write the history next to its data file
+    pub fn save(&self) -> Result<()> {
+        if !self.path.exists() {
+            fs::File::create(&self.path).with_context(|| {
+                format!("Could not create history file '{}'", self.path.display())
+            })?;
+        }
+        write_file(&self.path, &serde_json::to_string(self)?)
+            .with_context(|| format!("Could not write history file '{}'", self.path.display()))
+    }
+
+    /// Add a step after the current position, dropping the steps that could be redone
+    pub fn record(&mut self, step: Step) {
+        if step.is_empty() {
+            return;
+        }
+        self.steps.truncate(self.position);
+        self.steps.push(step);
+        if self.steps.len() > MAX_STEPS {
+            self.steps.drain(..self.steps.len() - MAX_STEPS);
+        }
+        self.position = self.steps.len();
+    }
+
+    /// Undo the last done step, returning it. None if there is nothing to undo.
+    pub fn undo(&mut self, d: &mut impl DataFile) -> Result<Option<&Step>> {
+        if self.position == 0 {
+            return Ok(None);
+        }
+        let step = &self.steps[self.position - 1];
+        for change in &step.changes {
+            check(d, change.id, &change.after)?;
+        }
+        let moves: Vec<(u32, u32)> = step.moves.iter().map(|item| (item.to, item.from)).collect();
+        d.renumber(&moves)?;
+        for change in step.changes.iter().rev() {
+            apply(d, change.id, &change.before)?;
+        }
+        self.position -= 1;
+        Ok(Some(&self.steps[self.position]))
+    }
+
+    /// Redo the last undone step, returning it. None if there is nothing to redo.
+    pub fn redo(&mut self, d: &mut impl DataFile) -> Result<Option<&Step>> {
+        if self.position == self.steps.len() {
+            return Ok(None);
+        }
+        let step = &self.steps[self.position];
+        for change in &step.changes {
+            check(d, change.id, &change.before)?;
+        }
+        for change in &step.changes {
+            apply(d, change.id, &change.after)?;
+        }
+        let moves: Vec<(u32, u32)> = step.moves.iter().map(|item| (item.from, item.to)).collect();
+        d.renumber(&moves)?;
+        self.position += 1;
+        Ok(Some(&self.steps[self.position - 1]))
+    }
+}
+
+/// Check that a memo is in the state the history expects before a change, None if it is
+/// expected not to exist. A memo changed outside of the history is left as it is.
+fn check(d: &impl DataFile, id: u32, expected: &Option<Record>) -> Result<()> {
+    let current = d.get(id).map(|content| Record::new(id, content));
+    match (&current, expected) {
+        (Some(_), None) => Err(anyhow!("Id '{}' is already in use", id)),
+        (Some(current), Some(expected)) if current.same_memo(expected) => Ok(()),
+        (None, None) => Ok(()),
+        _ => Err(anyhow!(
+            "Memo {} was changed since, it is left as it is",
+            id
+        )),
+    }
+}
+
+/// Bring a memo to the given state, removing it if the state is None
+fn apply(d: &mut impl DataFile, id: u32, state: &Option<Record>) -> Result<()> {
+    match state {
+        Some(record) => {
+            let (_, content) = record.clone().into_content()?;
+            d.insert(id, content)
+        }
+        None => d.remove(id),
+    }
+}
+
+/// Record a step in the history of a data file
+pub fn record(file_path: &Path, step: Step) -> Result<()> {
+    if step.is_empty() {
+        return Ok(());
+    }
+    let mut journal = Journal::load(file_path)?;
+    journal.record(step);
+    journal.save()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::MemoData;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_history_path() {
+        assert_eq!(
+            history_path(Path::new("/data/memo.txt")),
+            PathBuf::from("/data/memo.txt.history")
+        );
+    }
+
+    #[test]
+    fn test_journal_undo_redo() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("memo.txt");
+        let mut d = MemoData::new();
+
+        d.add(1, "one").unwrap();
+        d.add(2, "two").unwrap();
+        let mut step = Step::new("add");
+        step.push(1, None, d.get(1));
+        step.push(2, None, d.get(2));
+        record(&file_path, step).unwrap();
+
+        let before = d.get(1).cloned();
+        d.update(1, "uno").unwrap();
+        let mut step = Step::new("edit");
+        step.push(1, before.as_ref(), d.get(1));
+        record(&file_path, step).unwrap();
+
+        let mut journal = Journal::load(&file_path).unwrap();
+        assert_eq!(journal.undo(&mut d).unwrap().unwrap().action, "edit");
+        assert_eq!(d.get(1).unwrap().text, "one");
+        assert!(d.get(1).unwrap().updated_at.is_none());
+
+        assert_eq!(journal.undo(&mut d).unwrap().unwrap().ids(), vec![1, 2]);
+        assert!(d.sorted_ids().is_empty());
+        assert!(journal.undo(&mut d).unwrap().is_none());
+
+        assert_eq!(journal.redo(&mut d).unwrap().unwrap().action, "add");
+        assert_eq!(d.sorted_ids(), vec![1, 2]);
+        journal.save().unwrap();
+
+        let mut journal = Journal::load(&file_path).unwrap();
+        assert_eq!(journal.redo(&mut d).unwrap().unwrap().action, "edit");
+        assert_eq!(d.get(1).unwrap().text, "uno");
+        assert!(journal.redo(&mut d).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_journal_moves() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("memo.txt");
+        let mut d = MemoData::new();
+        d.add(2, "two").unwrap();
+        d.add(5, "five").unwrap();
+
+        d.renumber(&[(2, 1), (5, 2)]).unwrap();
+        let mut step = Step::new("renumber");
+        step.push_move(2, 1);
+        step.push_move(5, 2);
+        record(&file_path, step).unwrap();
+
+        let mut journal = Journal::load(&file_path).unwrap();
+        let step = journal.undo(&mut d).unwrap().unwrap();
+        assert_eq!(
+            step.moves(),
+            &[Move { from: 2, to: 1 }, Move { from: 5, to: 2 }]
+        );
+        assert_eq!(step.ids(), vec![2, 5]);
+        assert_eq!(d.get(2).unwrap().text, "two");
+        assert_eq!(d.get(5).unwrap().text, "five");
+
+        journal.redo(&mut d).unwrap();
+        assert_eq!(d.sorted_ids(), vec![1, 2]);
+        assert_eq!(d.get(2).unwrap().text, "five");
+    }
+
+    #[test]
+    fn test_journal_record_drops_redo() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("memo.txt");
+        let mut d = MemoData::new();
+
+        d.add(1, "one").unwrap();
+        let mut step = Step::new("add");
+        step.push(1, None, d.get(1));
+        let mut journal = Journal::load(&file_path).unwrap();
+        journal.record(step);
+        journal.undo(&mut d).unwrap();
+
+        journal.record(Step::new("empty"));
+        assert_eq!(journal.steps.len(), 1);
+
+        d.add(1, "uno").unwrap();
+        let mut step = Step::new("add");
+        step.push(1, None, d.get(1));
+        journal.record(step);
+        assert_eq!(journal.steps.len(), 1);
+        assert!(journal.redo(&mut d).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_journal_undo_conflict() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("memo.txt");
+        let mut d = MemoData::new();
+
+        d.add(1, "one").unwrap();
+        let before = d.get(1).cloned();
+        d.remove(1).unwrap();
+        let mut step = Step::new("remove");
+        step.push(1, before.as_ref(), None);
+        let mut journal = Journal::load(&file_path).unwrap();
+        journal.record(step);
+
+        d.add(1, "another one").unwrap();
+        assert!(journal.undo(&mut d).is_err());
+        assert_eq!(d.get(1).unwrap().text, "another one");
+    }
+
+    #[test]
+    fn test_journal_changed_since() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("memo.txt");
+        let mut d = MemoData::new();
+
+        d.add(1, "one").unwrap();
+        let before = d.get(1).cloned();
+        d.update(1, "uno").unwrap();
+        let mut step = Step::new("edit");
+        step.push(1, before.as_ref(), d.get(1));
+        let mut journal = Journal::load(&file_path).unwrap();
+        journal.record(step);
+
+        // A change that is not in the history is not rolled back
+        d.set_done(1, true).unwrap();
+        let err = journal.undo(&mut d).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "Memo 1 was changed since, it is left as it is"
+        );
+        assert_eq!(d.get(1).unwrap().text, "uno");
+        assert!(d.get(1).unwrap().done.is_some());
+
+        d.set_done(1, false).unwrap();
+        assert_eq!(journal.undo(&mut d).unwrap().unwrap().action, "edit");
+        assert_eq!(d.get(1).unwrap().text, "one");
+        d.set_done(1, true).unwrap();
+        assert!(journal.redo(&mut d).is_err());
+        assert_eq!(d.get(1).unwrap().text, "one");
+    }
+}
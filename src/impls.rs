@@ -1,19 +1,64 @@
 use crate::app;
-use crate::data::{read_file, DataFile, DisplayMode};
-use crate::models::{Content, MemoData};
+use crate::data::{
+    read_file, rotate_backups, trash_path, write_file, DataFile, DisplayMode, Filter, Format,
+};
+use crate::jsonl;
+use crate::models::{normalize_tag, BadLine, Content, DuplicateLine, MemoData, Priority, Trashed};
+use crate::output::{self, OutputFormat};
+use crate::recur::Recurrence;
+use crate::search::Query;
 use anyhow::{anyhow, Context, Result};
 use chrono::prelude::*;
+use std::collections::HashSet;
 use std::fmt;
+use uuid::Uuid;
 
 /// Date time format used in Content
 pub const DATE_TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
 
+/// Date time format used in the attributes of Content, it has no spaces
+pub const ATTRIBUTE_DATE_TIME_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+
+/// Keys allowed in the attributes block of Content
+const ATTRIBUTE_KEYS: [&str; 8] = [
+    "due", "done", "tags", "prio", "every", "snoozed", "updated", "uuid",
+];
+
+/// Split the attributes block from the text of a memo
+/// The block has the format `[key:value key:value uuid:value] text`. Every line written with a
+/// block has a uuid, so None is returned if the text does not start with a valid block with a
+/// uuid, and lines of older files where the text starts with e.g. `[due:friday]` are read as text.
+fn split_attributes(s: &str) -> Option<(Vec<(&str, &str)>, &str)> {
+    let (block, text) = s.strip_prefix('[')?.split_once("] ")?;
+    let attributes: Vec<_> = block
+        .split_whitespace()
+        .map(|attribute| attribute.split_once(':'))
+        .collect::<Option<_>>()?;
+
+    if !attributes.iter().any(|(key, _)| *key == "uuid")
+        || attributes
+            .iter()
+            .any(|(key, _)| !ATTRIBUTE_KEYS.contains(key))
+    {
+        return None;
+    }
+    Some((attributes, text.trim()))
+}
+
+/// Parse a date time stored in an attribute
+fn parse_attribute_date_time(key: &str, value: &str) -> Result<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(value, ATTRIBUTE_DATE_TIME_FORMAT)
+        .with_context(|| format!("invalid {} date time '{}'", key, value))
+}
+
 /// Implement FromStr trait for Content
 impl std::str::FromStr for Content {
     type Err = anyhow::Error;
 
     /// Create a Content struct from a string
-    /// String format: %Y-%m-%d %H:%M:%S content
+    /// String format: %Y-%m-%d %H:%M:%S [attributes] content
+    /// The attributes block is optional, e.g. `[due:2024-01-01T09:00:00]`
+    /// Without a uuid attribute, the memo is given a new UUID, stored when it is written
     fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
         let parts: Vec<_> = s.trim().splitn(3, ' ').collect();
 
@@ -29,25 +74,123 @@ impl std::str::FromStr for Content {
         let date_time = NaiveDateTime::parse_from_str(&date_time, DATE_TIME_FORMAT)
             .with_context(|| format!("invalid date time '{}'", date_time))?;
 
-        let content = parts[2];
+        let mut content = Content::new(parts[2], date_time);
+        let mut uuid = None;
+
+        if let Some((attributes, text)) = split_attributes(parts[2]) {
+            content.text = text.to_string();
+            for (key, value) in attributes {
+                match key {
+                    "due" => content.due = Some(parse_attribute_date_time(key, value)?),
+                    "done" => content.done = Some(parse_attribute_date_time(key, value)?),
+                    "tags" => {
+                        content.tags = value
+                            .split(',')
+                            .map(|tag| {
+                                normalize_tag(tag).ok_or_else(|| anyhow!("invalid tag '{}'", tag))
+                            })
+                            .collect::<Result<_>>()?
+                    }
+                    "prio" => content.priority = Some(Priority::from_str(value)?),
+                    "every" => content.recurrence = Some(Recurrence::from_str(value)?),
+                    "snoozed" => {
+                        content.snoozed = value
+                            .parse()
+                            .with_context(|| format!("invalid snooze count '{}'", value))?
+                    }
+                    "updated" => content.updated_at = Some(parse_attribute_date_time(key, value)?),
+                    "uuid" => {
+                        uuid = Some(
+                            Uuid::parse_str(value)
+                                .with_context(|| format!("invalid uuid '{}'", value))?,
+                        )
+                    }
+                    _ => (),
+                }
+            }
+        }
+        if let Some(uuid) = uuid {
+            content.uuid = uuid;
+        }
+
+        Ok(content)
+    }
+}
+
+/// Implement FromStr trait for Priority
+impl std::str::FromStr for Priority {
+    type Err = anyhow::Error;
+
+    /// Create a Priority from a letter (A-Z) or from high, medium or low
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let invalid = || {
+            anyhow!(
+                "Invalid priority '{}'. Expected: A-Z, high, medium or low",
+                s
+            )
+        };
+        let letter = match s.to_lowercase().as_str() {
+            "high" => 'A',
+            "medium" => 'B',
+            "low" => 'C',
+            _ => {
+                let mut chars = s.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(letter), None) => letter,
+                    _ => return Err(invalid()),
+                }
+            }
+        };
+        Priority::new(letter).ok_or_else(invalid)
+    }
+}
 
-        Ok(Content {
-            text: content.to_string(),
-            date_time,
-        })
+/// Implement Display trait for Priority
+impl fmt::Display for Priority {
+    /// Format Priority as its letter
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.letter())
     }
 }
 
 /// Implement Display trait for Content
 impl fmt::Display for Content {
     /// Format Content for display
+    /// The alternate form, `{:#}`, also writes the UUID, as stored in the data file
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "{} {}",
-            self.date_time.format(DATE_TIME_FORMAT),
-            self.text
-        )
+        write!(f, "{} ", self.date_time.format(DATE_TIME_FORMAT))?;
+        let mut attributes = Vec::new();
+        if let Some(due) = self.due {
+            attributes.push(format!("due:{}", due.format(ATTRIBUTE_DATE_TIME_FORMAT)));
+        }
+        if let Some(done) = self.done {
+            attributes.push(format!("done:{}", done.format(ATTRIBUTE_DATE_TIME_FORMAT)));
+        }
+        if !self.tags.is_empty() {
+            attributes.push(format!("tags:{}", self.tags.join(",")));
+        }
+        if let Some(priority) = self.priority {
+            attributes.push(format!("prio:{}", priority));
+        }
+        if let Some(recurrence) = &self.recurrence {
+            attributes.push(format!("every:{}", recurrence));
+        }
+        if self.snoozed > 0 {
+            attributes.push(format!("snoozed:{}", self.snoozed));
+        }
+        if let Some(updated_at) = self.updated_at {
+            attributes.push(format!(
+                "updated:{}",
+                updated_at.format(ATTRIBUTE_DATE_TIME_FORMAT)
+            ));
+        }
+        if f.alternate() {
+            attributes.push(format!("uuid:{}", self.uuid));
+        }
+        if !attributes.is_empty() {
+            write!(f, "[{}] ", attributes.join(" "))?;
+        }
+        write!(f, "{}", self.text)
     }
 }
 
@@ -61,65 +204,273 @@ impl Default for MemoData {
 
 /// Implement DataFile trait for MemoData
 impl DataFile for MemoData {
-    /// Load data from file
+    /// Load data from file, detecting its format, and the trash next to it.
+    /// Lines that cannot be read are skipped and kept in `bad_lines`, and duplicate ids are
+    /// resolved with the policy of the app config.
     fn load(&mut self, cli_app: &app::AppConfig) -> Result<()> {
         let data = read_file(&cli_app.data_file_path())?;
-        self.contents = MemoData::parse(data)?;
+        if jsonl::is_jsonl(&data) {
+            self.set_lines(jsonl::parse_lines(&data)?, cli_app.duplicates);
+            self.format = Format::JsonLines;
+        } else {
+            self.set_lines(MemoData::parse_lines(&data), cli_app.duplicates);
+            self.format = Format::Lines;
+        }
+
+        let trash_path = trash_path(&cli_app.data_file_path());
+        self.trash = if trash_path.exists() {
+            jsonl::parse_trash(&read_file(&trash_path)?).context("Could not load the trash")?
+        } else {
+            Vec::new()
+        };
         Ok(())
     }
 
+    /// Save data to file, in the format it was loaded from, and the trash next to it.
+    /// The trash is written first, so a memo removed from the data file is never lost.
+    /// The previous content of the data file is kept as a rotating backup.
+    fn save(&mut self, cli_app: &app::AppConfig) -> Result<()> {
+        self.check_bad_lines()?;
+        let data = match self.format {
+            Format::Lines => format!("{}", self),
+            Format::JsonLines => jsonl::to_string(self)?,
+        };
+
+        let trash_path = trash_path(&cli_app.data_file_path());
+        if trash_path.exists() || !self.trash.is_empty() {
+            if !trash_path.exists() {
+                std::fs::File::create(&trash_path)?;
+            }
+            write_file(&trash_path, &jsonl::trash_to_string(&self.trash)?)?;
+        }
+
+        rotate_backups(&cli_app.data_file_path(), cli_app.backups)?;
+        write_file(&cli_app.data_file_path(), &data)
+    }
+
     /// Return sorted ids of items in MemoData
     fn sorted_ids(&self) -> Vec<u32> {
         self.sorted_ids()
     }
 
+    /// Return the lines of the data file that could not be read
+    fn bad_lines(&self) -> &[BadLine] {
+        &self.bad_lines
+    }
+
+    /// Return the lines of the data file whose duplicate id was dropped or renumbered
+    fn duplicates(&self) -> &[DuplicateLine] {
+        &self.duplicates
+    }
+
+    /// Return item of MemoData given its id
+    fn get(&self, id: u32) -> Option<&Content> {
+        self.get(id)
+    }
+
     /// Add item to MemoData
     fn add(&mut self, id: u32, name: &str) -> Result<()> {
         if self.contents.contains_key(&id) {
             return Err(anyhow!("Id '{}' already exists", id));
         }
         let date_time = Local::now().naive_local();
-        self.contents.insert(
-            id,
-            Content {
-                text: name.to_string(),
-                date_time,
-            },
-        );
+        self.contents.insert(id, Content::new(name, date_time));
         Ok(())
     }
 
-    /// Remove item from MemoData
-    fn remove(&mut self, id: u32) -> Result<()> {
-        if !self.contents.contains_key(&id) {
-            return Err(anyhow!("Id '{}' not found", id));
+    /// Set or clear the due date of an item in MemoData.
+    /// The series of a recurring item goes on from the new due date, not from a snooze anchor.
+    fn set_due(&mut self, id: u32, due: Option<NaiveDateTime>) -> Result<()> {
+        let content = self
+            .contents
+            .get_mut(&id)
+            .ok_or_else(|| anyhow!("Id '{}' not found", id))?;
+        content.due = due;
+        if let Some(recurrence) = &mut content.recurrence {
+            recurrence.anchor = None;
         }
-        self.contents.remove(&id);
         Ok(())
     }
 
-    /// Display MemoData
-    fn display(&self, mode: DisplayMode) -> Result<()> {
-        match mode {
-            DisplayMode::Sorted => println!("{}", self),
-            DisplayMode::GroupByDate => println!("{}", self.group_by_date()?),
+    /// Mark an item in MemoData as done, keeping the completion time, or as not done
+    fn set_done(&mut self, id: u32, done: bool) -> Result<()> {
+        let content = self
+            .contents
+            .get_mut(&id)
+            .ok_or_else(|| anyhow!("Id '{}' not found", id))?;
+        content.done = match (done, content.done) {
+            (true, Some(done_at)) => Some(done_at),
+            (true, None) => Some(Local::now().naive_local()),
+            (false, _) => None,
+        };
+        Ok(())
+    }
+
+    /// Set the tags of an item in MemoData
+    fn set_tags(&mut self, id: u32, tags: Vec<String>) -> Result<()> {
+        let content = self
+            .contents
+            .get_mut(&id)
+            .ok_or_else(|| anyhow!("Id '{}' not found", id))?;
+        content.tags = tags;
+        Ok(())
+    }
+
+    /// Set or clear the priority of an item in MemoData
+    fn set_priority(&mut self, id: u32, priority: Option<Priority>) -> Result<()> {
+        let content = self
+            .contents
+            .get_mut(&id)
+            .ok_or_else(|| anyhow!("Id '{}' not found", id))?;
+        content.priority = priority;
+        Ok(())
+    }
+
+    /// Put off an item in MemoData to a new due date, counting the snooze
+    fn snooze(&mut self, id: u32, due: NaiveDateTime) -> Result<()> {
+        let content = self
+            .contents
+            .get_mut(&id)
+            .ok_or_else(|| anyhow!("Id '{}' not found", id))?;
+        // A recurring memo keeps the occurrence it was first snoozed from as its anchor
+        if let (Some(recurrence), Some(previous)) = (&mut content.recurrence, content.due) {
+            recurrence.anchor.get_or_insert(previous);
         }
+        content.due = Some(due);
+        content.snoozed += 1;
+        Ok(())
+    }
+
+    /// Set or clear the recurrence rule of an item in MemoData
+    fn set_recurrence(&mut self, id: u32, recurrence: Option<Recurrence>) -> Result<()> {
+        let content = self
+            .contents
+            .get_mut(&id)
+            .ok_or_else(|| anyhow!("Id '{}' not found", id))?;
+        content.recurrence = recurrence;
+        Ok(())
+    }
+
+    /// Replace the text of an item in MemoData, recording when it was edited
+    fn update(&mut self, id: u32, text: &str) -> Result<()> {
+        let content = self
+            .contents
+            .get_mut(&id)
+            .ok_or_else(|| anyhow!("Id '{}' not found", id))?;
+        content.text = text.to_string();
+        content.updated_at = Some(Local::now().naive_local());
         Ok(())
     }
+
+    /// Insert an item in MemoData as it is, replacing the item with the same id.
+    /// A copy of the item in the trash, with the same id and creation time, is dropped.
+    fn insert(&mut self, id: u32, content: Content) -> Result<()> {
+        self.trash
+            .retain(|trashed| trashed.id != id || trashed.content.date_time != content.date_time);
+        self.contents.insert(id, content);
+        Ok(())
+    }
+
+    /// Move item from MemoData to the trash
+    fn remove(&mut self, id: u32) -> Result<()> {
+        let content = self
+            .contents
+            .remove(&id)
+            .ok_or_else(|| anyhow!("Id '{}' not found", id))?;
+        self.trash.push(Trashed {
+            id,
+            removed_at: Local::now().naive_local(),
+            content,
+        });
+        Ok(())
+    }
+
+    /// Give items new ids at once, as (old id, new id) pairs. A new id must be free or be the
+    /// old id of another moved item.
+    fn renumber(&mut self, moves: &[(u32, u32)]) -> Result<()> {
+        let moved: HashSet<u32> = moves.iter().map(|(from, _)| *from).collect();
+        let mut targets = HashSet::new();
+        for (from, to) in moves {
+            if !self.contents.contains_key(from) {
+                return Err(anyhow!("Id '{}' not found", from));
+            }
+            if !targets.insert(*to) || (self.contents.contains_key(to) && !moved.contains(to)) {
+                return Err(anyhow!("Id '{}' is already in use", to));
+            }
+        }
+        if moved.len() != moves.len() {
+            return Err(anyhow!("An id can only be moved once"));
+        }
+
+        let contents: Vec<(u32, Content)> = moves
+            .iter()
+            .filter_map(|(from, to)| Some((*to, self.contents.remove(from)?)))
+            .collect();
+        self.contents.extend(contents);
+        Ok(())
+    }
+
+    /// Return the items in the trash of MemoData, oldest removal first
+    fn trash(&self) -> &[Trashed] {
+        &self.trash
+    }
+
+    /// Move the last removed item with the id back from the trash, returning its new id.
+    /// The item keeps its id if it is still free, otherwise it gets the next free one.
+    fn restore(&mut self, id: u32) -> Result<u32> {
+        let position = self
+            .trash
+            .iter()
+            .rposition(|trashed| trashed.id == id)
+            .ok_or_else(|| anyhow!("Id '{}' not found in the trash", id))?;
+        let new_id = if self.contents.contains_key(&id) {
+            self.sorted_ids().last().unwrap_or(&0) + 1
+        } else {
+            id
+        };
+        let trashed = self.trash.remove(position);
+        self.contents.insert(new_id, trashed.content);
+        Ok(new_id)
+    }
+
+    /// Permanently delete the items removed before the given time, or all of them,
+    /// from the trash of MemoData. Returns the number of items deleted.
+    fn purge(&mut self, removed_before: Option<NaiveDateTime>) -> Result<usize> {
+        let count = self.trash.len();
+        self.trash
+            .retain(|trashed| removed_before.is_some_and(|before| trashed.removed_at >= before));
+        Ok(count - self.trash.len())
+    }
+
+    /// Search the text of MemoData
+    fn search(&self, query: &Query, ranked: bool) -> Result<String> {
+        self.search(query, ranked)
+    }
+
+    /// Render MemoData in the given layout and output format
+    fn render(&self, mode: DisplayMode, filter: &Filter, format: OutputFormat) -> Result<String> {
+        match (format, mode) {
+            (OutputFormat::Pretty, DisplayMode::Sorted) => Ok(self.sorted(filter)),
+            (OutputFormat::Pretty, DisplayMode::GroupByDate) => self.group_by_date(filter),
+            (OutputFormat::Pretty, DisplayMode::ByPriority) => self.by_priority(filter),
+            (format, mode) => output::render(self, &self.ordered_ids(mode, filter), format),
+        }
+    }
 }
 
 /// Implement Display trait for MemoData
 impl fmt::Display for MemoData {
-    /// Format MemoData for display
+    /// Format MemoData as the lines of a data file
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         for id in self.sorted_ids() {
-            writeln!(f, "{}: {}", id, self.contents[&id])?;
+            writeln!(f, "{}: {:#}", id, self.contents[&id])?;
         }
         Ok(())
     }
 }
 
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
 mod tests {
 
     use super::*;
@@ -155,6 +506,27 @@ mod tests {
         assert_eq!(d.load(&app_config).is_ok(), true);
     }
 
+    #[test]
+    fn test_memo_data_load_and_save_jsonl() {
+        let mut app_config = app::AppConfig::new("memo", "memo.txt");
+        let dir = tempdir().unwrap();
+        app_config.data_dir = dir.path().to_path_buf();
+        let data = "{\"format\":\"memo\",\"version\":1}\n{\"id\":1,\"text\":\"one\",\"created\":\"2001-01-01T01:01:01\"}\n";
+        fs::write(app_config.data_file_path(), data).unwrap();
+
+        let mut d = MemoData::new();
+        assert!(d.load(&app_config).is_ok());
+        assert_eq!(d.format, Format::JsonLines);
+        assert_eq!(d.get(1).unwrap().text, "one");
+
+        assert!(d.add(2, "two").is_ok());
+        assert!(d.save(&app_config).is_ok());
+        let saved = read_file(&app_config.data_file_path()).unwrap();
+        assert!(saved.starts_with(data.trim_end_matches("}\n")));
+        assert!(saved.contains("\"text\":\"two\""));
+        assert_eq!(saved.matches("\"uuid\":").count(), 2);
+    }
+
     #[test]
     fn test_memo_data_sorted_ids() {
         let mut d = MemoData::new();
@@ -170,6 +542,75 @@ mod tests {
         assert_eq!(d.add(1, "one").is_ok(), true);
         assert_eq!(d.remove(1).is_ok(), true);
         assert_eq!(d.contents.len(), 0);
+        assert_eq!(d.trash().len(), 1);
+        assert_eq!(d.trash()[0].id, 1);
+        assert!(d.remove(1).is_err());
+    }
+
+    #[test]
+    fn test_memo_data_restore() {
+        let mut d = MemoData::new();
+        d.add(1, "one").unwrap();
+        d.add(2, "two").unwrap();
+        d.remove(1).unwrap();
+        d.remove(2).unwrap();
+        assert_eq!(d.restore(1).unwrap(), 1);
+        assert_eq!(d.get(1).unwrap().text, "one");
+
+        d.add(2, "another two").unwrap();
+        assert_eq!(d.restore(2).unwrap(), 3);
+        assert_eq!(d.get(3).unwrap().text, "two");
+        assert!(d.trash().is_empty());
+        assert!(d.restore(2).is_err());
+    }
+
+    #[test]
+    fn test_memo_data_purge() {
+        let mut d = MemoData::new();
+        for id in 1..=3 {
+            d.add(id, "memo").unwrap();
+            d.remove(id).unwrap();
+        }
+        d.trash[0].removed_at -= chrono::Duration::days(10);
+        let week_ago = Local::now().naive_local() - chrono::Duration::days(7);
+        assert_eq!(d.purge(Some(week_ago)).unwrap(), 1);
+        assert_eq!(d.trash().len(), 2);
+        assert_eq!(d.purge(None).unwrap(), 2);
+        assert!(d.trash().is_empty());
+    }
+
+    #[test]
+    fn test_memo_data_insert_drops_trashed_copy() {
+        let mut d = MemoData::new();
+        d.add(1, "one").unwrap();
+        let content = d.get(1).cloned().unwrap();
+        d.remove(1).unwrap();
+        d.insert(1, content).unwrap();
+        assert!(d.trash().is_empty());
+        assert_eq!(d.get(1).unwrap().text, "one");
+    }
+
+    #[test]
+    fn test_memo_data_save_and_load_trash() {
+        let dir = tempdir().unwrap();
+        let mut app_config = app::AppConfig::new("memo", "memo.txt");
+        app_config.data_dir = dir.path().to_path_buf();
+        fs::File::create(app_config.data_file_path()).unwrap();
+
+        let mut d = MemoData::new();
+        d.save(&app_config).unwrap();
+        assert!(!trash_path(&app_config.data_file_path()).exists());
+
+        d.add(1, "one").unwrap();
+        d.add(2, "two").unwrap();
+        d.remove(2).unwrap();
+        d.save(&app_config).unwrap();
+
+        let mut d = MemoData::new();
+        d.load(&app_config).unwrap();
+        assert_eq!(d.sorted_ids(), vec![1]);
+        assert_eq!(d.trash().len(), 1);
+        assert_eq!(d.trash()[0].content.text, "two");
     }
 
     #[test]
@@ -184,7 +625,18 @@ mod tests {
         let mut d = MemoData::new();
         let data_str = "1: 2021-01-01 01:01:01 one\n2: 2021-01-01 01:01:01 two\n3: 2021-01-01 01:01:01 three\n";
         d.contents = MemoData::parse(data_str.to_string()).expect("Error parsing data");
-        assert_eq!(format!("{}", d), data_str);
+        let display = format!("{}", d);
+        assert_eq!(display.lines().count(), 3);
+        assert!(display.starts_with(&format!(
+            "1: 2021-01-01 01:01:01 [uuid:{}] one\n",
+            d.get(1).unwrap().uuid
+        )));
+
+        // The UUIDs given to lines without one are kept when they are written
+        let mut reread = MemoData::new();
+        reread.contents = MemoData::parse(display.clone()).unwrap();
+        assert_eq!(format!("{}", reread), display);
+        assert_ne!(d.get(1).unwrap().uuid, d.get(2).unwrap().uuid);
     }
 
     #[test]
@@ -211,4 +663,251 @@ mod tests {
         let c = Content::from_str(content).expect("Error creating Content");
         assert_eq!(format!("{}", c), content);
     }
+
+    #[test]
+    fn test_content_from_str_due() {
+        let content = "2021-01-01 01:01:01 [due:2021-01-02T09:00:00 uuid:67e55044-10b1-426f-9247-bb680e5fe0c8] one two";
+        let c = Content::from_str(content).expect("Error creating Content");
+        assert_eq!(c.text, "one two");
+        assert_eq!(
+            c.due.unwrap().format(DATE_TIME_FORMAT).to_string(),
+            "2021-01-02 09:00:00"
+        );
+        assert_eq!(format!("{:#}", c), content);
+    }
+
+    #[test]
+    fn test_content_from_str_brackets_text() {
+        let content = "2021-01-01 01:01:01 [draft] one";
+        let c = Content::from_str(content).expect("Error creating Content");
+        assert_eq!(c.text, "[draft] one");
+        assert!(c.due.is_none());
+    }
+
+    #[test]
+    fn test_memo_data_legacy_brackets_text() {
+        // Lines of older files whose text looks like attributes, but without a uuid
+        let data = "1: 2001-01-01 01:01:01 [due:friday] call bob\n2: 2001-01-01 01:01:01 [tags:x] notes\n3: 2001-01-01 01:01:01 [prio:A done:2001-01-02T00:00:00] old\n";
+        let mut d = MemoData::new();
+        d.set_lines(
+            MemoData::parse_lines(data),
+            crate::data::DuplicatePolicy::Error,
+        );
+        assert!(d.check_bad_lines().is_ok());
+        assert_eq!(d.get(1).unwrap().text, "[due:friday] call bob");
+        assert!(d.get(2).unwrap().tags.is_empty());
+        assert_eq!(d.get(2).unwrap().text, "[tags:x] notes");
+        assert!(d.get(3).unwrap().done.is_none());
+
+        // Written with their uuid, the text is kept as it is
+        let mut reread = MemoData::new();
+        reread.contents = MemoData::parse(format!("{}", d)).unwrap();
+        assert_eq!(reread.get(1).unwrap().text, "[due:friday] call bob");
+        assert_eq!(reread.get(2).unwrap().text, "[tags:x] notes");
+        assert_eq!(
+            reread.get(3).unwrap().text,
+            "[prio:A done:2001-01-02T00:00:00] old"
+        );
+        assert!(reread.get(3).unwrap().priority.is_none());
+    }
+
+    #[test]
+    fn test_content_from_str_invalid_due() {
+        let content =
+            "2021-01-01 01:01:01 [due:tomorrow uuid:67e55044-10b1-426f-9247-bb680e5fe0c8] one";
+        assert!(Content::from_str(content).is_err());
+    }
+
+    #[test]
+    fn test_content_from_str_due_and_done() {
+        let content = "2021-01-01 01:01:01 [due:2021-01-02T09:00:00 done:2021-01-02T08:00:00 uuid:67e55044-10b1-426f-9247-bb680e5fe0c8] one";
+        let c = Content::from_str(content).expect("Error creating Content");
+        assert_eq!(c.text, "one");
+        assert!(c.due.is_some());
+        assert_eq!(
+            c.done.unwrap().format(DATE_TIME_FORMAT).to_string(),
+            "2021-01-02 08:00:00"
+        );
+        assert_eq!(format!("{:#}", c), content);
+    }
+
+    #[test]
+    fn test_memo_data_set_done() {
+        let mut d = MemoData::new();
+        assert!(d.add(1, "one").is_ok());
+        assert!(d.set_done(1, true).is_ok());
+        let done_at = d.get(1).unwrap().done;
+        assert!(done_at.is_some());
+
+        // Marking as done again keeps the original completion time
+        assert!(d.set_done(1, true).is_ok());
+        assert_eq!(d.get(1).unwrap().done, done_at);
+
+        assert!(d.set_done(1, false).is_ok());
+        assert!(d.get(1).unwrap().done.is_none());
+        assert!(d.set_done(2, true).is_err());
+    }
+
+    #[test]
+    fn test_content_from_str_tags() {
+        let content =
+            "2021-01-01 01:01:01 [tags:work,home uuid:67e55044-10b1-426f-9247-bb680e5fe0c8] one";
+        let c = Content::from_str(content).expect("Error creating Content");
+        assert_eq!(c.text, "one");
+        assert_eq!(c.tags, vec!["work", "home"]);
+        assert_eq!(format!("{:#}", c), content);
+
+        assert!(Content::from_str(
+            "2021-01-01 01:01:01 [tags:work,1 uuid:67e55044-10b1-426f-9247-bb680e5fe0c8] one"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_memo_data_set_tags() {
+        let mut d = MemoData::new();
+        assert!(d.add(1, "one").is_ok());
+        assert!(d.set_tags(1, vec!["work".to_string()]).is_ok());
+        assert!(d.get(1).unwrap().has_tag("work"));
+        assert!(d.set_tags(2, vec![]).is_err());
+    }
+
+    #[test]
+    fn test_priority_from_str() {
+        assert_eq!(Priority::from_str("high").unwrap().letter(), 'A');
+        assert_eq!(Priority::from_str("Medium").unwrap().letter(), 'B');
+        assert_eq!(Priority::from_str("low").unwrap().letter(), 'C');
+        assert_eq!(Priority::from_str("z").unwrap().letter(), 'Z');
+        assert!(Priority::from_str("urgent").is_err());
+        assert!(Priority::from_str("1").is_err());
+        assert!(Priority::from_str("").is_err());
+    }
+
+    #[test]
+    fn test_content_from_str_priority() {
+        let content = "2021-01-01 01:01:01 [prio:A uuid:67e55044-10b1-426f-9247-bb680e5fe0c8] one";
+        let c = Content::from_str(content).expect("Error creating Content");
+        assert_eq!(c.text, "one");
+        assert_eq!(c.priority, Priority::new('A'));
+        assert_eq!(format!("{:#}", c), content);
+    }
+
+    #[test]
+    fn test_memo_data_set_priority() {
+        let mut d = MemoData::new();
+        assert!(d.add(1, "one").is_ok());
+        assert!(d.set_priority(1, Priority::new('B')).is_ok());
+        assert_eq!(d.get(1).unwrap().priority, Priority::new('B'));
+        assert!(d.set_priority(1, None).is_ok());
+        assert!(d.get(1).unwrap().priority.is_none());
+        assert!(d.set_priority(2, None).is_err());
+    }
+
+    #[test]
+    fn test_memo_data_set_due() {
+        let mut d = MemoData::new();
+        let due = NaiveDate::from_ymd_opt(2021, 1, 2)
+            .unwrap()
+            .and_hms_opt(9, 0, 0)
+            .unwrap();
+        assert!(d.add(1, "one").is_ok());
+        assert!(d.set_due(1, Some(due)).is_ok());
+        assert_eq!(d.get(1).unwrap().due, Some(due));
+        assert!(d.set_due(2, Some(due)).is_err());
+    }
+
+    #[test]
+    fn test_content_from_str_every() {
+        let content = "2021-01-01 01:01:01 [due:2021-01-04T09:30:00 every:FREQ=WEEKLY;BYDAY=MO,TU,WE,TH,FR uuid:67e55044-10b1-426f-9247-bb680e5fe0c8] standup";
+        let c = Content::from_str(content).expect("Error creating Content");
+        assert_eq!(c.text, "standup");
+        assert_eq!(c.recurrence.as_ref().unwrap().describe(), "every weekday");
+        assert_eq!(format!("{:#}", c), content);
+
+        assert!(Content::from_str(
+            "2021-01-01 01:01:01 [every:FREQ=YEARLY uuid:67e55044-10b1-426f-9247-bb680e5fe0c8] one"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_content_from_str_snoozed() {
+        let content = "2021-01-01 01:01:01 [due:2021-01-04T09:30:00 snoozed:2 uuid:67e55044-10b1-426f-9247-bb680e5fe0c8] call mom";
+        let c = Content::from_str(content).expect("Error creating Content");
+        assert_eq!(c.text, "call mom");
+        assert_eq!(c.snoozed, 2);
+        assert_eq!(format!("{:#}", c), content);
+
+        assert!(Content::from_str(
+            "2021-01-01 01:01:01 [snoozed:-1 uuid:67e55044-10b1-426f-9247-bb680e5fe0c8] one"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_content_from_str_uuid() {
+        let content = "2021-01-01 01:01:01 [prio:A uuid:67e55044-10b1-426f-9247-bb680e5fe0c8] one";
+        let c = Content::from_str(content).expect("Error creating Content");
+        assert_eq!(c.text, "one");
+        assert_eq!(c.uuid.to_string(), "67e55044-10b1-426f-9247-bb680e5fe0c8");
+        assert_eq!(format!("{:#}", c), content);
+        assert_eq!(format!("{}", c), "2021-01-01 01:01:01 [prio:A] one");
+
+        // Without the attribute, identical lines get different random UUIDs
+        let c = Content::from_str("2021-01-01 01:01:01 one").unwrap();
+        assert_ne!(
+            c.uuid,
+            Content::from_str("2021-01-01 01:01:01 one").unwrap().uuid
+        );
+        assert_eq!(c.uuid.get_version_num(), 4);
+
+        assert!(Content::from_str("2021-01-01 01:01:01 [uuid:xyz] one").is_err());
+    }
+
+    #[test]
+    fn test_content_from_str_updated() {
+        let content = "2021-01-01 01:01:01 [prio:A updated:2021-01-02T03:04:05 uuid:67e55044-10b1-426f-9247-bb680e5fe0c8] one";
+        let c = Content::from_str(content).expect("Error creating Content");
+        assert_eq!(c.text, "one");
+        assert_eq!(
+            c.updated_at,
+            NaiveDate::from_ymd_opt(2021, 1, 2)
+                .unwrap()
+                .and_hms_opt(3, 4, 5)
+        );
+        assert_eq!(format!("{:#}", c), content);
+    }
+
+    #[test]
+    fn test_memo_data_update() {
+        let mut d = MemoData::new();
+        assert!(d.add(1, "one").is_ok());
+        let date_time = d.get(1).unwrap().date_time;
+        assert!(d.update(1, "uno").is_ok());
+        let content = d.get(1).unwrap();
+        assert_eq!(content.text, "uno");
+        assert_eq!(content.date_time, date_time);
+        assert!(content.updated_at.is_some());
+        assert!(d.update(2, "two").is_err());
+    }
+
+    #[test]
+    fn test_memo_data_renumber() {
+        let mut d = MemoData::new();
+        for (id, text) in [(1, "one"), (2, "two"), (5, "five")] {
+            d.add(id, text).unwrap();
+        }
+        assert!(d.renumber(&[(5, 2)]).is_err());
+        assert!(d.renumber(&[(4, 3)]).is_err());
+        assert!(d.renumber(&[(1, 3), (2, 3)]).is_err());
+        assert!(d.renumber(&[(1, 3), (1, 4)]).is_err());
+        assert_eq!(d.sorted_ids(), vec![1, 2, 5]);
+
+        d.renumber(&[(1, 2), (2, 1), (5, 3)]).unwrap();
+        assert_eq!(d.sorted_ids(), vec![1, 2, 3]);
+        assert_eq!(d.get(1).unwrap().text, "two");
+        assert_eq!(d.get(2).unwrap().text, "one");
+        assert_eq!(d.get(3).unwrap().text, "five");
+        assert!(d.trash().is_empty());
+    }
 }
@@ -0,0 +1,323 @@
+use crate::models::{Content, MemoData, ParsedLine, Priority, Trashed};
+use crate::recur::Recurrence;
+use anyhow::{anyhow, Context, Result};
+use chrono::{NaiveDateTime, Timelike};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::str::FromStr;
+use uuid::Uuid;
+
+/// Name written in the header of JSON Lines data files
+pub const FORMAT_NAME: &str = "memo";
+
+/// Version of the JSON Lines schema written by this version of memo
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// First line of a JSON Lines data file
+#[derive(Serialize, Deserialize)]
+struct Header {
+    format: String,
+    version: u32,
+}
+
+/// A memo as stored in a JSON Lines data file, one per line after the header.
+/// Also used to keep copies of memos in the history.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct Record {
+    id: u32,
+    text: String,
+    created: NaiveDateTime,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    due: Option<NaiveDateTime>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    done: Option<NaiveDateTime>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    priority: Option<String>,
+    /// Recurrence rule, as an RRULE
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    every: Option<String>,
+    /// Times the memo was snoozed
+    #[serde(default, skip_serializing_if = "is_zero")]
+    snoozed: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    updated: Option<NaiveDateTime>,
+    /// Missing in files written before memos had a UUID
+    #[serde(default)]
+    uuid: Option<Uuid>,
+}
+
+impl Record {
+    pub(crate) fn new(id: u32, content: &Content) -> Self {
+        Record {
+            id,
+            text: content.text.clone(),
+            created: content.date_time,
+            due: content.due,
+            done: content.done,
+            tags: content.tags.clone(),
+            priority: content.priority.map(|priority| priority.to_string()),
+            every: content
+                .recurrence
+                .as_ref()
+                .map(|recurrence| recurrence.to_string()),
+            snoozed: content.snoozed,
+            updated: content.updated_at,
+            uuid: Some(content.uuid),
+        }
+    }
+
+    /// Returns true if both records hold the same memo. Times are compared to the second, as
+    /// text data files keep them.
+    pub(crate) fn same_memo(&self, other: &Record) -> bool {
+        let seconds = |time: NaiveDateTime| time.with_nanosecond(0).unwrap_or(time);
+        let truncated = |record: &Record| Record {
+            created: seconds(record.created),
+            due: record.due.map(seconds),
+            done: record.done.map(seconds),
+            updated: record.updated.map(seconds),
+            ..record.clone()
+        };
+        truncated(self) == truncated(other)
+    }
+
+    pub(crate) fn into_content(self) -> Result<(u32, Content)> {
+        let mut content = Content::new(&self.text, self.created);
+        content.due = self.due;
+        content.done = self.done;
+        content.tags = self.tags;
+        content.priority = self
+            .priority
+            .map(|priority| Priority::from_str(&priority))
+            .transpose()?;
+        content.recurrence = self
+            .every
+            .map(|every| Recurrence::from_str(&every))
+            .transpose()?;
+        content.snoozed = self.snoozed;
+        content.updated_at = self.updated;
+        if let Some(uuid) = self.uuid {
+            content.uuid = uuid;
+        }
+        Ok((self.id, content))
+    }
+}
+
+/// Returns true for a count of zero, which is left out of the records
+fn is_zero(count: &u32) -> bool {
+    *count == 0
+}
+
+/// A memo in the trash, with the date and time it was removed
+#[derive(Serialize, Deserialize)]
+struct TrashRecord {
+    removed: NaiveDateTime,
+    #[serde(flatten)]
+    memo: Record,
+}
+
+/// Format a memo in the trash as a single line of JSON
+pub fn trashed_to_string(trashed: &Trashed) -> Result<String> {
+    Ok(serde_json::to_string(&TrashRecord {
+        removed: trashed.removed_at,
+        memo: Record::new(trashed.id, &trashed.content),
+    })?)
+}
+
+/// Parse a memo in the trash from a single line of JSON
+pub fn parse_trashed(line: &str) -> Result<Trashed> {
+    let record: TrashRecord = serde_json::from_str(line)?;
+    let (id, content) = record.memo.into_content()?;
+    Ok(Trashed {
+        id,
+        removed_at: record.removed,
+        content,
+    })
+}
+
+/// Parse the trash of a data file, one memo per line, oldest removal first
+pub fn parse_trash(data: &str) -> Result<Vec<Trashed>> {
+    data.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(number, line)| {
+            parse_trashed(line)
+                .with_context(|| format!("Invalid trash record on line {}", number + 1))
+        })
+        .collect()
+}
+
+/// Format the trash of a data file, one memo per line
+pub fn trash_to_string(trash: &[Trashed]) -> Result<String> {
+    let mut result = String::new();
+    for trashed in trash {
+        result.push_str(&trashed_to_string(trashed)?);
+        result.push('\n');
+    }
+    Ok(result)
+}
+
+/// Returns true if the data starts with a JSON Lines header
+pub fn is_jsonl(data: &str) -> bool {
+    data.lines()
+        .find(|line| !line.trim().is_empty())
+        .and_then(|line| serde_json::from_str::<Header>(line).ok())
+        .is_some_and(|header| header.format == FORMAT_NAME)
+}
+
+/// Parse the data of a JSON Lines data file
+pub fn parse(data: &str) -> Result<HashMap<u32, Content>> {
+    parse_lines(data)?
+        .into_iter()
+        .map(|parsed| {
+            let number = parsed.number;
+            parsed
+                .item
+                .with_context(|| format!("Invalid record on line {}", number))
+        })
+        .collect()
+}
+
+/// Parse every record of a JSON Lines data file, keeping the ones that cannot be read.
+/// Only a missing or unsupported header is an error.
+pub fn parse_lines(data: &str) -> Result<Vec<ParsedLine>> {
+    let mut lines = data
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty());
+
+    let (_, header) = lines.next().ok_or_else(|| anyhow!("Missing header"))?;
+    let header: Header = serde_json::from_str(header).context("Invalid header")?;
+    if header.format != FORMAT_NAME {
+        return Err(anyhow!("Invalid header format '{}'", header.format));
+    }
+    if header.version > SCHEMA_VERSION {
+        return Err(anyhow!(
+            "Schema version {} is newer than the supported version {}",
+            header.version,
+            SCHEMA_VERSION
+        ));
+    }
+
+    Ok(lines
+        .map(|(number, line)| ParsedLine {
+            number: number + 1,
+            line: line.to_string(),
+            item: serde_json::from_str::<Record>(line)
+                .map_err(anyhow::Error::from)
+                .and_then(Record::into_content),
+        })
+        .collect())
+}
+
+/// Returns a record line of a JSON Lines data file with another id
+pub fn with_id(line: &str, id: u32) -> Result<String> {
+    let mut record: Record = serde_json::from_str(line)?;
+    record.id = id;
+    Ok(serde_json::to_string(&record)?)
+}
+
+/// Format MemoData as a JSON Lines data file
+pub fn to_string(data: &MemoData) -> Result<String> {
+    let header = Header {
+        format: FORMAT_NAME.to_string(),
+        version: SCHEMA_VERSION,
+    };
+    let mut result = serde_json::to_string(&header)?;
+    result.push('\n');
+    for id in data.sorted_ids() {
+        result.push_str(&serde_json::to_string(&Record::new(
+            id,
+            &data.contents[&id],
+        ))?);
+        result.push('\n');
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DATA: &str = r#"{"format":"memo","version":1}
+{"id":1,"text":"one","created":"2001-01-01T01:01:01","uuid":"67e55044-10b1-426f-9247-bb680e5fe0c8"}
+{"id":2,"text":"two \"quoted\"","created":"2002-02-02T02:02:02","due":"2002-02-03T09:00:00","done":"2002-02-03T08:00:00","tags":["work","home"],"priority":"A","snoozed":3,"updated":"2002-02-02T03:00:00","uuid":"a1a2a3a4-b1b2-4c1c-8d1d-e1e2e3e4e5e6"}
+"#;
+
+    #[test]
+    fn test_is_jsonl() {
+        assert!(is_jsonl(DATA));
+        assert!(is_jsonl("\n{\"format\":\"memo\",\"version\":2}\n"));
+        assert!(!is_jsonl("1: 2001-01-01 01:01:01 one\n"));
+        assert!(!is_jsonl(""));
+    }
+
+    #[test]
+    fn test_parse() {
+        let contents = parse(DATA).unwrap();
+        assert_eq!(contents.len(), 2);
+        assert_eq!(contents[&1].text, "one");
+        let two = &contents[&2];
+        assert_eq!(two.text, "two \"quoted\"");
+        assert!(two.due.is_some());
+        assert!(two.done.is_some());
+        assert_eq!(two.tags, vec!["work", "home"]);
+        assert_eq!(two.priority, Priority::new('A'));
+        assert_eq!(two.snoozed, 3);
+        assert_eq!(contents[&1].snoozed, 0);
+        assert!(two.updated_at.is_some());
+        assert_eq!(two.uuid.to_string(), "a1a2a3a4-b1b2-4c1c-8d1d-e1e2e3e4e5e6");
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut data = MemoData::new();
+        data.contents = parse(DATA).unwrap();
+        assert_eq!(to_string(&data).unwrap(), DATA);
+    }
+
+    #[test]
+    fn test_missing_uuid() {
+        let data = "{\"format\":\"memo\",\"version\":1}\n{\"id\":1,\"text\":\"one\",\"created\":\"2001-01-01T01:01:01\"}\n";
+        let contents = parse(data).unwrap();
+        assert_eq!(contents[&1].uuid.get_version_num(), 4);
+
+        let mut data = MemoData::new();
+        data.contents = contents;
+        assert!(to_string(&data)
+            .unwrap()
+            .contains(&format!("\"uuid\":\"{}\"", data.contents[&1].uuid)));
+    }
+
+    #[test]
+    fn test_trash_round_trip() {
+        let data = "{\"removed\":\"2002-02-03T00:00:00\",\"id\":2,\"text\":\"two\",\"created\":\"2002-02-02T02:02:02\",\"tags\":[\"work\"],\"uuid\":\"67e55044-10b1-426f-9247-bb680e5fe0c8\"}\n";
+        let trash = parse_trash(data).unwrap();
+        assert_eq!(trash.len(), 1);
+        assert_eq!(trash[0].id, 2);
+        assert_eq!(trash[0].content.text, "two");
+        assert_eq!(trash[0].content.tags, vec!["work"]);
+        assert_eq!(trash_to_string(&trash).unwrap(), data);
+
+        let err = parse_trash("\n{\"id\":1}\n").unwrap_err();
+        assert_eq!(err.to_string(), "Invalid trash record on line 2");
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!(parse("").is_err());
+        assert!(parse("{\"format\":\"memo\",\"version\":99}\n").is_err());
+
+        let err = parse("{\"format\":\"memo\",\"version\":1}\n{\"id\":1}\n").unwrap_err();
+        assert_eq!(err.to_string(), "Invalid record on line 2");
+
+        let line =
+            "{\"id\":1,\"text\":\"one\",\"created\":\"2001-01-01T01:01:01\",\"priority\":\"1\"}";
+        assert!(parse(&format!(
+            "{{\"format\":\"memo\",\"version\":1}}\n{}\n",
+            line
+        ))
+        .is_err());
+    }
+}
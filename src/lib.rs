@@ -1,7 +1,18 @@
 pub mod app;
+pub mod config;
 pub mod data;
+pub mod dates;
+pub mod doctor;
+pub mod history;
 pub mod impls;
+pub mod jsonl;
+pub mod lock;
 pub mod models;
+pub mod notify;
+pub mod output;
+pub mod recur;
+pub mod search;
+pub mod sqlite;
 pub mod style;
 
 pub use impls::*;
@@ -0,0 +1,240 @@
+use crate::ATTRIBUTE_DATE_TIME_FORMAT;
+use anyhow::{anyhow, Context, Result};
+use chrono::prelude::*;
+use std::fs;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+/// Time to wait for another memo process to release the lock
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Age after which a lock is considered stale when it cannot be told whether its process
+/// is still running
+const STALE_AFTER: Duration = Duration::from_secs(10 * 60);
+
+/// Age under which an empty lock file is left alone, its owner may still be writing it
+const WRITE_GRACE: Duration = Duration::from_secs(1);
+
+/// Time between two attempts to take the lock
+const RETRY_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Path of the lock of a data file, the file path with `.lock` appended
+pub fn lock_path(file_path: &Path) -> PathBuf {
+    let mut path = file_path.as_os_str().to_owned();
+    path.push(".lock");
+    PathBuf::from(path)
+}
+
+/// Advisory lock on a data file, held from loading it until the last change is written.
+/// The lock is a file next to the data file with the id of the process holding it and the
+/// time it was taken. It is released when dropped.
+#[derive(Debug)]
+pub struct Lock {
+    path: PathBuf,
+    file_path: PathBuf,
+    timeout: Duration,
+    held: bool,
+}
+
+impl Lock {
+    /// Take the lock of a data file, waiting up to `timeout` for another process to release it.
+    /// Locks left behind by a process that is no longer running are removed.
+    pub fn acquire(file_path: &Path, timeout: Duration) -> Result<Self> {
+        let path = lock_path(file_path);
+        let started = SystemTime::now();
+        loop {
+            match fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&path)
+            {
+                Ok(mut file) => {
+                    let now = Local::now().naive_local();
+                    write!(
+                        file,
+                        "{}\n{}\n",
+                        std::process::id(),
+                        now.format(ATTRIBUTE_DATE_TIME_FORMAT)
+                    )
+                    .and_then(|_| file.sync_all())
+                    .with_context(|| format!("Could not write lock '{}'", path.display()))?;
+                    return Ok(Lock {
+                        path,
+                        file_path: file_path.to_path_buf(),
+                        timeout,
+                        held: true,
+                    });
+                }
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => {}
+                Err(e) if e.kind() == ErrorKind::NotFound => {
+                    return Err(anyhow!("File '{}' not found", file_path.display()))
+                }
+                Err(e) => {
+                    return Err(e)
+                        .with_context(|| format!("Could not create lock '{}'", path.display()))
+                }
+            }
+
+            let holder = fs::read_to_string(&path).unwrap_or_default();
+            if is_stale(&path, &holder) {
+                // Only remove the lock if it was not taken over in the meantime
+                if fs::read_to_string(&path).unwrap_or_default() == holder {
+                    let _ = fs::remove_file(&path);
+                }
+                continue;
+            }
+
+            if started.elapsed().unwrap_or_default() >= timeout {
+                let holder = match parse_holder(&holder) {
+                    Some((pid, since)) => format!("process {} since {}", pid, since),
+                    None => "another process".to_string(),
+                };
+                return Err(anyhow!(
+                    "The data file is locked by {}. Try again, or remove '{}' if no memo is running",
+                    holder,
+                    path.display()
+                ));
+            }
+            thread::sleep(RETRY_INTERVAL);
+        }
+    }
+
+    /// Release the lock while `f` runs, e.g. while the user edits a memo, and take it again,
+    /// waiting as long as when it was first taken. Other processes may change the data file
+    /// in the meantime, so it must be read again before it is written.
+    pub fn unlocked<T>(&mut self, f: impl FnOnce() -> T) -> Result<T> {
+        fs::remove_file(&self.path)
+            .with_context(|| format!("Could not release lock '{}'", self.path.display()))?;
+        self.held = false;
+        let result = f();
+        let mut lock = Lock::acquire(&self.file_path, self.timeout)?;
+        lock.held = false;
+        self.held = true;
+        Ok(result)
+    }
+}
+
+impl Drop for Lock {
+    fn drop(&mut self) {
+        if self.held {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// Returns the process id and the time of a lock file content
+fn parse_holder(holder: &str) -> Option<(u32, NaiveDateTime)> {
+    let mut lines = holder.lines();
+    let pid = lines.next()?.trim().parse().ok()?;
+    let since =
+        NaiveDateTime::parse_from_str(lines.next()?.trim(), ATTRIBUTE_DATE_TIME_FORMAT).ok()?;
+    Some((pid, since))
+}
+
+/// Returns Some(true) if a process is running, None if it cannot be told on this system
+fn is_running(pid: u32) -> Option<bool> {
+    let proc = Path::new("/proc");
+    if cfg!(target_os = "linux") && proc.is_dir() {
+        return Some(proc.join(pid.to_string()).exists());
+    }
+    None
+}
+
+/// Returns true if a lock was left behind: its process is no longer running, or it is older
+/// than `STALE_AFTER` when that cannot be told. Unreadable locks are stale once their owner
+/// had time to write them.
+fn is_stale(path: &Path, holder: &str) -> bool {
+    let age = fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| modified.elapsed().ok())
+        .unwrap_or_default();
+    match parse_holder(holder) {
+        Some((pid, _)) => match is_running(pid) {
+            Some(running) => !running,
+            None => age > STALE_AFTER,
+        },
+        None => age > WRITE_GRACE,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_lock_acquire_release() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("memo.txt");
+
+        let lock = Lock::acquire(&file_path, DEFAULT_TIMEOUT).unwrap();
+        let holder = fs::read_to_string(lock_path(&file_path)).unwrap();
+        assert_eq!(parse_holder(&holder).unwrap().0, std::process::id());
+
+        let error = Lock::acquire(&file_path, Duration::from_millis(100)).unwrap_err();
+        assert!(error.to_string().contains("locked by process"));
+
+        drop(lock);
+        assert!(!lock_path(&file_path).exists());
+        assert!(Lock::acquire(&file_path, Duration::ZERO).is_ok());
+
+        let missing = dir.path().join("missing").join("memo.txt");
+        assert!(Lock::acquire(&missing, Duration::ZERO)
+            .unwrap_err()
+            .to_string()
+            .contains("not found"));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_lock_stale() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("memo.txt");
+
+        // A process id above the maximum of Linux cannot be running
+        fs::write(lock_path(&file_path), "4294967295\n2001-01-01T01:01:01\n").unwrap();
+        let lock = Lock::acquire(&file_path, Duration::ZERO).unwrap();
+        drop(lock);
+
+        // The lock of a running process is kept
+        let holder = format!("{}\n2001-01-01T01:01:01\n", std::process::id());
+        fs::write(lock_path(&file_path), holder).unwrap();
+        assert!(Lock::acquire(&file_path, Duration::ZERO).is_err());
+    }
+
+    #[test]
+    fn test_lock_unlocked() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("memo.txt");
+
+        let mut lock = Lock::acquire(&file_path, Duration::from_millis(100)).unwrap();
+        let taken = lock
+            .unlocked(|| Lock::acquire(&file_path, Duration::ZERO).is_ok())
+            .unwrap();
+        assert!(taken);
+        assert!(lock_path(&file_path).exists());
+        assert!(Lock::acquire(&file_path, Duration::ZERO).is_err());
+
+        // A lock taken by another process in the meantime is not removed
+        let mut other = None;
+        let error = lock
+            .unlocked(|| other = Lock::acquire(&file_path, Duration::ZERO).ok())
+            .unwrap_err();
+        assert!(error.to_string().contains("locked by process"));
+        drop(lock);
+        assert!(lock_path(&file_path).exists());
+        drop(other);
+        assert!(!lock_path(&file_path).exists());
+    }
+
+    #[test]
+    fn test_lock_being_written() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("memo.txt");
+        fs::write(lock_path(&file_path), "").unwrap();
+        assert!(Lock::acquire(&file_path, Duration::ZERO).is_err());
+    }
+}
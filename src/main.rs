@@ -1,60 +1,322 @@
 use anyhow::Result;
-use clap::Parser;
 use memo::app;
+use memo::config;
 use memo::data;
+use memo::lock;
 use memo::models;
+use memo::search;
+use memo::sqlite;
 use memo::style;
 
+mod cli;
 mod commands;
 
-const USERDATA: &str = "memo.txt";
-
-#[derive(Parser)]
-#[command(name = "memo")]
-#[command(version = "0.5.0")]
-#[command(about="A simple memo app", long_about=None)]
-#[command(author = "Lucas Vieira dos Santos")]
-#[command(author, version, about, long_about=None)]
-struct Cli {
-    /// Memo message
-    message: Option<Vec<String>>,
-
-    #[arg(short, long)]
-    /// List memos grouped by date
-    list: bool,
+fn main() {
+    let cli = cli::Cli::parse_args();
+    let uses_legacy_flags = cli.uses_legacy_flags();
+    let (backend, file, trash_days) = (cli.backend, cli.file.clone(), cli.trash_days);
+    let notebook = cli.notebook.clone();
+    let global = cli.global;
+    let (duplicates, strict) = (cli.duplicates, cli.strict);
+    let config_path = config::config_path();
+    let commands = cli.commands();
 
-    #[arg(short, long)]
-    /// Initialize the memo file
-    init: bool,
+    // Handle 'Config' command before loading the configuration, so a broken file can be fixed
+    if let [cli::Command::Config { command }] = commands.as_slice() {
+        let result = match command {
+            cli::ConfigCommand::Get { key } => commands::config_get(&config_path, key),
+            cli::ConfigCommand::Set { key, value } => {
+                commands::config_set(&config_path, key, value)
+            }
+            cli::ConfigCommand::Unset { key } => commands::config_unset(&config_path, key),
+            cli::ConfigCommand::List => commands::config_list(&config_path),
+            cli::ConfigCommand::Path => {
+                println!("{}", config_path.display());
+                Ok(())
+            }
+        };
+        let _ = display_result(result, None, Some("Configuration error"));
+        return;
+    }
 
-    #[arg(short, long)]
-    /// List memos sorted by ID
-    sorted: bool,
+    let Ok(config) = display_result(
+        config::Config::load(&config_path),
+        None,
+        Some("Could not load configuration"),
+    ) else {
+        return; // exit if the configuration file is invalid
+    };
+    if let Some(theme) = config.theme {
+        style::set_theme(theme);
+    }
+    if let Some(date_format) = &config.date_format {
+        let result = style::set_date_format(date_format);
+        if display_result(result, None, Some("Could not load configuration")).is_err() {
+            return;
+        }
+    }
 
-    #[arg(short, long, value_parser = clap::value_parser!(u32).range(1..), num_args=1..)]
-    /// Remove a memo by ID
-    remove: Option<Vec<u32>>,
-}
+    // The flags and environment variables take precedence over the configuration file
+    let backend = backend.or(config.backend).unwrap_or_default();
+    let trash_days = trash_days.or(config.trash_days);
+    let lock_timeout = config
+        .lock_timeout
+        .map_or(lock::DEFAULT_TIMEOUT, |seconds| {
+            std::time::Duration::from_secs(seconds.into())
+        });
+    let display = config.display.unwrap_or(data::DisplayMode::GroupByDate);
+    let data_dir = std::env::var_os("MEMO_DATA_DIR")
+        .filter(|data_dir| !data_dir.is_empty())
+        .map(std::path::PathBuf::from);
+    let current_dir = std::env::current_dir().unwrap_or_else(|_| std::path::PathBuf::from("."));
+    let local = (!global).then(|| app::find_local(&current_dir)).flatten();
+    let (mut base_config, source) = config.app_config("memo", backend, file, data_dir, local);
+    if let Some(duplicates) = duplicates {
+        base_config.duplicates = duplicates;
+    }
+    let notebook = notebook
+        .or(config.notebook)
+        .unwrap_or_else(|| app::DEFAULT_NOTEBOOK.to_string());
+    let Ok(app_config) = display_result(
+        base_config.notebook(&notebook),
+        None,
+        Some("Invalid notebook"),
+    ) else {
+        return;
+    };
 
-fn main() {
-    let cli = Cli::parse();
-    let app_config = app::AppConfig::new("memo", USERDATA);
-    let has_no_flags = !cli.list && !cli.init && cli.message.is_none() && cli.remove.is_none();
+    if uses_legacy_flags {
+        eprintln!(
+            "{}",
+            style::str(
+                "Warning: the '--list', '--init', '--sorted' and '--remove' flags are deprecated, use 'memo ls', 'memo init' and 'memo rm' instead",
+                style::Options::Muted
+            )
+        );
+    }
 
     // Handle 'Init' command
-    if cli.init {
+    if let [cli::Command::Init { local }] = commands.as_slice() {
+        let target = if *local {
+            app::AppConfig::from_path("memo", &current_dir.join(app::LOCAL_FILE))
+        } else {
+            app_config
+        };
+        let result = if *local {
+            commands::init_local(&target)
+        } else {
+            commands::init(&target)
+        };
         let _ = display_result(
-            commands::init(&app_config),
-            Some("Initialized data file"),
+            result,
+            Some(&format!(
+                "Initialized data file '{}'",
+                target.data_file_path().display()
+            )),
             Some("Initialization error"),
         );
         return;
     }
 
-    let mut memo_data = models::MemoData::new();
+    // Handle 'Where' command
+    if let [cli::Command::Where] = commands.as_slice() {
+        let mut origin = source.to_string();
+        if notebook != app::DEFAULT_NOTEBOOK {
+            origin = format!("notebook '{}', {}", notebook, origin);
+        }
+        if !app_config.data_file_path().exists() {
+            origin.push_str(", not created yet");
+        }
+        println!(
+            "{} {}",
+            app_config.data_file_path().display(),
+            style::str(&format!("({})", origin), style::Options::Muted)
+        );
+        return;
+    }
+
+    // Handle 'Migrate' command
+    if let [cli::Command::Migrate { to: (to, format) }] = commands.as_slice() {
+        let result = lock::Lock::acquire(&app_config.data_file_path(), lock_timeout)
+            .and_then(|_lock| commands::migrate(&app_config, backend, *to, *format));
+        if let Ok(count) = display_result(result, None, Some("Migration error")) {
+            let name = commands::format_name(*to, *format);
+            let message = if backend == *to {
+                format!(
+                    "Converted {} memos to {}, the original file was kept as '{}'",
+                    count,
+                    name,
+                    data::backup_path(&app_config.data_file_path()).display()
+                )
+            } else {
+                format!(
+                    "Migrated {} memos to {}, use it with '--backend {}' or MEMO_BACKEND={}",
+                    count, name, to, to
+                )
+            };
+            eprintln!("{}", style::str(&message, style::Options::Title));
+        }
+        return;
+    }
+
+    // Handle 'Doctor' command, which reads the data file without loading it
+    if let [cli::Command::Doctor { fix }] = commands.as_slice() {
+        let result = lock::Lock::acquire(&app_config.data_file_path(), lock_timeout)
+            .and_then(|_lock| commands::doctor(&app_config, backend, *fix));
+        let result = display_result(result, None, Some("Doctor error"));
+        // With --strict, problems that were not fixed fail the run, e.g. in CI
+        if strict && result.map_or(true, |count| count > 0 && !*fix) {
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    // Handle 'Backup' command
+    if let [cli::Command::Backup { command }] = commands.as_slice() {
+        let result =
+            lock::Lock::acquire(&app_config.data_file_path(), lock_timeout).and_then(|_lock| {
+                match command {
+                    cli::BackupCommand::Ls => commands::backup_ls(&app_config),
+                    cli::BackupCommand::Restore { n } => {
+                        commands::backup_restore(&app_config, *n as usize)?;
+                        println!("Restored backup {}", n);
+                        Ok(())
+                    }
+                }
+            });
+        let _ = display_result(result, None, Some("Backup error"));
+        return;
+    }
+
+    // Handle 'Daemon' command, which reads the data file again whenever it changes
+    if let [cli::Command::Daemon {
+        notify,
+        interval,
+        once,
+    }] = commands.as_slice()
+    {
+        let notifier = notify.clone().or(config.notifier).unwrap_or_default();
+        let _ = display_result(
+            commands::daemon(
+                &app_config,
+                backend,
+                &notifier,
+                std::time::Duration::from_secs(*interval),
+                lock_timeout,
+                *once,
+            ),
+            None,
+            Some("Daemon error"),
+        );
+        return;
+    }
+
+    // Handle 'Notebooks' command
+    if let [cli::Command::Notebooks { command }] = commands.as_slice() {
+        let result = match command {
+            cli::NotebooksCommand::Ls => commands::notebooks_ls(&base_config, backend, &notebook),
+            cli::NotebooksCommand::Create { name } => {
+                commands::notebooks_create(&base_config, name)
+            }
+            cli::NotebooksCommand::Rename { name, new_name } => {
+                commands::notebooks_rename(&base_config, &config_path, name, new_name)
+            }
+            cli::NotebooksCommand::Delete { name, force } => {
+                commands::notebooks_delete(&base_config, &config_path, backend, name, *force)
+            }
+        };
+        let _ = display_result(result, None, Some("Notebook error"));
+        return;
+    }
+
+    // Handle 'Ls --all-notebooks', which reads every notebook instead of the current one
+    if let [cli::Command::Ls {
+        sorted,
+        by_priority,
+        all,
+        done,
+        tags,
+        format,
+        all_notebooks: true,
+        upcoming,
+    }] = commands.as_slice()
+    {
+        let _ = display_result(
+            list_options(*sorted, *by_priority, *all, *done, tags, *upcoming, display).and_then(
+                |(mode, filter)| {
+                    commands::list_all_notebooks(&base_config, backend, mode, &filter, *format)
+                },
+            ),
+            None,
+            Some("Could not list memos"),
+        );
+        return;
+    }
+
+    if notebook != app::DEFAULT_NOTEBOOK && !app_config.data_file_path().exists() {
+        let _ = display_result::<()>(
+            Err(anyhow::anyhow!(
+                "Notebook '{}' not found, create it with 'memo notebooks create {}'",
+                notebook,
+                notebook
+            )),
+            None,
+            None,
+        );
+        return;
+    }
+
+    let snooze = config
+        .snooze
+        .as_deref()
+        .and_then(|snooze| memo::dates::parse_duration(snooze).ok())
+        .unwrap_or_else(|| chrono::Duration::minutes(commands::DEFAULT_SNOOZE_MINUTES));
+    let options = Options {
+        trash_days,
+        display,
+        lock_timeout,
+        strict,
+        snooze,
+    };
+    match backend {
+        app::Backend::Text => execute(models::MemoData::new(), &app_config, commands, &options),
+        app::Backend::Sqlite => execute(sqlite::SqliteData::new(), &app_config, commands, &options),
+    }
+}
+
+/// Settings that change how the commands run, from the flags or the configuration file
+struct Options {
+    /// Days after which memos are deleted from the trash
+    trash_days: Option<u32>,
+    /// Layout of `memo ls` when neither `--sorted` nor `--by-priority` is given
+    display: data::DisplayMode,
+    /// Time to wait for another memo process to release the data file
+    lock_timeout: std::time::Duration,
+    /// Fail when lines of the data file are skipped or have duplicate ids
+    strict: bool,
+    /// Time `memo snooze` puts a memo off by when none is given
+    snooze: chrono::Duration,
+}
+
+/// Loads the data file, purges the old memos from the trash and runs the commands against it.
+/// The data file is locked for the whole run, so concurrent memo processes cannot overwrite
+/// each other's changes, except while `memo edit` waits for the editor.
+fn execute(
+    mut memo_data: impl data::DataFile,
+    app_config: &app::AppConfig,
+    commands: Vec<cli::Command>,
+    options: &Options,
+) {
+    let Ok(mut lock) = display_result(
+        lock::Lock::acquire(&app_config.data_file_path(), options.lock_timeout),
+        None,
+        Some("Could not lock data file"),
+    ) else {
+        return;
+    };
 
     if display_result(
-        data::DataFile::load(&mut memo_data, &app_config),
+        memo_data.load(app_config),
         None,
         Some("Could not load data file"),
     )
@@ -63,39 +325,341 @@ fn main() {
         return; //  exit if the data file cannot be loaded
     }
 
-    if let Some(message) = cli.message {
-        let _ = display_result(
-            commands::add(&mut memo_data, &app_config, message.join(" ")),
-            None,
-            Some("Could not add memo"),
+    let mut warnings: Vec<String> = memo_data
+        .bad_lines()
+        .iter()
+        .map(|bad_line| {
+            format!(
+                "skipped line {} of the data file: {}",
+                bad_line.number, bad_line.reason
+            )
+        })
+        .collect();
+    warnings.extend(
+        memo_data
+            .duplicates()
+            .iter()
+            .map(|duplicate| format!("line {} of the data file: {}", duplicate.number, duplicate)),
+    );
+    if !warnings.is_empty() {
+        let level = if options.strict { "Error" } else { "Warning" };
+        for warning in &warnings {
+            eprintln!(
+                "{}",
+                style::str(
+                    &format!("{}: {}", level, warning),
+                    if options.strict {
+                        style::Options::Error
+                    } else {
+                        style::Options::Muted
+                    }
+                )
+            );
+        }
+        eprintln!(
+            "{}",
+            style::str(
+                "Run 'memo doctor' for details and 'memo doctor --fix' to repair the data file",
+                style::Options::Muted
+            )
         );
+        if options.strict {
+            std::process::exit(1);
+        }
     }
 
-    // Handle remove
-    if let Some(id) = cli.remove {
+    if let Some(days) = options.trash_days {
         let _ = display_result(
-            commands::remove(&mut memo_data, &app_config, id),
+            commands::purge_trash(&mut memo_data, app_config, days),
             None,
-            Some("Could not remove memo"),
+            Some("Could not purge the trash"),
         );
     }
 
-    // Handle list
-    if cli.list || has_no_flags {
-        let mode = if cli.sorted {
-            data::DisplayMode::Sorted
-        } else {
-            data::DisplayMode::GroupByDate
-        };
+    for command in commands {
+        run(&mut memo_data, app_config, &mut lock, options, command);
+    }
+}
 
-        let _ = display_result(
-            commands::list(&memo_data, mode),
-            None,
-            Some("Could not list memos"),
-        );
+/// Returns the ids of memos given by their id or a prefix of their UUID
+fn resolve_all(memo_data: &impl data::DataFile, ids: &[data::MemoRef]) -> anyhow::Result<Vec<u32>> {
+    ids.iter().map(|id| memo_data.resolve(id)).collect()
+}
+
+/// Runs a single command against the loaded data
+fn run(
+    memo_data: &mut impl data::DataFile,
+    app_config: &app::AppConfig,
+    lock: &mut lock::Lock,
+    options: &Options,
+    command: cli::Command,
+) {
+    match command {
+        cli::Command::Add {
+            message,
+            due,
+            tags,
+            priority,
+            every,
+            at,
+        } => {
+            let _ = display_result(
+                commands::add(
+                    memo_data,
+                    app_config,
+                    message.join(" "),
+                    due,
+                    tags,
+                    priority,
+                    every.map(|every| (every, at)),
+                ),
+                None,
+                Some("Could not add memo"),
+            );
+        }
+        cli::Command::Rm { ids } => {
+            let _ = display_result(
+                resolve_all(memo_data, &ids)
+                    .and_then(|ids| commands::remove(memo_data, app_config, ids)),
+                None,
+                Some("Could not remove memo"),
+            );
+        }
+        cli::Command::Renumber => {
+            let _ = display_result(
+                commands::renumber(memo_data, app_config),
+                None,
+                Some("Could not renumber memos"),
+            );
+        }
+        cli::Command::Mv { id, new_id } => {
+            let _ = display_result(
+                memo_data
+                    .resolve(&id)
+                    .and_then(|id| commands::mv(memo_data, app_config, id, new_id)),
+                None,
+                Some("Could not move memo"),
+            );
+        }
+        cli::Command::Ls {
+            sorted,
+            by_priority,
+            all,
+            done,
+            tags,
+            format,
+            upcoming,
+            ..
+        } => {
+            let _ = display_result(
+                list_options(
+                    sorted,
+                    by_priority,
+                    all,
+                    done,
+                    &tags,
+                    upcoming,
+                    options.display,
+                )
+                .and_then(|(mode, filter)| commands::list(memo_data, mode, &filter, format)),
+                None,
+                Some("Could not list memos"),
+            );
+        }
+        cli::Command::Done { ids } => {
+            let _ = display_result(
+                resolve_all(memo_data, &ids)
+                    .and_then(|ids| commands::done(memo_data, app_config, ids)),
+                None,
+                Some("Could not mark memo as done"),
+            );
+        }
+        cli::Command::Undone { ids } => {
+            let _ = display_result(
+                resolve_all(memo_data, &ids)
+                    .and_then(|ids| commands::undone(memo_data, app_config, ids)),
+                None,
+                Some("Could not mark memo as not done"),
+            );
+        }
+        cli::Command::Tags => {
+            let _ = display_result(commands::tags(memo_data), None, Some("Could not list tags"));
+        }
+        cli::Command::Search {
+            query,
+            word,
+            regex,
+            rank,
+        } => {
+            let mode = if word {
+                search::SearchMode::Word
+            } else if regex {
+                search::SearchMode::Regex
+            } else {
+                search::SearchMode::Substring
+            };
+            let _ = display_result(
+                commands::search(memo_data, &query.join(" "), mode, rank),
+                None,
+                Some("Could not search memos"),
+            );
+        }
+        cli::Command::Prio { id, level } => {
+            let _ = display_result(
+                memo_data
+                    .resolve(&id)
+                    .and_then(|id| commands::prio(memo_data, app_config, id, level)),
+                None,
+                Some("Could not set priority"),
+            );
+        }
+        cli::Command::Snooze { id, when } => {
+            let postpone = when.unwrap_or(memo::dates::Postpone::For(options.snooze));
+            let _ = display_result(
+                memo_data
+                    .resolve(&id)
+                    .and_then(|id| commands::snooze(memo_data, app_config, id, postpone)),
+                None,
+                Some("Could not snooze memo"),
+            );
+        }
+        cli::Command::Edit { id, text } => {
+            let text = (!text.is_empty()).then(|| text.join(" "));
+            let _ = display_result(
+                memo_data
+                    .resolve(&id)
+                    .and_then(|id| commands::edit(memo_data, app_config, lock, id, text)),
+                None,
+                Some("Could not edit memo"),
+            );
+        }
+        cli::Command::Undo => {
+            let _ = display_result(
+                commands::undo(memo_data, app_config),
+                None,
+                Some("Could not undo"),
+            );
+        }
+        cli::Command::Redo => {
+            let _ = display_result(
+                commands::redo(memo_data, app_config),
+                None,
+                Some("Could not redo"),
+            );
+        }
+        cli::Command::Trash { command } => {
+            let result = match command {
+                cli::TrashCommand::Ls => commands::trash_ls(memo_data),
+                cli::TrashCommand::Restore { ids } => ids
+                    .iter()
+                    .map(|id| memo_data.resolve_trashed(id))
+                    .collect::<anyhow::Result<_>>()
+                    .and_then(|ids| commands::trash_restore(memo_data, app_config, ids)),
+                cli::TrashCommand::Empty => commands::trash_empty(memo_data, app_config),
+            };
+            let _ = display_result(result, None, Some("Could not update the trash"));
+        }
+        cli::Command::Show { id } => {
+            let _ = display_result(
+                memo_data
+                    .resolve(&id)
+                    .and_then(|id| commands::show(memo_data, id)),
+                None,
+                Some("Could not show memo"),
+            );
+        }
+        cli::Command::Notebooks { .. } => {
+            let _ = display_result::<()>(
+                Err(anyhow::anyhow!("'notebooks' must be run on its own")),
+                None,
+                Some("Notebook error"),
+            );
+        }
+        cli::Command::Config { .. } => {
+            let _ = display_result::<()>(
+                Err(anyhow::anyhow!("'config' must be run on its own")),
+                None,
+                Some("Configuration error"),
+            );
+        }
+        cli::Command::Doctor { .. } => {
+            let _ = display_result::<()>(
+                Err(anyhow::anyhow!("'doctor' must be run on its own")),
+                None,
+                Some("Doctor error"),
+            );
+        }
+        cli::Command::Daemon { .. } => {
+            let _ = display_result::<()>(
+                Err(anyhow::anyhow!("'daemon' must be run on its own")),
+                None,
+                Some("Daemon error"),
+            );
+        }
+        cli::Command::Backup { .. } => {
+            let _ = display_result::<()>(
+                Err(anyhow::anyhow!("'backup' must be run on its own")),
+                None,
+                Some("Backup error"),
+            );
+        }
+        cli::Command::Migrate { .. } => {
+            let _ = display_result::<()>(
+                Err(anyhow::anyhow!("'migrate' must be run on its own")),
+                None,
+                Some("Migration error"),
+            );
+        }
+        cli::Command::Init { .. } | cli::Command::Where => {
+            let _ = display_result::<()>(
+                Err(anyhow::anyhow!(
+                    "'init' and 'where' must be run on their own"
+                )),
+                None,
+                None,
+            );
+        }
     }
 }
 
+/// Returns the display mode and filter of `memo ls` from its flags
+fn list_options(
+    sorted: bool,
+    by_priority: bool,
+    all: bool,
+    done: bool,
+    tags: &[String],
+    upcoming: Option<chrono::Duration>,
+    display: data::DisplayMode,
+) -> Result<(data::DisplayMode, data::Filter)> {
+    let mode = if sorted {
+        data::DisplayMode::Sorted
+    } else if by_priority {
+        data::DisplayMode::ByPriority
+    } else {
+        display
+    };
+    let status = match (all, done) {
+        (true, _) => data::Status::All,
+        (_, true) => data::Status::Done,
+        _ => data::Status::Pending,
+    };
+    let (exclude_tags, include_tags): (Vec<String>, Vec<String>) =
+        tags.iter().cloned().partition(|tag| tag.starts_with('-'));
+    let filter = data::Filter {
+        status,
+        include_tags,
+        exclude_tags: exclude_tags
+            .iter()
+            .map(|tag| tag.trim_start_matches('-').to_string())
+            .collect(),
+        due_within: upcoming
+            .map(|upcoming| data::Filter::upcoming(chrono::Local::now().naive_local(), upcoming))
+            .transpose()?,
+    };
+    Ok((mode, filter))
+}
+
 /// Prints  restult or error to stderror if error found. Option ok and err messages can be customized.
 fn display_result<T>(
     result: Result<T>,
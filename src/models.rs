@@ -1,20 +1,256 @@
+use crate::data::{DataFile, DisplayMode, DuplicatePolicy, Filter, Format};
+use crate::recur::Recurrence;
+use crate::search::Query;
 use crate::style;
 use anyhow::{anyhow, Context, Result};
 use chrono::prelude::*;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
 use std::str::FromStr;
+use uuid::Uuid;
 
 /// Struct that holds all the data of the application
 /// The data is stored in a HashMap where the key is the id of the item and the value is the content.
+/// The format is the one of the file the data was loaded from, and is kept when saving.
+/// Removed items are kept in the trash until it is emptied.
+/// When several notebooks are combined, `origins` holds the notebook and id of each item.
+/// Lines of the file that could not be read are kept in `bad_lines`, and the data cannot be
+/// saved until they are moved aside with `memo doctor --fix`. Lines whose id was already used
+/// are resolved by the duplicate id policy and listed in `duplicates`.
 pub struct MemoData {
     pub contents: HashMap<u32, Content>,
+    pub format: Format,
+    pub trash: Vec<Trashed>,
+    pub origins: HashMap<u32, Origin>,
+    pub bad_lines: Vec<BadLine>,
+    pub duplicates: Vec<DuplicateLine>,
+}
+
+/// A line of a data file, numbered from 1, with the item read from it or why it could not be read
+pub struct ParsedLine {
+    pub number: usize,
+    pub line: String,
+    pub item: Result<(u32, Content)>,
+}
+
+/// A line of a data file that could not be read, numbered from 1
+#[derive(Clone, Debug, PartialEq)]
+pub struct BadLine {
+    pub number: usize,
+    pub line: String,
+    pub reason: String,
+}
+
+/// A line of a data file using the id of another line, that was dropped or given a new id
+#[derive(Clone, Debug, PartialEq)]
+pub struct DuplicateLine {
+    pub number: usize,
+    pub line: String,
+    pub id: u32,
+    /// Line that kept the id
+    pub kept_line: usize,
+    /// New id of the line when renumbered, None when it was dropped
+    pub new_id: Option<u32>,
+}
+
+impl fmt::Display for DuplicateLine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.new_id {
+            Some(new_id) => write!(
+                f,
+                "Duplicate id {} renumbered to {}, line {} keeps it",
+                self.id, new_id, self.kept_line
+            ),
+            None => write!(
+                f,
+                "Duplicate id {} dropped, line {} keeps it",
+                self.id, self.kept_line
+            ),
+        }
+    }
+}
+
+impl ParsedLine {
+    /// Returns the line as a BadLine if it could not be read
+    pub fn bad_line(&self) -> Option<BadLine> {
+        let error = self.item.as_ref().err()?;
+        Some(BadLine {
+            number: self.number,
+            line: self.line.clone(),
+            reason: format!("{:#}", error).replace('\n', " "),
+        })
+    }
+}
+
+/// Notebook an item of a combined view comes from, with the id it has there
+#[derive(Clone, Debug, PartialEq)]
+pub struct Origin {
+    pub notebook: String,
+    pub id: u32,
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.notebook, self.id)
+    }
+}
+
+/// A memo in the trash, with the id it had and the date and time it was removed
+#[derive(Clone, Debug)]
+pub struct Trashed {
+    pub id: u32,
+    pub removed_at: NaiveDateTime,
+    pub content: Content,
 }
 
 /// Stores the content of a 'memo'
-/// Includes the text, the date and time
+/// Includes the text, the date and time it was created, an optional due date,
+/// the date and time it was completed, if it was, its tags, an optional priority and
+/// the date and time its text was last edited.
+/// The UUID identifies the memo everywhere, while its id is short and can be reused.
+/// A recurring memo has a recurrence rule, and its due date is its next occurrence.
+/// `snoozed` counts the times its due date was put off with `memo snooze`.
+#[derive(Clone, Debug)]
 pub struct Content {
     pub text: String,
     pub date_time: NaiveDateTime,
+    pub due: Option<NaiveDateTime>,
+    pub done: Option<NaiveDateTime>,
+    pub tags: Vec<String>,
+    pub priority: Option<Priority>,
+    pub updated_at: Option<NaiveDateTime>,
+    pub uuid: Uuid,
+    pub recurrence: Option<Recurrence>,
+    pub snoozed: u32,
+}
+
+/// Priority of a memo, from 'A' (highest) to 'Z' (lowest), as in todo.txt
+/// The names high, medium and low are accepted for 'A', 'B' and 'C'.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Priority(char);
+
+impl Priority {
+    /// Create a Priority from a letter between 'A' and 'Z', case insensitive
+    pub fn new(letter: char) -> Option<Self> {
+        let letter = letter.to_ascii_uppercase();
+        letter.is_ascii_uppercase().then_some(Priority(letter))
+    }
+
+    /// Returns the letter of the priority
+    pub fn letter(&self) -> char {
+        self.0
+    }
+
+    /// Returns true for the highest priority, 'A'
+    pub fn is_high(&self) -> bool {
+        self.0 == 'A'
+    }
+}
+
+impl Content {
+    /// Create a new Content without due date, with a random UUID
+    pub fn new(text: &str, date_time: NaiveDateTime) -> Self {
+        Content {
+            text: text.to_string(),
+            date_time,
+            due: None,
+            done: None,
+            tags: Vec::new(),
+            priority: None,
+            updated_at: None,
+            uuid: Uuid::new_v4(),
+            recurrence: None,
+            snoozed: 0,
+        }
+    }
+
+    /// Returns the occurrence of a recurring memo that follows its current one, skipping the
+    /// ones that are already past or that its due date was snoozed beyond. The current
+    /// occurrence is the anchor of a snoozed memo, its due date otherwise.
+    /// None if the memo does not recur.
+    pub fn next_occurrence(&self, now: NaiveDateTime) -> Option<NaiveDateTime> {
+        let recurrence = self.recurrence.as_ref()?;
+        let due = self.due?;
+        let current = recurrence.anchor.unwrap_or(due);
+        recurrence.first_after(recurrence.next(current)?, now.max(due))
+    }
+
+    /// Returns the due dates of the memo from `from` to `until`, both included: every
+    /// occurrence of a recurring memo, or its due date. None for memos that are done.
+    /// A snoozed recurring memo is due at its due date, then at the occurrences after it.
+    pub fn occurrences(&self, from: NaiveDateTime, until: NaiveDateTime) -> Vec<NaiveDateTime> {
+        match (self.done, self.due, &self.recurrence) {
+            (None, Some(due), Some(recurrence)) if recurrence.anchor.is_some() => {
+                let mut occurrences = Vec::new();
+                if due >= from && due <= until {
+                    occurrences.push(due);
+                }
+                if let Some(next) = self.next_occurrence(due) {
+                    occurrences.extend(recurrence.occurrences(next, from, until));
+                }
+                occurrences
+            }
+            (None, Some(due), Some(recurrence)) => recurrence.occurrences(due, from, until),
+            (None, Some(due), None) if due >= from && due <= until => vec![due],
+            _ => Vec::new(),
+        }
+    }
+
+    /// Date time a memo is listed at when grouped by date: the next occurrence of a
+    /// recurring memo, or its creation time
+    pub fn listed_at(&self) -> NaiveDateTime {
+        match (&self.recurrence, self.due) {
+            (Some(_), Some(due)) => due,
+            _ => self.date_time,
+        }
+    }
+
+    /// Returns true if the content has the tag
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t == tag)
+    }
+
+    /// Returns true if the due date is in the past and the memo is not done
+    pub fn is_overdue(&self, now: NaiveDateTime) -> bool {
+        self.done.is_none() && self.due.is_some_and(|due| due < now)
+    }
+
+    /// Returns true if the due date is today, not yet passed and the memo is not done
+    pub fn is_due_today(&self, now: NaiveDateTime) -> bool {
+        self.done.is_none()
+            && self
+                .due
+                .is_some_and(|due| due >= now && due.date() == now.date())
+    }
+}
+
+/// Normalize a tag name, removing the '+' or '#' prefix.
+/// Tags start with a letter and only contain letters, digits, '-', '_' and '/'.
+pub fn normalize_tag(tag: &str) -> Option<String> {
+    let tag = tag.trim_start_matches(['+', '#']).to_lowercase();
+    let valid = tag.starts_with(|c: char| c.is_alphabetic())
+        && tag
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '/'));
+    valid.then_some(tag)
+}
+
+/// Split the inline tags (`+tag` or `#tag`) from a memo text.
+/// Returns the text without the tags and the tags found, without duplicates.
+pub fn split_tags(text: &str) -> (String, Vec<String>) {
+    let mut words = Vec::new();
+    let mut tags: Vec<String> = Vec::new();
+    for word in text.split_whitespace() {
+        match normalize_tag(word) {
+            Some(tag) if word.starts_with(['+', '#']) => {
+                if !tags.contains(&tag) {
+                    tags.push(tag);
+                }
+            }
+            _ => words.push(word),
+        }
+    }
+    (words.join(" "), tags)
 }
 
 impl MemoData {
@@ -22,6 +258,48 @@ impl MemoData {
     pub fn new() -> Self {
         MemoData {
             contents: HashMap::new(),
+            format: Format::default(),
+            trash: Vec::new(),
+            origins: HashMap::new(),
+            bad_lines: Vec::new(),
+            duplicates: Vec::new(),
+        }
+    }
+
+    /// Combine the items of several notebooks into a single read-only view.
+    /// Items get new ids in the order they were created, so the view can be grouped by date,
+    /// and keep their notebook and original id in `origins`.
+    pub fn combine<D: DataFile>(notebooks: &[(String, D)]) -> Self {
+        let mut items: Vec<(&String, u32, &Content)> = notebooks
+            .iter()
+            .flat_map(|(notebook, data)| {
+                data.sorted_ids()
+                    .into_iter()
+                    .filter_map(move |id| data.get(id).map(|content| (notebook, id, content)))
+            })
+            .collect();
+        items.sort_by_key(|(_, _, content)| content.date_time);
+
+        let mut combined = MemoData::new();
+        for (new_id, (notebook, id, content)) in (1..).zip(items) {
+            combined.contents.insert(new_id, content.clone());
+            combined.origins.insert(
+                new_id,
+                Origin {
+                    notebook: notebook.clone(),
+                    id,
+                },
+            );
+        }
+        combined
+    }
+
+    /// Returns the label of an item when listing it: its notebook and id in a combined view,
+    /// otherwise its id padded to two digits
+    fn label(&self, id: u32) -> String {
+        match self.origins.get(&id) {
+            Some(origin) => origin.to_string(),
+            None => format!("{:0>#2}", id),
         }
     }
 
@@ -33,6 +311,110 @@ impl MemoData {
             .collect()
     }
 
+    /// Parse every line of data from file, keeping the ones that cannot be read.
+    /// Empty lines are skipped.
+    pub fn parse_lines(data: &str) -> Vec<ParsedLine> {
+        data.lines()
+            .enumerate()
+            .filter(|(_, line)| !line.is_empty())
+            .map(|(number, line)| ParsedLine {
+                number: number + 1,
+                line: line.to_string(),
+                item: vaidate_line(line),
+            })
+            .collect()
+    }
+
+    /// Set the items read from parsed lines, and keep the lines that could not be read.
+    /// When an id is used by several lines, they are resolved by the duplicate id policy.
+    /// With `DuplicatePolicy::Error`, the later lines are kept in `bad_lines`.
+    pub fn set_lines(&mut self, lines: Vec<ParsedLine>, policy: DuplicatePolicy) {
+        self.contents.clear();
+        self.bad_lines.clear();
+        self.duplicates.clear();
+
+        let mut kept_lines: HashMap<u32, usize> = HashMap::new();
+        for parsed in &lines {
+            if let Ok((id, _)) = parsed.item {
+                if policy == DuplicatePolicy::KeepLast {
+                    kept_lines.insert(id, parsed.number);
+                } else {
+                    kept_lines.entry(id).or_insert(parsed.number);
+                }
+            }
+        }
+        let mut next_id = kept_lines.keys().max().map_or(1, |id| id + 1);
+
+        for parsed in lines {
+            let bad_line = parsed.bad_line();
+            let (id, content) = match parsed.item {
+                Ok(item) => item,
+                Err(_) => {
+                    self.bad_lines.extend(bad_line);
+                    continue;
+                }
+            };
+            let kept_line = kept_lines[&id];
+            if kept_line == parsed.number {
+                self.contents.insert(id, content);
+                continue;
+            }
+
+            let mut duplicate = DuplicateLine {
+                number: parsed.number,
+                line: parsed.line,
+                id,
+                kept_line,
+                new_id: None,
+            };
+            match policy {
+                DuplicatePolicy::Error => self.bad_lines.push(BadLine {
+                    number: duplicate.number,
+                    line: duplicate.line,
+                    reason: format!("Duplicate id {}, already used on line {}", id, kept_line),
+                }),
+                DuplicatePolicy::KeepFirst | DuplicatePolicy::KeepLast => {
+                    self.duplicates.push(duplicate)
+                }
+                DuplicatePolicy::Renumber => {
+                    duplicate.new_id = Some(next_id);
+                    self.contents.insert(next_id, content);
+                    self.duplicates.push(duplicate);
+                    next_id += 1;
+                }
+            }
+        }
+        self.repair_uuids();
+    }
+
+    /// Gives a new UUID to the memos whose UUID is already used by a memo with a lower id,
+    /// e.g. lines copied in the data file. Returns the ids of the memos given a new UUID.
+    pub fn repair_uuids(&mut self) -> Vec<u32> {
+        let mut seen = HashSet::new();
+        let mut repaired = Vec::new();
+        for id in self.sorted_ids() {
+            let content = self.contents.get_mut(&id).expect("id of a memo");
+            if !seen.insert(content.uuid) {
+                content.uuid = Uuid::new_v4();
+                seen.insert(content.uuid);
+                repaired.push(id);
+            }
+        }
+        repaired
+    }
+
+    /// Returns an error if some lines of the data file could not be read,
+    /// as writing the data would drop them
+    pub fn check_bad_lines(&self) -> Result<()> {
+        if self.bad_lines.is_empty() {
+            return Ok(());
+        }
+        Err(anyhow!(
+            "{} lines of the data file could not be read, run 'memo doctor --fix' to move them aside first",
+            self.bad_lines.len()
+        ))
+    }
+
     /// gets the content of an item given its id
     pub fn get(&self, id: u32) -> Option<&Content> {
         self.contents.get(&id)
@@ -45,19 +427,164 @@ impl MemoData {
         ids
     }
 
-    /// Returns string with contents split by date
-    pub fn group_by_date(&self) -> Result<String> {
+    /// Returns a vector with the sorted ids of the items that pass the filter
+    pub fn filtered_ids(&self, filter: &Filter) -> Vec<u32> {
+        self.sorted_ids()
+            .into_iter()
+            .filter(|id| filter.matches(&self.contents[id]))
+            .collect()
+    }
+
+    /// Returns the ids of the items that pass the filter in the order they are shown in `mode`:
+    /// by id for `Sorted`, newest first for `GroupByDate` and highest priority first for `ByPriority`.
+    /// Recurring items are dated by their next occurrence in `GroupByDate`, see `Content::listed_at`.
+    pub fn ordered_ids(&self, mode: DisplayMode, filter: &Filter) -> Vec<u32> {
+        let mut ids = self.filtered_ids(filter);
+        match mode {
+            DisplayMode::Sorted => {}
+            DisplayMode::GroupByDate => {
+                ids.reverse();
+                ids.sort_by_key(|id| std::cmp::Reverse(self.contents[id].listed_at()));
+            }
+            DisplayMode::ByPriority => ids.sort_by_key(|id| {
+                let content = &self.contents[id];
+                (
+                    content.priority.is_none(),
+                    content.priority,
+                    content.due.is_none(),
+                    content.due,
+                )
+            }),
+        }
+        ids
+    }
+
+    /// Returns string with the items that pass the filter, one per line, sorted by id
+    pub fn sorted(&self, filter: &Filter) -> String {
+        self.filtered_ids(filter)
+            .iter()
+            .map(|id| match self.origins.get(id) {
+                Some(origin) => format!("{}: {}\n", origin, self.contents[id]),
+                None => format!("{}: {}\n", id, self.contents[id]),
+            })
+            .collect()
+    }
+
+    /// Returns string with contents split by date.
+    /// When the filter has a due window, the occurrences in it are listed instead, see `upcoming`.
+    pub fn group_by_date(&self, filter: &Filter) -> Result<String> {
+        match filter.due_within {
+            Some((from, until)) => self.upcoming(filter, from, until),
+            None => self.grouped_by_date(&self.ordered_ids(DisplayMode::GroupByDate, filter), None),
+        }
+    }
+
+    /// Returns string with the items that pass the filter at each of their due dates from `from`
+    /// to `until`, soonest first and split by date. Recurring items are listed once per occurrence.
+    pub fn upcoming(
+        &self,
+        filter: &Filter,
+        from: NaiveDateTime,
+        until: NaiveDateTime,
+    ) -> Result<String> {
+        let mut occurrences: Vec<(NaiveDateTime, u32)> = self
+            .filtered_ids(filter)
+            .into_iter()
+            .flat_map(|id| {
+                self.contents[&id]
+                    .occurrences(from, until)
+                    .into_iter()
+                    .map(move |due| (due, id))
+            })
+            .collect();
+        occurrences.sort();
+
+        let mut result = String::new();
+        let mut previous_date = None;
+        for (due, id) in occurrences {
+            if previous_date != Some(due.date()) {
+                result.push_str(&format!(
+                    "\n\n{}",
+                    style::str(
+                        &due.date().format("%A, %B %e, %Y").to_string(),
+                        style::Options::Title
+                    )
+                ));
+            }
+            let mut content = self.contents[&id].clone();
+            content.due = Some(due);
+            let id_and_time = format!("{}: {}", self.label(id), due.time());
+            result.push_str(&format!(
+                "\n{} {}",
+                style::str(&id_and_time, style::Options::Muted),
+                styled_text(&content, from, None)
+            ));
+            previous_date = Some(due.date());
+        }
+        Ok(result.trim_start().to_string())
+    }
+
+    /// Returns the ids of the items whose text matches the query.
+    /// Newest first, or most relevant first if `ranked`, newest first within the same relevance.
+    pub fn search_ids(&self, query: &Query, ranked: bool) -> Vec<u32> {
+        let mut ids: Vec<u32> = self
+            .sorted_ids()
+            .into_iter()
+            .rev()
+            .filter(|id| query.matches(&self.contents[id].text))
+            .collect();
+        if ranked {
+            ids.sort_by_key(|id| std::cmp::Reverse(query.score(&self.contents[id].text)));
+        } else {
+            ids.sort_by_key(|id| std::cmp::Reverse(self.contents[id].listed_at()));
+        }
+        ids
+    }
+
+    /// Returns string with the items whose text matches the query, with the matches highlighted.
+    /// Items are split by date like `group_by_date`, or listed by relevance if `ranked`.
+    pub fn search(&self, query: &Query, ranked: bool) -> Result<String> {
+        let ids = self.search_ids(query, ranked);
+        if !ranked {
+            return self.grouped_by_date(&ids, Some(query));
+        }
+
+        let now = Local::now().naive_local();
+        let mut lines = Vec::new();
+        for id in ids {
+            let content = self
+                .contents
+                .get(&id)
+                .with_context(|| format!("No item found for id '{}'", id))?;
+            let id_and_date = format!(
+                "{}: {}",
+                self.label(id),
+                style::date_time(content.date_time)
+            );
+            lines.push(format!(
+                "{} {}",
+                style::str(&id_and_date, style::Options::Muted),
+                styled_text(content, now, Some(query))
+            ));
+        }
+        Ok(lines.join("\n"))
+    }
+
+    /// Returns string with the items of `ids`, in that order, split by date.
+    /// Matches of the query are highlighted when one is given.
+    fn grouped_by_date(&self, ids: &[u32], query: Option<&Query>) -> Result<String> {
         let mut result = String::new();
         let mut previous_date =
             NaiveDate::from_ymd_opt(1, 1, 1).with_context(|| "Error creating date NaiveDate")?;
-        for id in self.sorted_ids().iter().rev() {
+        let now = Local::now().naive_local();
+        for id in ids {
             let content = self
                 .contents
                 .get(id)
                 .with_context(|| format!("No item found for id '{}'", id))?;
 
-            let currrent_date = content.date_time.date();
-            let current_time = content.date_time.time();
+            let currrent_date = content.listed_at().date();
+            let current_time = content.listed_at().time();
 
             if previous_date != currrent_date || result.is_empty() {
                 result.push_str(&format!(
@@ -69,11 +596,11 @@ impl MemoData {
                 ));
             }
 
-            let id_and_time = format!("{:0>#2}: {}", id, current_time);
+            let id_and_time = format!("{}: {}", self.label(*id), current_time);
             result.push_str(&format!(
                 "\n{} {}",
                 style::str(&id_and_time, style::Options::Muted),
-                content.text
+                styled_text(content, now, query)
             ));
             previous_date = currrent_date;
         }
@@ -81,10 +608,134 @@ impl MemoData {
         result = result.trim_start().to_string();
         Ok(result)
     }
+
+    /// Returns string with the items that pass the filter grouped by priority, highest first.
+    /// Within a priority items are sorted by due date, items without due date last.
+    pub fn by_priority(&self, filter: &Filter) -> Result<String> {
+        let mut result = String::new();
+        let now = Local::now().naive_local();
+        let mut previous_priority = None;
+        for id in self.ordered_ids(DisplayMode::ByPriority, filter) {
+            let content = self
+                .contents
+                .get(&id)
+                .with_context(|| format!("No item found for id '{}'", id))?;
+
+            if previous_priority != Some(content.priority) {
+                let title = match content.priority {
+                    Some(priority) => format!("Priority {}", priority),
+                    None => "No priority".to_string(),
+                };
+                result.push_str(&format!(
+                    "\n\n{}",
+                    style::str(&title, style::Options::Title)
+                ));
+            }
+
+            let id_and_date = format!(
+                "{}: {}",
+                self.label(id),
+                style::date_time(content.date_time)
+            );
+            result.push_str(&format!(
+                "\n{} {}",
+                style::str(&id_and_date, style::Options::Muted),
+                styled_text(content, now, None)
+            ));
+            previous_priority = Some(content.priority);
+        }
+        result = result.trim_start().to_string();
+        Ok(result)
+    }
+}
+
+/// Returns how many times a memo was snoozed in words, e.g. `snoozed 3 times`.
+/// None if it was never snoozed.
+pub fn snoozed_label(snoozed: u32) -> Option<String> {
+    match snoozed {
+        0 => None,
+        1 => Some("snoozed once".to_string()),
+        n => Some(format!("snoozed {} times", n)),
+    }
+}
+
+/// Returns the text of a content styled for the terminal, followed by its
+/// due date, recurrence, snooze count, tags and completion date. Matches of the query are highlighted when one is given.
+fn styled_text(content: &Content, now: NaiveDateTime, query: Option<&Query>) -> String {
+    let plain = |text: &str| match content.done {
+        Some(_) => style::str(text, style::Options::Done).to_string(),
+        None => text.to_string(),
+    };
+    let mut result = match query {
+        Some(query) => {
+            let mut highlighted = String::new();
+            let mut end = 0;
+            for range in query.match_ranges(&content.text) {
+                highlighted.push_str(&plain(&content.text[end..range.start]));
+                highlighted.push_str(
+                    &style::str(&content.text[range.clone()], style::Options::Highlight)
+                        .to_string(),
+                );
+                end = range.end;
+            }
+            highlighted.push_str(&plain(&content.text[end..]));
+            highlighted
+        }
+        None => plain(&content.text),
+    };
+
+    if let Some(priority) = content.priority {
+        let option = if priority.is_high() {
+            style::Options::HighPriority
+        } else {
+            style::Options::Muted
+        };
+        result = format!(
+            "{} {}",
+            style::str(&format!("({})", priority), option),
+            result
+        );
+    }
+
+    if let Some(due) = content.due {
+        let due = format!("(due {})", style::date_time(due));
+        let option = if content.is_overdue(now) {
+            style::Options::Overdue
+        } else if content.is_due_today(now) {
+            style::Options::DueToday
+        } else {
+            style::Options::Muted
+        };
+        result.push_str(&format!(" {}", style::str(&due, option)));
+    }
+
+    if let Some(recurrence) = &content.recurrence {
+        let every = format!("({})", recurrence.describe());
+        result.push_str(&format!(" {}", style::str(&every, style::Options::Muted)));
+    }
+
+    if let Some(snoozed) = snoozed_label(content.snoozed) {
+        let snoozed = format!("({})", snoozed);
+        result.push_str(&format!(" {}", style::str(&snoozed, style::Options::Muted)));
+    }
+
+    for tag in &content.tags {
+        result.push_str(&format!(
+            " {}",
+            style::str(&format!("+{}", tag), style::Options::Tag)
+        ));
+    }
+
+    if let Some(done) = content.done {
+        let done = format!("(done {})", style::date_time(done));
+        result.push_str(&format!(" {}", style::str(&done, style::Options::Muted)));
+    }
+    result
 }
 
 /// validate a line of file content
-/// Each line must have the format: id: [yyyy-mm-dd hh:mm:ss] content
+/// Each line must have the format: id: [yyyy-mm-dd hh:mm:ss] [attributes] content
+/// The attributes block is optional, see `Content::from_str`
 fn vaidate_line(line: &str) -> Result<(u32, Content)> {
     let mut parts = line.splitn(2, ':');
     let id = parts
@@ -94,8 +745,9 @@ fn vaidate_line(line: &str) -> Result<(u32, Content)> {
         .with_context(|| format!("Invalid id in line '{}'", line))?;
     let content = parts
         .next()
-        .ok_or_else(|| anyhow!("Missing content in line"))?
-        .trim();
+        .map(str::trim)
+        .filter(|content| !content.is_empty())
+        .ok_or_else(|| anyhow!("Missing content in line"))?;
     let content = Content::from_str(content)?;
     Ok((id, content))
 }
@@ -120,18 +772,83 @@ mod tests {
         assert_eq!(d.len(), 3);
     }
 
+    #[test]
+    fn test_memo_data_parse_lines() {
+        let data = "1: 2001-01-01 01:01:01 one\n\n2: 2001-13-01 01:01:01 bad\n3: 2003-03-03 03:03:03 three\n";
+        let mut d = MemoData::new();
+        d.set_lines(MemoData::parse_lines(data), DuplicatePolicy::Error);
+        assert_eq!(d.sorted_ids(), vec![1, 3]);
+        assert_eq!(d.bad_lines.len(), 1);
+        assert_eq!(d.bad_lines[0].number, 3);
+        assert_eq!(d.bad_lines[0].line, "2: 2001-13-01 01:01:01 bad");
+        assert!(d.bad_lines[0].reason.starts_with("invalid date time"));
+        assert!(d.check_bad_lines().is_err());
+
+        d.set_lines(
+            MemoData::parse_lines("1: 2001-01-01 01:01:01 one\n"),
+            DuplicatePolicy::Error,
+        );
+        assert!(d.bad_lines.is_empty());
+        assert!(d.check_bad_lines().is_ok());
+    }
+
+    #[test]
+    fn test_memo_data_duplicate_ids() {
+        let data = "1: 2001-01-01 01:01:01 one\n2: 2002-02-02 02:02:02 two\n1: 2003-03-03 03:03:03 three\n";
+        let text = |d: &MemoData, id| d.get(id).unwrap().text.clone();
+        let mut d = MemoData::new();
+
+        d.set_lines(MemoData::parse_lines(data), DuplicatePolicy::Error);
+        assert_eq!(text(&d, 1), "one");
+        assert_eq!(d.bad_lines.len(), 1);
+        assert_eq!(d.bad_lines[0].number, 3);
+        assert!(d.duplicates.is_empty());
+
+        d.set_lines(MemoData::parse_lines(data), DuplicatePolicy::KeepFirst);
+        assert_eq!(text(&d, 1), "one");
+        assert!(d.bad_lines.is_empty());
+        assert_eq!(d.duplicates[0].number, 3);
+        assert_eq!(d.duplicates[0].kept_line, 1);
+
+        d.set_lines(MemoData::parse_lines(data), DuplicatePolicy::KeepLast);
+        assert_eq!(text(&d, 1), "three");
+        assert_eq!(d.duplicates[0].number, 1);
+        assert_eq!(d.duplicates[0].kept_line, 3);
+
+        d.set_lines(MemoData::parse_lines(data), DuplicatePolicy::Renumber);
+        assert_eq!(d.sorted_ids(), vec![1, 2, 3]);
+        assert_eq!(text(&d, 3), "three");
+        assert_eq!(d.duplicates[0].new_id, Some(3));
+        assert!(d.check_bad_lines().is_ok());
+    }
+
+    #[test]
+    fn test_memo_data_duplicate_uuids() {
+        let uuid = "67e55044-10b1-426f-9247-bb680e5fe0c8";
+        let data = format!(
+            "1: 2001-01-01 01:01:01 one\n2: 2001-01-01 01:01:01 one\n\
+             3: 2003-03-03 03:03:03 [uuid:{uuid}] three\n4: 2003-03-03 03:03:03 [uuid:{uuid}] three\n"
+        );
+        let mut d = MemoData::new();
+        d.set_lines(MemoData::parse_lines(&data), DuplicatePolicy::Error);
+        let uuids: HashSet<Uuid> = d.contents.values().map(|c| c.uuid).collect();
+        assert_eq!(uuids.len(), 4);
+        assert_eq!(d.get(3).unwrap().uuid.to_string(), uuid);
+        assert!(d.repair_uuids().is_empty());
+    }
+
     #[test]
     fn test_memo_data_get() {
         let mut d = MemoData::new();
         d.contents.insert(
             1,
-            Content {
-                text: "one".to_string(),
-                date_time: NaiveDate::from_ymd_opt(2001, 1, 1)
+            Content::new(
+                "one",
+                NaiveDate::from_ymd_opt(2001, 1, 1)
                     .expect("Error creating date NaiveDate")
                     .and_hms_opt(1, 1, 1)
                     .expect("Error creating time NaiveDateTime"),
-            },
+            ),
         );
         assert_eq!(d.get(1).unwrap().text, "one");
         assert!(d.get(2).is_none());
@@ -161,4 +878,239 @@ mod tests {
         assert!(vaidate_line(line_missing_content).is_err());
         assert!(vaidate_line(line_invalid_date).is_err());
     }
+
+    #[test]
+    fn test_validate_line_due() {
+        let line = "1: 2001-01-01 01:01:01 [due:2001-01-02T09:30:00 uuid:67e55044-10b1-426f-9247-bb680e5fe0c1] one";
+        let (id, content) = vaidate_line(line).unwrap();
+        assert_eq!(id, 1);
+        assert_eq!(content.text, "one");
+        assert_eq!(
+            content.due.unwrap().format("%Y-%m-%d %H:%M:%S").to_string(),
+            "2001-01-02 09:30:00"
+        );
+    }
+
+    #[test]
+    fn test_content_due_state() {
+        let now = NaiveDate::from_ymd_opt(2001, 1, 2)
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap();
+        let mut content = Content::new("one", now);
+        assert!(!content.is_overdue(now));
+        assert!(!content.is_due_today(now));
+
+        content.due = now.checked_sub_signed(chrono::Duration::hours(1));
+        assert!(content.is_overdue(now));
+        assert!(!content.is_due_today(now));
+
+        content.due = now.checked_add_signed(chrono::Duration::hours(1));
+        assert!(!content.is_overdue(now));
+        assert!(content.is_due_today(now));
+
+        content.due = now.checked_add_signed(chrono::Duration::days(1));
+        assert!(!content.is_overdue(now));
+        assert!(!content.is_due_today(now));
+
+        content.due = now.checked_sub_signed(chrono::Duration::hours(1));
+        content.done = Some(now);
+        assert!(!content.is_overdue(now));
+    }
+
+    #[test]
+    fn test_split_tags() {
+        let (text, tags) = split_tags("call bob +work about #Project-x +work");
+        assert_eq!(text, "call bob about");
+        assert_eq!(tags, vec!["work", "project-x"]);
+
+        let (text, tags) = split_tags("fix issue #12 with C++ a+b");
+        assert_eq!(text, "fix issue #12 with C++ a+b");
+        assert!(tags.is_empty());
+    }
+
+    #[test]
+    fn test_normalize_tag() {
+        assert_eq!(normalize_tag("+Work"), Some("work".to_string()));
+        assert_eq!(
+            normalize_tag("home/kitchen"),
+            Some("home/kitchen".to_string())
+        );
+        assert_eq!(normalize_tag("#1"), None);
+        assert_eq!(normalize_tag("a,b"), None);
+        assert_eq!(normalize_tag(""), None);
+    }
+
+    #[test]
+    fn test_priority_new() {
+        assert_eq!(Priority::new('a').unwrap().letter(), 'A');
+        assert!(Priority::new('A').unwrap().is_high());
+        assert!(!Priority::new('B').unwrap().is_high());
+        assert!(Priority::new('1').is_none());
+        assert!(Priority::new('é').is_none());
+        assert!(Priority::new('A') < Priority::new('B'));
+    }
+
+    #[test]
+    fn test_memo_data_by_priority() {
+        let data = "1: 2001-01-01 01:01:01 one\n2: 2002-02-02 02:02:02 [prio:B uuid:67e55044-10b1-426f-9247-bb680e5fe0c1] two\n3: 2003-03-03 03:03:03 [prio:A uuid:67e55044-10b1-426f-9247-bb680e5fe0c2] three\n4: 2003-03-03 03:03:03 [prio:B due:2003-03-04T00:00:00 uuid:67e55044-10b1-426f-9247-bb680e5fe0c3] four\n".to_string();
+        let mut d = MemoData::new();
+        d.contents = MemoData::parse(data).unwrap();
+        let result = d.by_priority(&Filter::default()).unwrap();
+        let positions: Vec<_> = ["three", "four", "two", "one"]
+            .iter()
+            .map(|text| result.find(text).unwrap())
+            .collect();
+        assert!(positions.windows(2).all(|w| w[0] < w[1]));
+        assert!(result.contains("Priority A"));
+        assert!(result.contains("No priority"));
+    }
+
+    #[test]
+    fn test_memo_data_recurring() {
+        let data = "1: 2001-01-01 01:01:01 one\n2: 2002-02-02 02:02:02 [due:2026-10-19T09:30:00 every:FREQ=WEEKLY;BYDAY=MO,TU,WE,TH,FR uuid:67e55044-10b1-426f-9247-bb680e5fe0c1] standup\n3: 2003-03-03 03:03:03 [due:2026-10-20T12:00:00 uuid:67e55044-10b1-426f-9247-bb680e5fe0c2] lunch\n".to_string();
+        let mut d = MemoData::new();
+        d.contents = MemoData::parse(data).unwrap();
+
+        // The recurring memo is listed at its next occurrence, so first
+        assert_eq!(
+            d.ordered_ids(DisplayMode::GroupByDate, &Filter::default()),
+            vec![2, 3, 1]
+        );
+        let grouped = d.group_by_date(&Filter::default()).unwrap();
+        assert!(grouped.starts_with(
+            &style::str("Monday, October 19, 2026", style::Options::Title).to_string()
+        ));
+        assert!(grouped.contains("(every weekday)"));
+
+        let now = NaiveDate::from_ymd_opt(2026, 10, 18)
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap();
+        let filter = Filter {
+            due_within: Some((now, now + chrono::Duration::days(3))),
+            ..Default::default()
+        };
+        assert_eq!(d.filtered_ids(&filter), vec![2, 3]);
+        let upcoming = d.group_by_date(&filter).unwrap();
+        assert_eq!(upcoming.matches("standup").count(), 3);
+        assert_eq!(upcoming.matches("lunch").count(), 1);
+        assert!(upcoming.contains("Wednesday, October 21, 2026"));
+        assert!(!upcoming.contains("one"));
+
+        let standup = d.get(2).unwrap();
+        assert_eq!(
+            standup.next_occurrence(now).unwrap().to_string(),
+            "2026-10-20 09:30:00"
+        );
+        assert_eq!(
+            standup
+                .next_occurrence(
+                    NaiveDate::from_ymd_opt(2026, 10, 22)
+                        .unwrap()
+                        .and_hms_opt(10, 0, 0)
+                        .unwrap()
+                )
+                .unwrap()
+                .to_string(),
+            "2026-10-23 09:30:00"
+        );
+        assert!(d.get(3).unwrap().next_occurrence(now).is_none());
+    }
+
+    #[test]
+    fn test_content_snoozed_recurring() {
+        let date_time = |day, hour, min| {
+            NaiveDate::from_ymd_opt(2026, 10, day)
+                .unwrap()
+                .and_hms_opt(hour, min, 0)
+                .unwrap()
+        };
+        let mut content = Content::new("standup", date_time(1, 0, 0));
+        let mut recurrence = crate::recur::Recurrence::from_str("daily").unwrap();
+        recurrence.anchor = Some(date_time(19, 9, 30));
+        content.recurrence = Some(recurrence);
+        content.due = Some(date_time(19, 10, 0));
+
+        // The series goes on from the anchor, not from the snoozed due date
+        assert_eq!(
+            content.next_occurrence(date_time(19, 10, 5)),
+            Some(date_time(20, 9, 30))
+        );
+        assert_eq!(
+            content.occurrences(date_time(19, 0, 0), date_time(21, 23, 0)),
+            vec![
+                date_time(19, 10, 0),
+                date_time(20, 9, 30),
+                date_time(21, 9, 30)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_memo_data_filtered_ids() {
+        let data = "1: 2001-01-01 01:01:01 one\n2: 2002-02-02 02:02:02 [done:2002-02-03T00:00:00 uuid:67e55044-10b1-426f-9247-bb680e5fe0c1] two\n3: 2003-03-03 03:03:03 three\n".to_string();
+        let mut d = MemoData::new();
+        d.contents = MemoData::parse(data).unwrap();
+        assert_eq!(d.filtered_ids(&Filter::default()), vec![1, 3]);
+        assert_eq!(
+            d.filtered_ids(&Filter {
+                status: crate::data::Status::Done,
+                ..Default::default()
+            }),
+            vec![2]
+        );
+        assert_eq!(
+            d.sorted(&Filter::default()),
+            "1: 2001-01-01 01:01:01 one\n3: 2003-03-03 03:03:03 three\n"
+        );
+        assert!(!d.group_by_date(&Filter::default()).unwrap().contains("two"));
+    }
+
+    #[test]
+    fn test_memo_data_search() {
+        let data = "1: 2001-01-01 01:01:01 buttermilk\n2: 2002-02-02 02:02:02 [done:2002-02-03T00:00:00 uuid:67e55044-10b1-426f-9247-bb680e5fe0c1] milk and more milk\n3: 2003-03-03 03:03:03 buy milk\n4: 2003-03-03 03:03:03 eggs\n".to_string();
+        let mut d = MemoData::new();
+        d.contents = MemoData::parse(data).unwrap();
+        let query = Query::new("MILK", crate::search::SearchMode::Substring).unwrap();
+        assert_eq!(d.search_ids(&query, false), vec![3, 2, 1]);
+        assert_eq!(d.search_ids(&query, true), vec![2, 3, 1]);
+
+        let query = Query::new("milk", crate::search::SearchMode::Word).unwrap();
+        assert_eq!(d.search_ids(&query, false), vec![3, 2]);
+
+        let result = d.search(&query, false).unwrap();
+        assert!(result.contains("Monday, March  3, 2003"));
+        assert!(!result.contains("eggs"));
+    }
+
+    #[test]
+    fn test_memo_data_combine() {
+        let mut work = MemoData::new();
+        work.contents = MemoData::parse("3: 2001-01-01 01:01:01 deploy\n".to_string()).unwrap();
+        let mut home = MemoData::new();
+        home.contents = MemoData::parse(
+            "1: 2002-02-02 02:02:02 milk\n2: 2003-03-03 03:03:03 eggs\n".to_string(),
+        )
+        .unwrap();
+
+        let d = MemoData::combine(&[("home".to_string(), home), ("work".to_string(), work)]);
+        assert_eq!(d.sorted_ids(), vec![1, 2, 3]);
+        assert_eq!(
+            d.origins[&1],
+            Origin {
+                notebook: "work".to_string(),
+                id: 3
+            }
+        );
+        assert_eq!(d.get(3).unwrap().text, "eggs");
+        assert_eq!(
+            d.sorted(&Filter::default()),
+            "work/3: 2001-01-01 01:01:01 deploy\nhome/1: 2002-02-02 02:02:02 milk\nhome/2: 2003-03-03 03:03:03 eggs\n"
+        );
+        assert!(d
+            .group_by_date(&Filter::default())
+            .unwrap()
+            .contains("home/2: 03:03:03"));
+    }
 }
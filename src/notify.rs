@@ -0,0 +1,514 @@
+use crate::config::expand_home;
+use crate::data::write_file;
+use crate::models::Content;
+use crate::style;
+use crate::{ATTRIBUTE_DATE_TIME_FORMAT, DATE_TIME_FORMAT};
+use anyhow::{anyhow, Context, Result};
+use chrono::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::{fmt, fs, str::FromStr};
+use uuid::Uuid;
+
+/// Path of the firing state of a data file, the file path with `.fired` appended
+pub fn fired_path(file_path: &Path) -> PathBuf {
+    let mut path = file_path.as_os_str().to_owned();
+    path.push(".fired");
+    PathBuf::from(path)
+}
+
+/// A memo that came due and is notified by `memo daemon`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Reminder {
+    pub id: u32,
+    pub uuid: Uuid,
+    pub text: String,
+    pub due: NaiveDateTime,
+    /// The memo came due while the daemon was not running, or was added already overdue
+    pub missed: bool,
+}
+
+impl Reminder {
+    /// Returns the line used to notify the memo, e.g. `Memo 3 is due: buy milk`
+    pub fn message(&self) -> String {
+        if self.missed {
+            format!(
+                "Memo {} was due on {}: {}",
+                self.id,
+                style::date_time(self.due),
+                self.text
+            )
+        } else {
+            format!("Memo {} is due: {}", self.id, self.text)
+        }
+    }
+}
+
+/// How `memo daemon` notifies the memos that come due
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum Notifier {
+    /// Print the message to stderr, e.g. to the journal of a systemd service
+    #[default]
+    Stderr,
+    /// Append the message to a log file, after the time it was sent
+    Log(PathBuf),
+    /// Run a command, a shell command line on Unix and a program with its arguments on Windows.
+    /// `{id}`, `{uuid}`, `{text}`, `{due}` and `{message}` are replaced with the values, which
+    /// are also given as `MEMO_ID`, `MEMO_UUID`, `MEMO_TEXT`, `MEMO_DUE` and `MEMO_MESSAGE`
+    /// environment variables.
+    Command(String),
+}
+
+impl Notifier {
+    /// Notify a memo that came due
+    pub fn notify(&self, reminder: &Reminder) -> Result<()> {
+        match self {
+            Notifier::Stderr => {
+                eprintln!("{}", style::str(&reminder.message(), style::Options::Title));
+                Ok(())
+            }
+            Notifier::Log(path) => {
+                let mut log = fs::OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                    .with_context(|| format!("Could not open log '{}'", path.display()))?;
+                writeln!(
+                    log,
+                    "{} {}",
+                    Local::now().naive_local().format(DATE_TIME_FORMAT),
+                    reminder.message()
+                )
+                .with_context(|| format!("Could not write log '{}'", path.display()))
+            }
+            Notifier::Command(template) => {
+                let values = [
+                    ("id", reminder.id.to_string()),
+                    ("uuid", reminder.uuid.to_string()),
+                    ("text", reminder.text.clone()),
+                    (
+                        "due",
+                        reminder.due.format(ATTRIBUTE_DATE_TIME_FORMAT).to_string(),
+                    ),
+                    ("message", reminder.message()),
+                ];
+                let mut command = template_command(template, &values, cfg!(windows));
+                for (name, value) in &values {
+                    command.env(format!("MEMO_{}", name.to_uppercase()), value);
+                }
+                let status = command
+                    .status()
+                    .with_context(|| format!("Could not run '{}'", template))?;
+                if !status.success() {
+                    return Err(anyhow!("'{}' failed with {}", template, status));
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl FromStr for Notifier {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.split_once(':') {
+            _ if s == "stderr" => Ok(Notifier::Stderr),
+            Some(("log", path)) if !path.trim().is_empty() => {
+                Ok(Notifier::Log(expand_home(Path::new(path.trim()))))
+            }
+            Some(("command", template)) if !template.trim().is_empty() => {
+                Ok(Notifier::Command(template.trim().to_string()))
+            }
+            _ => Err(anyhow!(
+                "Invalid notifier '{}'. Expected: stderr, log:<path> or command:<template>",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Notifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Notifier::Stderr => write!(f, "stderr"),
+            Notifier::Log(path) => write!(f, "log:{}", path.display()),
+            Notifier::Command(template) => write!(f, "command:{}", template),
+        }
+    }
+}
+
+/// Returns the command running a template with the values of its placeholders.
+/// On Unix the template is a `sh` command line and the values are quoted for it. On Windows it
+/// is run without a shell, as `cmd` cannot quote every value safely: the template is split into
+/// words and the placeholders of each word are replaced with the values as they are.
+fn template_command(template: &str, values: &[(&str, String)], windows: bool) -> Command {
+    if !windows {
+        let mut command = Command::new("sh");
+        command.args(["-c", &expand_template(template, values)]);
+        return command;
+    }
+    let mut words = split_words(template)
+        .into_iter()
+        .map(|word| expand_with(&word, values, str::to_string));
+    let mut command = Command::new(words.next().unwrap_or_default());
+    command.args(words);
+    command
+}
+
+/// Split a command line into words on whitespace, double quotes grouping words,
+/// e.g. `msg * "memo {id}"` becomes `msg`, `*` and `memo {id}`
+fn split_words(command_line: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut quoted = false;
+    for c in command_line.chars() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                word.get_or_insert_with(String::new);
+            }
+            c if c.is_whitespace() && !quoted => words.extend(word.take()),
+            c => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    words.extend(word);
+    words
+}
+
+/// Quote a value for a POSIX shell, e.g. `it's` becomes `'it'\''s'`
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+/// Replace the `{name}` placeholders of a command template with their quoted values.
+/// The template is read once from start to end, so placeholders found in the values, e.g. in
+/// the text of a memo, are never expanded.
+fn expand_template(template: &str, values: &[(&str, String)]) -> String {
+    expand_with(template, values, shell_quote)
+}
+
+/// Replace the `{name}` placeholders of a template with their values passed through `quote`,
+/// see `expand_template`
+fn expand_with(template: &str, values: &[(&str, String)], quote: fn(&str) -> String) -> String {
+    let mut command = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        command.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let placeholder = values
+            .iter()
+            .find(|(name, _)| rest.starts_with(&format!("{{{}}}", name)));
+        match placeholder {
+            Some((name, value)) => {
+                command.push_str(&quote(value));
+                rest = &rest[name.len() + 2..];
+            }
+            None => {
+                command.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    command.push_str(rest);
+    command
+}
+
+/// Memos already notified by `memo daemon`, stored next to the data file so nothing is
+/// notified twice across restarts. A memo is notified again when its due time changes,
+/// e.g. when a recurring memo is done.
+#[derive(Default, Serialize, Deserialize)]
+pub struct Fired {
+    #[serde(skip)]
+    path: PathBuf,
+    /// Last time the memos were checked
+    checked: Option<NaiveDateTime>,
+    /// Due time notified of each memo, by UUID
+    memos: BTreeMap<Uuid, NaiveDateTime>,
+}
+
+impl Fired {
+    /// Load the firing state of a data file, empty if it has none yet
+    pub fn load(file_path: &Path) -> Result<Self> {
+        let path = fired_path(file_path);
+        let data = if path.exists() {
+            fs::read_to_string(&path)?
+        } else {
+            String::new()
+        };
+        let mut fired = if !data.trim().is_empty() {
+            serde_json::from_str::<Fired>(&data)
+                .with_context(|| format!("Invalid firing state '{}'", path.display()))?
+        } else {
+            Fired::default()
+        };
+        fired.path = path;
+        Ok(fired)
+    }
+
+    /// Write the firing state next to its data file, replacing it atomically so a crash
+    /// leaves either the old or the new state. An empty file is read as an empty state.
+    pub fn save(&self) -> Result<()> {
+        if !self.path.exists() {
+            fs::File::create(&self.path).with_context(|| {
+                format!("Could not create firing state '{}'", self.path.display())
+            })?;
+        }
+        write_file(&self.path, &serde_json::to_string(self)?)
+            .with_context(|| format!("Could not write firing state '{}'", self.path.display()))
+    }
+
+    /// Returns the memos that are due at `now` and were not notified yet, the oldest first.
+    /// Those that came due before the last check were missed, e.g. while the daemon was down.
+    pub fn pending(&self, memos: &HashMap<u32, Content>, now: NaiveDateTime) -> Vec<Reminder> {
+        let mut reminders: Vec<Reminder> = memos
+            .iter()
+            .filter(|(_, content)| content.done.is_none())
+            .filter_map(|(id, content)| {
+                let due = content.due.filter(|due| *due <= now)?;
+                (self.memos.get(&content.uuid) != Some(&due)).then(|| Reminder {
+                    id: *id,
+                    uuid: content.uuid,
+                    text: content.text.clone(),
+                    due,
+                    missed: self.checked.is_none_or(|checked| due < checked),
+                })
+            })
+            .collect();
+        reminders.sort_by_key(|reminder| (reminder.due, reminder.id));
+        reminders
+    }
+
+    /// Record that a memo was notified
+    pub fn mark(&mut self, reminder: &Reminder) {
+        self.memos.insert(reminder.uuid, reminder.due);
+    }
+
+    /// Record the time of a check, and forget the memos that were removed, done or given
+    /// another due time since they were notified
+    pub fn checked(&mut self, memos: &HashMap<u32, Content>, now: NaiveDateTime) {
+        let current: HashMap<Uuid, NaiveDateTime> = memos
+            .values()
+            .filter(|content| content.done.is_none())
+            .filter_map(|content| Some((content.uuid, content.due?)))
+            .collect();
+        self.memos
+            .retain(|uuid, due| current.get(uuid) == Some(due));
+        self.checked = Some(now);
+    }
+}
+
+/// Returns the next time a memo that is not done comes due after `now`
+pub fn next_due(memos: &HashMap<u32, Content>, now: NaiveDateTime) -> Option<NaiveDateTime> {
+    memos
+        .values()
+        .filter(|content| content.done.is_none())
+        .filter_map(|content| content.due)
+        .filter(|due| *due > now)
+        .min()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn date_time(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, DATE_TIME_FORMAT).unwrap()
+    }
+
+    fn memos() -> HashMap<u32, Content> {
+        let created = date_time("2023-12-01 00:00:00");
+        let mut memos = HashMap::new();
+        for (id, due) in [(1, "2024-01-01 09:00:00"), (2, "2024-01-01 12:00:00")] {
+            let mut content = Content::new(&format!("memo {}", id), created);
+            content.due = Some(date_time(due));
+            memos.insert(id, content);
+        }
+        let mut done = Content::new("done", created);
+        done.due = Some(date_time("2024-01-01 08:00:00"));
+        done.done = Some(date_time("2024-01-01 08:30:00"));
+        memos.insert(3, done);
+        memos.insert(4, Content::new("no due", created));
+        memos
+    }
+
+    #[test]
+    fn test_notifier_from_str() {
+        assert_eq!(Notifier::from_str("stderr").unwrap(), Notifier::Stderr);
+        assert_eq!(
+            Notifier::from_str("log:/var/log/memo.log").unwrap(),
+            Notifier::Log(PathBuf::from("/var/log/memo.log"))
+        );
+        let notifier = Notifier::from_str("command:notify-send memo {text}").unwrap();
+        assert_eq!(
+            notifier,
+            Notifier::Command("notify-send memo {text}".to_string())
+        );
+        assert_eq!(notifier.to_string(), "command:notify-send memo {text}");
+        assert!(Notifier::from_str("log:").is_err());
+        assert!(Notifier::from_str("email").is_err());
+    }
+
+    #[test]
+    fn test_expand_template() {
+        let values = [("id", "3".to_string()), ("text", "it's due".to_string())];
+        assert_eq!(
+            expand_template("echo {id} {text} {uuid}", &values),
+            r"echo '3' 'it'\''s due' {uuid}"
+        );
+
+        // Placeholders in the values are not expanded again
+        let values = [
+            ("text", "{message}'{due}".to_string()),
+            ("due", "2024-01-01T09:00:00".to_string()),
+            ("message", "'; echo INJECTED; '".to_string()),
+        ];
+        assert_eq!(
+            expand_template("echo {text} {due", &values),
+            r"echo '{message}'\''{due}' {due"
+        );
+    }
+
+    #[test]
+    fn test_template_command() {
+        let values = [
+            ("id", "3".to_string()),
+            ("text", "milk & eggs | \"bread\" %PATH%".to_string()),
+        ];
+        let args = |command: &Command| -> Vec<String> {
+            command
+                .get_args()
+                .map(|arg| arg.to_string_lossy().to_string())
+                .collect()
+        };
+
+        let command = template_command("notify-send {text}", &values, false);
+        assert_eq!(command.get_program(), "sh");
+        assert_eq!(
+            args(&command),
+            vec!["-c", "notify-send 'milk & eggs | \"bread\" %PATH%'"]
+        );
+
+        // On Windows each value is a single argument, never read by a shell
+        let command = template_command(
+            r#"C:\Tools\toast.exe --title "Memo {id}" {text}"#,
+            &values,
+            true,
+        );
+        assert_eq!(command.get_program(), r"C:\Tools\toast.exe");
+        assert_eq!(
+            args(&command),
+            vec!["--title", "Memo 3", "milk & eggs | \"bread\" %PATH%"]
+        );
+        assert_eq!(split_words(r#"msg "" *"#), vec!["msg", "", "*"]);
+    }
+
+    #[test]
+    fn test_fired_pending() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("memo.txt");
+        let memos = memos();
+
+        // Before any check, overdue memos were missed
+        let mut fired = Fired::load(&file_path).unwrap();
+        let reminders = fired.pending(&memos, date_time("2024-01-01 10:00:00"));
+        assert_eq!(reminders.len(), 1);
+        assert_eq!(reminders[0].id, 1);
+        assert!(reminders[0].missed);
+        assert_eq!(
+            next_due(&memos, date_time("2024-01-01 10:00:00")),
+            Some(date_time("2024-01-01 12:00:00"))
+        );
+
+        fired.mark(&reminders[0]);
+        fired.checked(&memos, date_time("2024-01-01 10:00:00"));
+        fired.save().unwrap();
+
+        // Nothing is notified twice, even after a restart
+        let fired = Fired::load(&file_path).unwrap();
+        assert_eq!(fired.memos.len(), 1);
+        let reminders = fired.pending(&memos, date_time("2024-01-01 12:00:00"));
+        assert_eq!(reminders.len(), 1);
+        assert_eq!(reminders[0].id, 2);
+        assert!(!reminders[0].missed);
+        assert_eq!(reminders[0].message(), "Memo 2 is due: memo 2");
+    }
+
+    #[test]
+    fn test_fired_empty_file() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("memo.txt");
+
+        // A state file created but not written yet, e.g. after a crash, is empty
+        fs::File::create(fired_path(&file_path)).unwrap();
+        let mut fired = Fired::load(&file_path).unwrap();
+        assert!(fired.checked.is_none());
+        fired.checked(&memos(), date_time("2024-01-01 10:00:00"));
+        fired.save().unwrap();
+        assert!(Fired::load(&file_path).unwrap().checked.is_some());
+    }
+
+    #[test]
+    fn test_fired_new_due() {
+        let dir = tempdir().unwrap();
+        let mut memos = memos();
+        let mut fired = Fired::load(&dir.path().join("memo.txt")).unwrap();
+        let now = date_time("2024-01-01 10:00:00");
+        for reminder in fired.pending(&memos, now) {
+            fired.mark(&reminder);
+        }
+        fired.checked(&memos, now);
+        assert!(fired.pending(&memos, now).is_empty());
+
+        // A memo given another due time, e.g. the next occurrence, is notified again
+        memos.get_mut(&1).unwrap().due = Some(date_time("2024-01-01 09:30:00"));
+        let reminders = fired.pending(&memos, now);
+        assert_eq!(reminders.len(), 1);
+        assert!(reminders[0].missed);
+        fired.checked(&memos, now);
+        assert!(fired.memos.is_empty());
+    }
+
+    #[test]
+    fn test_notifier_notify() {
+        let dir = tempdir().unwrap();
+        let reminder = Reminder {
+            id: 1,
+            uuid: Uuid::nil(),
+            text: "buy milk".to_string(),
+            due: date_time("2024-01-01 09:00:00"),
+            missed: false,
+        };
+        let log = dir.path().join("memo.log");
+        Notifier::Log(log.clone()).notify(&reminder).unwrap();
+        Notifier::Log(log.clone()).notify(&reminder).unwrap();
+        let data = fs::read_to_string(&log).unwrap();
+        assert_eq!(data.lines().count(), 2);
+        assert!(data.ends_with(" Memo 1 is due: buy milk\n"));
+
+        if cfg!(unix) {
+            let out = dir.path().join("out");
+            let template = format!("printf '%s %s' {{id}} \"$MEMO_TEXT\" > '{}'", out.display());
+            Notifier::Command(template).notify(&reminder).unwrap();
+            assert_eq!(fs::read_to_string(&out).unwrap(), "1 buy milk");
+
+            // A text made to break out of the quoting is passed to the command as it is
+            let text = "{message}; echo INJECTED '{due}'; # it's";
+            let injected = Reminder {
+                text: text.to_string(),
+                ..reminder.clone()
+            };
+            let template = format!("printf '%s' {{text}} > '{}'", out.display());
+            Notifier::Command(template).notify(&injected).unwrap();
+            assert_eq!(fs::read_to_string(&out).unwrap(), text);
+
+            assert!(Notifier::Command("exit 3".to_string())
+                .notify(&reminder)
+                .is_err());
+        }
+    }
+}
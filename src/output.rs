@@ -0,0 +1,348 @@
+use crate::models::{snoozed_label, Content, MemoData};
+use crate::ATTRIBUTE_DATE_TIME_FORMAT;
+use anyhow::{anyhow, Context, Result};
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use std::fmt;
+
+/// Columns of the csv and tsv output, in order
+const COLUMNS: [&str; 11] = [
+    "id", "text", "created", "due", "done", "tags", "priority", "updated", "uuid", "every",
+    "snoozed",
+];
+
+/// Format used to output the memos when listing them
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum OutputFormat {
+    /// Colored layout for the terminal
+    #[default]
+    Pretty,
+    /// One memo per line, without colors
+    Plain,
+    /// A JSON array of records
+    Json,
+    /// One JSON record per line
+    JsonLines,
+    /// Comma separated values with a header row
+    Csv,
+    /// Tab separated values with a header row
+    Tsv,
+}
+
+impl std::str::FromStr for OutputFormat {
+    type Err = anyhow::Error;
+
+    /// Parse an output format from its name
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "pretty" => Ok(OutputFormat::Pretty),
+            "plain" => Ok(OutputFormat::Plain),
+            "json" => Ok(OutputFormat::Json),
+            "jsonl" => Ok(OutputFormat::JsonLines),
+            "csv" => Ok(OutputFormat::Csv),
+            "tsv" => Ok(OutputFormat::Tsv),
+            _ => Err(anyhow!(
+                "Invalid output format '{}'. Expected: pretty, plain, json, jsonl, csv or tsv",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for OutputFormat {
+    /// Format an output format as its name
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            OutputFormat::Pretty => "pretty",
+            OutputFormat::Plain => "plain",
+            OutputFormat::Json => "json",
+            OutputFormat::JsonLines => "jsonl",
+            OutputFormat::Csv => "csv",
+            OutputFormat::Tsv => "tsv",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// A memo as written in the structured output formats.
+/// Every field is always present so the records have a stable shape.
+#[derive(Serialize)]
+struct Record {
+    /// Notebook of the memo, only in a combined view of several notebooks
+    #[serde(skip_serializing_if = "Option::is_none")]
+    notebook: Option<String>,
+    id: u32,
+    text: String,
+    created: String,
+    due: Option<String>,
+    done: Option<String>,
+    tags: Vec<String>,
+    priority: Option<String>,
+    updated: Option<String>,
+    uuid: String,
+    /// Recurrence rule, as an RRULE
+    every: Option<String>,
+    /// Times the memo was snoozed
+    snoozed: u32,
+    /// Recurrence rule in words, for the plain format
+    #[serde(skip)]
+    repeats: Option<String>,
+}
+
+impl Record {
+    fn new(id: u32, content: &Content) -> Self {
+        let date_time = |d: NaiveDateTime| d.format(ATTRIBUTE_DATE_TIME_FORMAT).to_string();
+        Record {
+            notebook: None,
+            id,
+            text: content.text.clone(),
+            created: date_time(content.date_time),
+            due: content.due.map(date_time),
+            done: content.done.map(date_time),
+            tags: content.tags.clone(),
+            priority: content.priority.map(|priority| priority.to_string()),
+            updated: content.updated_at.map(date_time),
+            uuid: content.uuid.to_string(),
+            every: content
+                .recurrence
+                .as_ref()
+                .map(|recurrence| recurrence.to_string()),
+            snoozed: content.snoozed,
+            repeats: content
+                .recurrence
+                .as_ref()
+                .map(|recurrence| recurrence.describe()),
+        }
+    }
+
+    /// Values of the record in the order of `COLUMNS`, after its notebook if it has one
+    fn values(&self) -> Vec<String> {
+        let mut values: Vec<String> = self.notebook.iter().cloned().collect();
+        values.extend([
+            self.id.to_string(),
+            self.text.clone(),
+            self.created.clone(),
+            self.due.clone().unwrap_or_default(),
+            self.done.clone().unwrap_or_default(),
+            self.tags.join(","),
+            self.priority.clone().unwrap_or_default(),
+            self.updated.clone().unwrap_or_default(),
+            self.uuid.clone(),
+            self.every.clone().unwrap_or_default(),
+            self.snoozed.to_string(),
+        ]);
+        values
+    }
+}
+
+/// Render the memos with the given ids, in that order, in a format without colors.
+/// The `Pretty` format depends on the display mode and is rendered by `MemoData` itself.
+pub fn render(data: &MemoData, ids: &[u32], format: OutputFormat) -> Result<String> {
+    let records = ids
+        .iter()
+        .map(|id| {
+            data.get(*id)
+                .map(|content| match data.origins.get(id) {
+                    Some(origin) => Record {
+                        notebook: Some(origin.notebook.clone()),
+                        ..Record::new(origin.id, content)
+                    },
+                    None => Record::new(*id, content),
+                })
+                .with_context(|| format!("No item found for id '{}'", id))
+        })
+        .collect::<Result<Vec<Record>>>()?;
+
+    match format {
+        OutputFormat::Pretty | OutputFormat::Plain => Ok(records
+            .iter()
+            .map(plain_line)
+            .collect::<Vec<String>>()
+            .join("\n")),
+        OutputFormat::Json => Ok(serde_json::to_string_pretty(&records)?),
+        OutputFormat::JsonLines => Ok(records
+            .iter()
+            .map(serde_json::to_string)
+            .collect::<serde_json::Result<Vec<String>>>()?
+            .join("\n")),
+        OutputFormat::Csv => Ok(delimited(&records, ',', csv_field)),
+        OutputFormat::Tsv => Ok(delimited(&records, '\t', tsv_field)),
+    }
+}
+
+/// Format a record as a single line of plain text
+fn plain_line(record: &Record) -> String {
+    let id = match &record.notebook {
+        Some(notebook) => format!("{}/{}", notebook, record.id),
+        None => record.id.to_string(),
+    };
+    let mut line = format!("{}: {}", id, record.created.replace('T', " "));
+    if let Some(priority) = &record.priority {
+        line.push_str(&format!(" ({})", priority));
+    }
+    line.push_str(&format!(" {}", record.text));
+    if let Some(due) = &record.due {
+        line.push_str(&format!(" (due {})", due.replace('T', " ")));
+    }
+    if let Some(repeats) = &record.repeats {
+        line.push_str(&format!(" ({})", repeats));
+    }
+    if let Some(snoozed) = snoozed_label(record.snoozed) {
+        line.push_str(&format!(" ({})", snoozed));
+    }
+    for tag in &record.tags {
+        line.push_str(&format!(" +{}", tag));
+    }
+    if let Some(done) = &record.done {
+        line.push_str(&format!(" (done {})", done.replace('T', " ")));
+    }
+    line
+}
+
+/// Format the records as delimiter separated values with a header row
+fn delimited(records: &[Record], delimiter: char, field: fn(&str) -> String) -> String {
+    let separator = delimiter.to_string();
+    let mut columns = COLUMNS.to_vec();
+    if records.iter().any(|record| record.notebook.is_some()) {
+        columns.insert(0, "notebook");
+    }
+    let mut lines = vec![columns.join(&separator)];
+    for record in records {
+        let values: Vec<String> = record.values().iter().map(|value| field(value)).collect();
+        lines.push(values.join(&separator));
+    }
+    lines.join("\n")
+}
+
+/// Quote a csv field if it contains a comma, quote or line break
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Replace the characters that would break a tsv row with spaces
+fn tsv_field(value: &str) -> String {
+    value.replace(['\t', '\n', '\r'], " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Priority;
+    use crate::recur::Recurrence;
+    use std::str::FromStr;
+    use uuid::Uuid;
+
+    fn data() -> MemoData {
+        let date_time = chrono::NaiveDate::from_ymd_opt(2001, 1, 1)
+            .unwrap()
+            .and_hms_opt(1, 1, 1)
+            .unwrap();
+        let mut data = MemoData::new();
+        let mut one = Content::new("buy milk, eggs", date_time);
+        one.uuid = Uuid::parse_str("67e55044-10b1-426f-9247-bb680e5fe0c8").unwrap();
+        data.contents.insert(1, one);
+        let mut two = Content::new("say \"hi\"", date_time);
+        two.uuid = Uuid::parse_str("a1a2a3a4-b1b2-4c1c-8d1d-e1e2e3e4e5e6").unwrap();
+        two.recurrence = Some(Recurrence::from_str("weekday").unwrap());
+        two.due = Some(date_time);
+        two.tags = vec!["work".to_string(), "home".to_string()];
+        two.priority = Priority::new('A');
+        two.snoozed = 2;
+        data.contents.insert(2, two);
+        data
+    }
+
+    #[test]
+    fn test_output_format_from_str() {
+        assert_eq!(
+            OutputFormat::from_str("JSONL").unwrap(),
+            OutputFormat::JsonLines
+        );
+        assert_eq!(OutputFormat::from_str("csv").unwrap(), OutputFormat::Csv);
+        assert!(OutputFormat::from_str("xml").is_err());
+        assert_eq!(OutputFormat::Tsv.to_string(), "tsv");
+    }
+
+    #[test]
+    fn test_render_json() {
+        let output = render(&data(), &[2], OutputFormat::Json).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(value[0]["id"], 2);
+        assert_eq!(value[0]["text"], "say \"hi\"");
+        assert_eq!(value[0]["created"], "2001-01-01T01:01:01");
+        assert_eq!(value[0]["due"], "2001-01-01T01:01:01");
+        assert!(value[0]["done"].is_null());
+        assert_eq!(value[0]["tags"][1], "home");
+        assert_eq!(value[0]["priority"], "A");
+        assert!(value[0]["updated"].is_null());
+        assert_eq!(value[0]["uuid"], "a1a2a3a4-b1b2-4c1c-8d1d-e1e2e3e4e5e6");
+        assert_eq!(value[0]["every"], "FREQ=WEEKLY;BYDAY=MO,TU,WE,TH,FR");
+        assert!(value[1]["every"].is_null());
+        assert_eq!(value[0]["snoozed"], 2);
+
+        assert_eq!(render(&data(), &[], OutputFormat::Json).unwrap(), "[]");
+    }
+
+    #[test]
+    fn test_render_jsonl() {
+        let output = render(&data(), &[2, 1], OutputFormat::JsonLines).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("{\"id\":2,"));
+        assert!(lines[1].starts_with("{\"id\":1,"));
+    }
+
+    #[test]
+    fn test_render_csv() {
+        let output = render(&data(), &[1, 2], OutputFormat::Csv).unwrap();
+        assert_eq!(
+            output,
+            "id,text,created,due,done,tags,priority,updated,uuid,every,snoozed\n\
+             1,\"buy milk, eggs\",2001-01-01T01:01:01,,,,,,67e55044-10b1-426f-9247-bb680e5fe0c8,,0\n\
+             2,\"say \"\"hi\"\"\",2001-01-01T01:01:01,2001-01-01T01:01:01,,\"work,home\",A,,a1a2a3a4-b1b2-4c1c-8d1d-e1e2e3e4e5e6,\"FREQ=WEEKLY;BYDAY=MO,TU,WE,TH,FR\",2"
+        );
+    }
+
+    #[test]
+    fn test_render_tsv() {
+        let output = render(&data(), &[2], OutputFormat::Tsv).unwrap();
+        assert_eq!(
+            output,
+            "id\ttext\tcreated\tdue\tdone\ttags\tpriority\tupdated\tuuid\tevery\tsnoozed\n\
+             2\tsay \"hi\"\t2001-01-01T01:01:01\t2001-01-01T01:01:01\t\twork,home\tA\t\ta1a2a3a4-b1b2-4c1c-8d1d-e1e2e3e4e5e6\tFREQ=WEEKLY;BYDAY=MO,TU,WE,TH,FR\t2"
+        );
+    }
+
+    #[test]
+    fn test_render_plain() {
+        let output = render(&data(), &[1, 2], OutputFormat::Plain).unwrap();
+        assert_eq!(
+            output,
+            "1: 2001-01-01 01:01:01 buy milk, eggs\n\
+             2: 2001-01-01 01:01:01 (A) say \"hi\" (due 2001-01-01 01:01:01) (every weekday) (snoozed 2 times) +work +home"
+        );
+    }
+
+    #[test]
+    fn test_render_combined() {
+        let combined = MemoData::combine(&[("work".to_string(), data())]);
+        let output = render(&combined, &[2], OutputFormat::Csv).unwrap();
+        assert!(output.starts_with("notebook,id,text,"));
+        assert!(output.ends_with(
+            "\nwork,2,\"say \"\"hi\"\"\",2001-01-01T01:01:01,2001-01-01T01:01:01,,\"work,home\",A,,a1a2a3a4-b1b2-4c1c-8d1d-e1e2e3e4e5e6,\"FREQ=WEEKLY;BYDAY=MO,TU,WE,TH,FR\",2"
+        ));
+        let output = render(&combined, &[1], OutputFormat::Plain).unwrap();
+        assert_eq!(output, "work/1: 2001-01-01 01:01:01 buy milk, eggs");
+        let output = render(&combined, &[1], OutputFormat::JsonLines).unwrap();
+        assert!(output.starts_with("{\"notebook\":\"work\",\"id\":1,"));
+    }
+
+    #[test]
+    fn test_render_unknown_id() {
+        assert!(render(&data(), &[3], OutputFormat::Json).is_err());
+    }
+}
@@ -0,0 +1,659 @@
+use crate::dates;
+use anyhow::{anyhow, Context, Result};
+use chrono::prelude::*;
+use chrono::{Duration, Months};
+use std::fmt;
+use std::str::FromStr;
+
+/// Largest number of occurrences looked at when searching for the next one, so a rule
+/// that never matches, or a memo overdue for years, cannot loop forever
+const MAX_STEPS: usize = 100_000;
+
+/// Largest number of hours, days, weeks or months between two occurrences
+pub const MAX_INTERVAL: u32 = 1000;
+
+/// Format of the DTSTART part of an RRULE, the anchor of a snoozed series
+const RRULE_DATE_TIME_FORMAT: &str = "%Y%m%dT%H%M%S";
+
+/// Days of the week of the `weekday` rule
+const WORK_DAYS: [Weekday; 5] = [
+    Weekday::Mon,
+    Weekday::Tue,
+    Weekday::Wed,
+    Weekday::Thu,
+    Weekday::Fri,
+];
+
+/// Days of the week of the `weekend` rule
+const WEEKEND_DAYS: [Weekday; 2] = [Weekday::Sat, Weekday::Sun];
+
+/// Unit of the interval between the occurrences of a recurring memo
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Frequency {
+    Hourly,
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+/// Recurrence rule of a memo, a subset of the iCalendar RRULE.
+/// The due date of the memo is its next occurrence, and gives the time of day of the others.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Recurrence {
+    pub frequency: Frequency,
+    /// Number of hours, days, weeks or months between occurrences
+    pub interval: u32,
+    /// Days of the week the occurrences fall on. Empty for any day.
+    pub weekdays: Vec<Weekday>,
+    /// Day of the month of monthly occurrences, the last day of the month in shorter months.
+    /// None to keep the day of the due date.
+    pub month_day: Option<u32>,
+    /// Occurrence the series goes on from while the memo is snoozed past it, stored as DTSTART.
+    /// None when the due date of the memo is its current occurrence.
+    pub anchor: Option<NaiveDateTime>,
+}
+
+impl Recurrence {
+    /// Create a rule repeating every hour, day, week or month
+    pub fn new(frequency: Frequency) -> Self {
+        Recurrence {
+            frequency,
+            interval: 1,
+            weekdays: Vec::new(),
+            month_day: None,
+            anchor: None,
+        }
+    }
+
+    /// Returns the rule with the day of the month of the first occurrence, so monthly
+    /// occurrences keep it after a shorter month
+    pub fn anchored(mut self, first: NaiveDateTime) -> Self {
+        if self.frequency == Frequency::Monthly && self.month_day.is_none() {
+            self.month_day = Some(first.day());
+        }
+        self
+    }
+
+    /// Returns true if an occurrence can fall on the date time
+    fn matches(&self, date_time: NaiveDateTime) -> bool {
+        let weekday = self.weekdays.is_empty() || self.weekdays.contains(&date_time.weekday());
+        let month_day = match (self.frequency, self.month_day) {
+            (Frequency::Monthly, Some(day)) => {
+                Some(date_time.date()) == month_date(date_time.date(), 0, day)
+            }
+            _ => true,
+        };
+        weekday && month_day
+    }
+
+    /// Returns the first occurrence at or after `start`, at the time of day of `start`
+    pub fn first(&self, start: NaiveDateTime) -> Option<NaiveDateTime> {
+        if self.matches(start) {
+            Some(start)
+        } else {
+            self.next(start)
+        }
+    }
+
+    /// Returns the occurrence that follows the one at `previous`.
+    /// None if the rule has no occurrence within a reasonable time.
+    pub fn next(&self, previous: NaiveDateTime) -> Option<NaiveDateTime> {
+        let interval = i64::from(self.interval.max(1));
+        match self.frequency {
+            Frequency::Hourly | Frequency::Daily => {
+                let step = match self.frequency {
+                    Frequency::Hourly => Duration::try_hours(interval)?,
+                    _ => Duration::try_days(interval)?,
+                };
+                let mut next = previous;
+                for _ in 0..MAX_STEPS {
+                    next = next.checked_add_signed(step)?;
+                    if self.matches(next) {
+                        return Some(next);
+                    }
+                }
+                None
+            }
+            Frequency::Weekly if self.weekdays.is_empty() => {
+                previous.checked_add_signed(Duration::try_weeks(interval)?)
+            }
+            Frequency::Weekly => {
+                // Occurrences fall on the given days of every `interval`th week: the next one
+                // is later in the week of `previous`, or in the week `interval` weeks after it
+                let week_start = previous
+                    .date()
+                    .week(Weekday::Mon)
+                    .first_day()
+                    .and_time(previous.time());
+                let later_this_week = (previous.weekday().num_days_from_monday() + 1..7)
+                    .map(|days| week_start.checked_add_signed(Duration::days(days.into())));
+                let target_start = Duration::try_weeks(interval)
+                    .and_then(|weeks| week_start.checked_add_signed(weeks));
+                let target_week = (0..7).map(|days| {
+                    target_start.and_then(|start| start.checked_add_signed(Duration::days(days)))
+                });
+                later_this_week
+                    .chain(target_week)
+                    .map_while(|next| next)
+                    .find(|next| self.matches(*next))
+            }
+            Frequency::Monthly => {
+                let day = self.month_day.unwrap_or(previous.day());
+                (0..=12u32)
+                    .map_while(|step| {
+                        month_date(
+                            previous.date(),
+                            step.checked_mul(self.interval.max(1))?,
+                            day,
+                        )
+                    })
+                    .map(|date| date.and_time(previous.time()))
+                    .find(|next| *next > previous)
+            }
+        }
+    }
+
+    /// Returns the first occurrence after `now`, starting from the one at or after `start`
+    pub fn first_after(&self, start: NaiveDateTime, now: NaiveDateTime) -> Option<NaiveDateTime> {
+        let mut next = self.first(start)?;
+        for _ in 0..MAX_STEPS {
+            if next > now {
+                return Some(next);
+            }
+            next = self.next(next)?;
+        }
+        None
+    }
+
+    /// Returns the occurrences from `from` to `until`, both included, of the series whose
+    /// next occurrence is `first`
+    pub fn occurrences(
+        &self,
+        first: NaiveDateTime,
+        from: NaiveDateTime,
+        until: NaiveDateTime,
+    ) -> Vec<NaiveDateTime> {
+        let mut occurrences = Vec::new();
+        let mut next = Some(first);
+        for _ in 0..MAX_STEPS {
+            match next {
+                Some(occurrence) if occurrence <= until => {
+                    if occurrence >= from {
+                        occurrences.push(occurrence);
+                    }
+                    next = self.next(occurrence);
+                }
+                _ => break,
+            }
+        }
+        occurrences
+    }
+
+    /// Describe the rule in words, e.g. `every weekday` or `every 2 weeks on Mon, Thu`
+    pub fn describe(&self) -> String {
+        let unit = match self.frequency {
+            Frequency::Hourly => "hour",
+            Frequency::Daily => "day",
+            Frequency::Weekly => "week",
+            Frequency::Monthly => "month",
+        };
+        let mut description = match self.interval {
+            0 | 1 => format!("every {}", unit),
+            n => format!("every {} {}s", n, unit),
+        };
+        if self.interval <= 1 && self.weekdays == WORK_DAYS {
+            return "every weekday".to_string();
+        }
+        if self.interval <= 1 && self.weekdays == WEEKEND_DAYS {
+            return "every weekend day".to_string();
+        }
+        if !self.weekdays.is_empty() {
+            let days: Vec<String> = self.weekdays.iter().map(|day| day.to_string()).collect();
+            description.push_str(&format!(" on {}", days.join(", ")));
+        }
+        if let Some(day) = self.month_day {
+            description.push_str(&format!(" on the {}", ordinal(day)));
+        }
+        description
+    }
+
+    /// Parse an RRULE, e.g. `FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,TH`.
+    /// FREQ is HOURLY, DAILY, WEEKLY or MONTHLY, and only INTERVAL, BYDAY (without a number)
+    /// and BYMONTHDAY (1 to 31) are supported.
+    fn parse_rrule(s: &str) -> Result<Self> {
+        let rule = s.trim();
+        let rule = match rule.get(..6) {
+            Some(prefix) if prefix.eq_ignore_ascii_case("rrule:") => &rule[6..],
+            _ => rule,
+        };
+
+        let mut recurrence: Option<Recurrence> = None;
+        let mut interval = 1;
+        let mut weekdays = Vec::new();
+        let mut month_day = None;
+        let mut anchor = None;
+        for part in rule.split(';').filter(|part| !part.is_empty()) {
+            let (key, value) = part
+                .split_once('=')
+                .with_context(|| format!("Invalid RRULE part '{}'", part))?;
+            let value = value.to_uppercase();
+            match key.to_uppercase().as_str() {
+                "FREQ" => {
+                    let frequency = match value.as_str() {
+                        "HOURLY" => Frequency::Hourly,
+                        "DAILY" => Frequency::Daily,
+                        "WEEKLY" => Frequency::Weekly,
+                        "MONTHLY" => Frequency::Monthly,
+                        _ => return Err(anyhow!("Unsupported RRULE frequency '{}'", value)),
+                    };
+                    recurrence = Some(Recurrence::new(frequency));
+                }
+                "INTERVAL" => {
+                    interval = value
+                        .parse()
+                        .ok()
+                        .filter(|interval| (1..=MAX_INTERVAL).contains(interval))
+                        .with_context(|| format!("Invalid RRULE interval '{}'", value))?
+                }
+                "BYDAY" => {
+                    weekdays = value
+                        .split(',')
+                        .map(|day| {
+                            rrule_weekday(day)
+                                .with_context(|| format!("Invalid RRULE day '{}'", day))
+                        })
+                        .collect::<Result<_>>()?
+                }
+                "BYMONTHDAY" => {
+                    month_day = Some(
+                        value
+                            .parse()
+                            .ok()
+                            .filter(|day| (1..=31).contains(day))
+                            .with_context(|| format!("Invalid RRULE day of month '{}'", value))?,
+                    )
+                }
+                "DTSTART" => {
+                    anchor = Some(
+                        NaiveDateTime::parse_from_str(&value, RRULE_DATE_TIME_FORMAT)
+                            .with_context(|| format!("Invalid RRULE start '{}'", value))?,
+                    )
+                }
+                _ => return Err(anyhow!("Unsupported RRULE part '{}'", key)),
+            }
+        }
+
+        let mut recurrence = recurrence.ok_or_else(|| anyhow!("Missing FREQ in RRULE '{}'", s))?;
+        if month_day.is_some() && recurrence.frequency != Frequency::Monthly {
+            return Err(anyhow!("BYMONTHDAY needs FREQ=MONTHLY"));
+        }
+        if !weekdays.is_empty() && recurrence.frequency == Frequency::Monthly {
+            return Err(anyhow!("BYDAY is not supported with FREQ=MONTHLY"));
+        }
+        recurrence.interval = interval;
+        recurrence.weekdays = sorted_weekdays(weekdays);
+        recurrence.month_day = month_day;
+        recurrence.anchor = anchor;
+        Ok(recurrence)
+    }
+}
+
+/// Parse a recurrence rule written in words or as an RRULE.
+///
+/// Accepted rules:
+/// - `hourly`, `daily`, `weekly`, `monthly`, or `hour`, `day`, `week`, `month`
+/// - an interval: `3h`, `3 hours`, `2 days`, `2w`, `6 months`
+/// - `weekday` (Monday to Friday), `weekend`, or days of the week: `mon,wed,fri`
+/// - weekly rules followed by days of the week: `2 weeks on mon,thu`
+/// - monthly rules followed by a day of the month: `month on 15`, `monthly on the 1st`
+/// - an RRULE: `FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,TH`
+///
+/// The word `every` in front of a rule is ignored.
+impl FromStr for Recurrence {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let upper = s.trim().to_uppercase();
+        if upper.starts_with("FREQ=") || upper.starts_with("RRULE:") {
+            return Recurrence::parse_rrule(s);
+        }
+
+        let lowered = s.to_lowercase();
+        let tokens: Vec<&str> = lowered
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|token| !matches!(*token, "" | "every" | "on" | "the" | "and"))
+            .collect();
+        let invalid = || {
+            anyhow!(
+                "Invalid recurrence '{}'. Expected e.g. daily, weekday, mon,thu, 3 hours, monthly on 15 or an RRULE",
+                s.trim()
+            )
+        };
+        let first = *tokens.first().ok_or_else(invalid)?;
+
+        let mut weekdays = Vec::new();
+        let (mut recurrence, used) = match first {
+            "weekday" | "weekdays" => {
+                weekdays.extend(WORK_DAYS);
+                (Recurrence::new(Frequency::Weekly), 1)
+            }
+            "weekend" | "weekends" => {
+                weekdays.extend(WEEKEND_DAYS);
+                (Recurrence::new(Frequency::Weekly), 1)
+            }
+            "hourly" | "hour" => (Recurrence::new(Frequency::Hourly), 1),
+            "daily" | "day" => (Recurrence::new(Frequency::Daily), 1),
+            "weekly" | "week" => (Recurrence::new(Frequency::Weekly), 1),
+            "monthly" | "month" => (Recurrence::new(Frequency::Monthly), 1),
+            _ if dates::parse_weekday(first).is_some() => (Recurrence::new(Frequency::Weekly), 0),
+            _ => parse_interval(&tokens).ok_or_else(invalid)?,
+        };
+
+        for token in &tokens[used..] {
+            match (recurrence.frequency, dates::parse_weekday(token)) {
+                (Frequency::Weekly, Some(weekday)) if !weekdays.contains(&weekday) => {
+                    weekdays.push(weekday)
+                }
+                (Frequency::Monthly, None) if recurrence.month_day.is_none() => {
+                    let day = token.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+                    recurrence.month_day = Some(
+                        day.parse()
+                            .ok()
+                            .filter(|day| (1..=31).contains(day))
+                            .ok_or_else(invalid)?,
+                    );
+                }
+                _ => return Err(invalid()),
+            }
+        }
+        recurrence.weekdays = sorted_weekdays(weekdays);
+        Ok(recurrence)
+    }
+}
+
+/// Format the rule as an RRULE, as stored in the data file
+impl fmt::Display for Recurrence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let frequency = match self.frequency {
+            Frequency::Hourly => "HOURLY",
+            Frequency::Daily => "DAILY",
+            Frequency::Weekly => "WEEKLY",
+            Frequency::Monthly => "MONTHLY",
+        };
+        write!(f, "FREQ={}", frequency)?;
+        if self.interval > 1 {
+            write!(f, ";INTERVAL={}", self.interval)?;
+        }
+        if !self.weekdays.is_empty() {
+            let days: Vec<String> = self
+                .weekdays
+                .iter()
+                .map(|day| day.to_string()[..2].to_uppercase())
+                .collect();
+            write!(f, ";BYDAY={}", days.join(","))?;
+        }
+        if let Some(day) = self.month_day {
+            write!(f, ";BYMONTHDAY={}", day)?;
+        }
+        if let Some(anchor) = self.anchor {
+            write!(f, ";DTSTART={}", anchor.format(RRULE_DATE_TIME_FORMAT))?;
+        }
+        Ok(())
+    }
+}
+
+/// Parse an interval at the start of `tokens`, such as `3h`, `2 days` or `6 months`,
+/// returning the rule with the number of tokens used
+fn parse_interval(tokens: &[&str]) -> Option<(Recurrence, usize)> {
+    let token = tokens.first()?;
+    let split = token
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(token.len());
+    let (amount, unit, used) = match token.split_at(split) {
+        (amount, "") => (amount, *tokens.get(1)?, 2),
+        (amount, unit) => (amount, unit, 1),
+    };
+    let frequency = match unit {
+        "h" | "hr" | "hrs" | "hour" | "hours" => Frequency::Hourly,
+        "d" | "day" | "days" => Frequency::Daily,
+        "w" | "week" | "weeks" => Frequency::Weekly,
+        "mo" | "month" | "months" => Frequency::Monthly,
+        _ => return None,
+    };
+    let mut recurrence = Recurrence::new(frequency);
+    recurrence.interval = amount
+        .parse()
+        .ok()
+        .filter(|amount| (1..=MAX_INTERVAL).contains(amount))?;
+    Some((recurrence, used))
+}
+
+/// Returns the date `months` months after `date`, on `day` or on the last day of that month.
+/// None if it is out of the range of dates.
+fn month_date(date: NaiveDate, months: u32, day: u32) -> Option<NaiveDate> {
+    let first = date
+        .with_day(1)
+        .unwrap_or(date)
+        .checked_add_months(Months::new(months))?;
+    Some(
+        (1..=day)
+            .rev()
+            .find_map(|day| first.with_day(day))
+            .unwrap_or(first),
+    )
+}
+
+/// Parse a day of the week of an RRULE: MO, TU, WE, TH, FR, SA or SU
+fn rrule_weekday(day: &str) -> Option<Weekday> {
+    [
+        Weekday::Mon,
+        Weekday::Tue,
+        Weekday::Wed,
+        Weekday::Thu,
+        Weekday::Fri,
+        Weekday::Sat,
+        Weekday::Sun,
+    ]
+    .into_iter()
+    .find(|weekday| weekday.to_string()[..2].eq_ignore_ascii_case(day))
+}
+
+/// Returns the weekdays from Monday to Sunday, without duplicates
+fn sorted_weekdays(mut weekdays: Vec<Weekday>) -> Vec<Weekday> {
+    weekdays.sort_by_key(|day| day.num_days_from_monday());
+    weekdays.dedup();
+    weekdays
+}
+
+/// Returns a day of the month in words, e.g. `1st` or `22nd`
+fn ordinal(day: u32) -> String {
+    let suffix = match (day % 10, day % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+    format!("{}{}", day, suffix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date_time(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    fn rule(s: &str) -> Recurrence {
+        Recurrence::from_str(s).expect("Error parsing recurrence")
+    }
+
+    /// The first `n` occurrences of the rule from `start`
+    fn series(s: &str, start: &str, n: usize) -> Vec<String> {
+        let recurrence = rule(s);
+        let mut next = recurrence.first(date_time(start));
+        let mut occurrences = Vec::new();
+        while let Some(occurrence) = next.filter(|_| occurrences.len() < n) {
+            occurrences.push(occurrence.format("%a %Y-%m-%d %H:%M").to_string());
+            next = recurrence.next(occurrence);
+        }
+        occurrences
+    }
+
+    #[test]
+    fn test_recurrence_from_str() {
+        assert_eq!(rule("daily"), Recurrence::new(Frequency::Daily));
+        assert_eq!(
+            rule("every weekday").to_string(),
+            "FREQ=WEEKLY;BYDAY=MO,TU,WE,TH,FR"
+        );
+        assert_eq!(rule("Fri, mon").to_string(), "FREQ=WEEKLY;BYDAY=MO,FR");
+        assert_eq!(rule("3h").to_string(), "FREQ=HOURLY;INTERVAL=3");
+        assert_eq!(
+            rule("every 2 weeks on mon thu").to_string(),
+            "FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,TH"
+        );
+        assert_eq!(
+            rule("monthly on the 15th").to_string(),
+            "FREQ=MONTHLY;BYMONTHDAY=15"
+        );
+        assert_eq!(rule("6 months").to_string(), "FREQ=MONTHLY;INTERVAL=6");
+
+        assert!(Recurrence::from_str("").is_err());
+        assert!(Recurrence::from_str("15m").is_err());
+        assert!(Recurrence::from_str("daily on mon").is_err());
+        assert!(Recurrence::from_str("monthly on 32").is_err());
+        assert!(Recurrence::from_str("0 days").is_err());
+    }
+
+    #[test]
+    fn test_recurrence_rrule() {
+        let recurrence = rule("RRULE:FREQ=weekly;INTERVAL=2;BYDAY=TH,MO");
+        assert_eq!(recurrence.to_string(), "FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,TH");
+        assert_eq!(rule(&recurrence.to_string()), recurrence);
+        assert_eq!(rule("FREQ=MONTHLY;BYMONTHDAY=31").month_day, Some(31));
+
+        let anchored = rule("FREQ=DAILY;DTSTART=20261017T090000");
+        assert_eq!(anchored.anchor.unwrap().to_string(), "2026-10-17 09:00:00");
+        assert_eq!(anchored.to_string(), "FREQ=DAILY;DTSTART=20261017T090000");
+        assert!(Recurrence::from_str("FREQ=DAILY;DTSTART=tomorrow").is_err());
+
+        assert_eq!(
+            Recurrence::from_str("FREQ=YEARLY").unwrap_err().to_string(),
+            "Unsupported RRULE frequency 'YEARLY'"
+        );
+        assert_eq!(
+            Recurrence::from_str("FREQ=DAILY;COUNT=3")
+                .unwrap_err()
+                .to_string(),
+            "Unsupported RRULE part 'COUNT'"
+        );
+        assert!(Recurrence::from_str("FREQ=DAILY;BYMONTHDAY=3").is_err());
+        assert!(Recurrence::from_str("FREQ=WEEKLY;BYDAY=1MO").is_err());
+    }
+
+    #[test]
+    fn test_recurrence_describe() {
+        assert_eq!(rule("weekdays").describe(), "every weekday");
+        assert_eq!(rule("daily").describe(), "every day");
+        assert_eq!(rule("3 hours").describe(), "every 3 hours");
+        assert_eq!(rule("2w on tue").describe(), "every 2 weeks on Tue");
+        assert_eq!(rule("month 2").describe(), "every month on the 2nd");
+    }
+
+    #[test]
+    fn test_recurrence_next() {
+        // 2026-10-17 is a Saturday
+        assert_eq!(
+            series("weekday", "2026-10-17 09:30", 3),
+            vec![
+                "Mon 2026-10-19 09:30",
+                "Tue 2026-10-20 09:30",
+                "Wed 2026-10-21 09:30"
+            ]
+        );
+        assert_eq!(
+            series("2 weeks on mon,thu", "2026-10-19 08:00", 4),
+            vec![
+                "Mon 2026-10-19 08:00",
+                "Thu 2026-10-22 08:00",
+                "Mon 2026-11-02 08:00",
+                "Thu 2026-11-05 08:00"
+            ]
+        );
+        assert_eq!(
+            series("8h", "2026-10-17 20:00", 2),
+            vec!["Sat 2026-10-17 20:00", "Sun 2026-10-18 04:00"]
+        );
+        assert_eq!(
+            series("weekly", "2026-10-17 09:00", 2),
+            vec!["Sat 2026-10-17 09:00", "Sat 2026-10-24 09:00"]
+        );
+        // The last day of shorter months, and the 31st again after them
+        assert_eq!(
+            series("monthly on 31", "2027-01-05 09:00", 3),
+            vec![
+                "Sun 2027-01-31 09:00",
+                "Sun 2027-02-28 09:00",
+                "Wed 2027-03-31 09:00"
+            ]
+        );
+        let monthly = rule("monthly").anchored(date_time("2027-01-31 09:00"));
+        assert_eq!(monthly.month_day, Some(31));
+    }
+
+    #[test]
+    fn test_recurrence_limits() {
+        assert!(Recurrence::from_str("FREQ=MONTHLY;INTERVAL=400000000").is_err());
+        assert!(Recurrence::from_str("1000000000 weeks on sun").is_err());
+        assert!(Recurrence::from_str("1000 weeks on sun").is_ok());
+
+        // Rules built directly are not capped, but stop at the end of the range of dates
+        let start = date_time("2026-10-17 09:00");
+        for frequency in [
+            Frequency::Hourly,
+            Frequency::Daily,
+            Frequency::Weekly,
+            Frequency::Monthly,
+        ] {
+            let mut recurrence = Recurrence::new(frequency);
+            recurrence.interval = u32::MAX;
+            assert_eq!(recurrence.next(start), None);
+        }
+        let mut recurrence = rule("sun");
+        recurrence.interval = u32::MAX;
+        assert_eq!(recurrence.next(start), Some(date_time("2026-10-18 09:00")));
+        assert_eq!(recurrence.next(date_time("2026-10-18 09:00")), None);
+        assert_eq!(rule("sun").next(NaiveDateTime::MAX), None);
+        assert_eq!(rule("monthly").next(NaiveDateTime::MAX), None);
+    }
+
+    #[test]
+    fn test_recurrence_first_after_and_occurrences() {
+        let recurrence = rule("daily");
+        let now = date_time("2026-10-17 15:30");
+        assert_eq!(
+            recurrence.first_after(date_time("2026-10-17 09:00"), now),
+            Some(date_time("2026-10-18 09:00"))
+        );
+        assert_eq!(
+            recurrence.first_after(date_time("2026-10-10 18:00"), now),
+            Some(date_time("2026-10-17 18:00"))
+        );
+
+        let occurrences = recurrence.occurrences(
+            date_time("2026-10-16 09:00"),
+            now,
+            date_time("2026-10-20 09:00"),
+        );
+        assert_eq!(
+            occurrences,
+            vec![
+                date_time("2026-10-18 09:00"),
+                date_time("2026-10-19 09:00"),
+                date_time("2026-10-20 09:00")
+            ]
+        );
+    }
+}
@@ -0,0 +1,120 @@
+use anyhow::{Context, Result};
+use regex::{Regex, RegexBuilder};
+use std::ops::Range;
+
+/// How the text of a search query is matched against the memos
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum SearchMode {
+    /// The query appears anywhere in the text
+    #[default]
+    Substring,
+    /// The query appears as whole words
+    Word,
+    /// The query is a regular expression
+    Regex,
+}
+
+/// A search query, matched case-insensitively in every mode
+#[derive(Debug)]
+pub struct Query {
+    regex: Regex,
+}
+
+impl Query {
+    /// Create a query from its text and mode
+    pub fn new(text: &str, mode: SearchMode) -> Result<Self> {
+        let pattern = match mode {
+            SearchMode::Substring => regex::escape(text),
+            SearchMode::Word => format!(r"\b{}\b", regex::escape(text)),
+            SearchMode::Regex => text.to_string(),
+        };
+        let regex = RegexBuilder::new(&pattern)
+            .case_insensitive(true)
+            .build()
+            .with_context(|| format!("Invalid search query '{}'", text))?;
+        Ok(Query { regex })
+    }
+
+    /// Returns true if the query matches a non-empty part of the text
+    pub fn matches(&self, text: &str) -> bool {
+        self.regex.find_iter(text).any(|m| !m.is_empty())
+    }
+
+    /// Returns the byte ranges of the non-empty matches of the query in the text
+    pub fn match_ranges(&self, text: &str) -> Vec<Range<usize>> {
+        self.regex
+            .find_iter(text)
+            .filter(|m| !m.is_empty())
+            .map(|m| m.range())
+            .collect()
+    }
+
+    /// Relevance of the text for the query, higher is more relevant.
+    /// Every match counts, and matches covering whole words count double.
+    pub fn score(&self, text: &str) -> usize {
+        self.match_ranges(text)
+            .iter()
+            .map(|range| {
+                let before = text[..range.start].chars().next_back();
+                let after = text[range.end..].chars().next();
+                let is_word_start = before.is_none_or(|c| !c.is_alphanumeric());
+                let is_word_end = after.is_none_or(|c| !c.is_alphanumeric());
+                if is_word_start && is_word_end {
+                    2
+                } else {
+                    1
+                }
+            })
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_substring() {
+        let query = Query::new("MILK", SearchMode::Substring).unwrap();
+        assert!(query.matches("buy milk"));
+        assert!(query.matches("buttermilk"));
+        assert!(!query.matches("buy eggs"));
+
+        let query = Query::new("a.b", SearchMode::Substring).unwrap();
+        assert!(query.matches("see a.b"));
+        assert!(!query.matches("see axb"));
+    }
+
+    #[test]
+    fn test_word() {
+        let query = Query::new("milk", SearchMode::Word).unwrap();
+        assert!(query.matches("buy Milk today"));
+        assert!(!query.matches("buttermilk"));
+    }
+
+    #[test]
+    fn test_regex() {
+        let query = Query::new(r"call \w+", SearchMode::Regex).unwrap();
+        assert!(query.matches("Call Bob"));
+        assert!(!query.matches("call"));
+        assert!(Query::new("(", SearchMode::Regex).is_err());
+    }
+
+    #[test]
+    fn test_match_ranges() {
+        let query = Query::new("ab", SearchMode::Substring).unwrap();
+        assert_eq!(query.match_ranges("ab cab"), vec![0..2, 4..6]);
+        let query = Query::new("x*", SearchMode::Regex).unwrap();
+        assert!(query.match_ranges("abc").is_empty());
+        assert!(!query.matches("abc"));
+    }
+
+    #[test]
+    fn test_score() {
+        let query = Query::new("milk", SearchMode::Substring).unwrap();
+        assert_eq!(query.score("buy milk"), 2);
+        assert_eq!(query.score("buttermilk"), 1);
+        assert_eq!(query.score("milk and more milk"), 4);
+        assert_eq!(query.score("eggs"), 0);
+    }
+}
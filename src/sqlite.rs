@@ -0,0 +1,654 @@
+use crate::app;
+use crate::data::{file_exist, DataFile, DisplayMode, Filter};
+use crate::jsonl;
+use crate::models::{Content, MemoData, Priority, Trashed};
+use crate::output::OutputFormat;
+use crate::recur::Recurrence;
+use crate::search::Query;
+use crate::DATE_TIME_FORMAT;
+use anyhow::{anyhow, Context, Result};
+use chrono::prelude::*;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::HashSet;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+use uuid::Uuid;
+
+/// Schema migrations, applied in order. The index of the last applied migration plus one is
+/// stored in the `user_version` pragma of the database.
+const MIGRATIONS: [&str; 7] = [
+    "
+    CREATE TABLE memos (
+        id INTEGER PRIMARY KEY,
+        text TEXT NOT NULL,
+        created TEXT NOT NULL,
+        due TEXT,
+        done TEXT,
+        priority TEXT
+    );
+    CREATE INDEX memos_due ON memos (due);
+    CREATE INDEX memos_done ON memos (done);
+    CREATE TABLE tags (
+        memo_id INTEGER NOT NULL REFERENCES memos (id) ON DELETE CASCADE,
+        tag TEXT NOT NULL,
+        PRIMARY KEY (memo_id, tag)
+    );
+    CREATE INDEX tags_tag ON tags (tag);
+    ",
+    "
+    ALTER TABLE memos ADD COLUMN updated TEXT;
+    ",
+    "
+    CREATE TABLE trash (
+        memo TEXT NOT NULL
+    );
+    ",
+    "
+    ALTER TABLE memos ADD COLUMN uuid TEXT;
+    CREATE INDEX memos_uuid ON memos (uuid);
+    ",
+    "
+    ALTER TABLE memos ADD COLUMN every TEXT;
+    ",
+    "
+    ALTER TABLE memos ADD COLUMN snoozed INTEGER NOT NULL DEFAULT 0;
+    ",
+    "
+    DROP INDEX IF EXISTS memos_due;
+    DROP INDEX IF EXISTS memos_done;
+    DROP INDEX IF EXISTS tags_tag;
+    DROP INDEX IF EXISTS memos_uuid;
+    ",
+];
+
+/// Memos stored in an SQLite database.
+/// The whole database is read into memory as a MemoData when loading, and lookups, filters and
+/// searches run on it like with the other backends, so the tables have no secondary indexes.
+/// Only the memos that changed are written back to the database, in a single transaction,
+/// when saving. The trash is stored as one JSON record per memo and rewritten when it changes.
+pub struct SqliteData {
+    connection: Option<Connection>,
+    data: MemoData,
+    changed: HashSet<u32>,
+    removed: HashSet<u32>,
+    trash_changed: bool,
+}
+
+impl SqliteData {
+    /// Create a new SqliteData, not connected to any database yet
+    pub fn new() -> Self {
+        SqliteData {
+            connection: None,
+            data: MemoData::new(),
+            changed: HashSet::new(),
+            removed: HashSet::new(),
+            trash_changed: false,
+        }
+    }
+
+    /// Create a SqliteData holding the memos of a MemoData, all of them will be written on save
+    pub fn from_memo_data(data: MemoData) -> Self {
+        let mut sqlite_data = SqliteData::new();
+        sqlite_data.changed = data.contents.keys().cloned().collect();
+        sqlite_data.trash_changed = !data.trash.is_empty();
+        sqlite_data.data = data;
+        sqlite_data
+    }
+
+    /// Returns the memos held in memory
+    pub fn into_memo_data(self) -> MemoData {
+        self.data
+    }
+
+    /// Returns the connection to the database, opening it if needed
+    fn connection(&mut self, path: &Path) -> Result<&mut Connection> {
+        if self.connection.is_none() {
+            self.connection = Some(connect(path)?);
+        }
+        self.connection
+            .as_mut()
+            .ok_or_else(|| anyhow!("Database not loaded"))
+    }
+
+    /// Mark an item as changed if the result of an operation on it is ok
+    fn track(&mut self, id: u32, result: Result<()>) -> Result<()> {
+        result?;
+        self.changed.insert(id);
+        Ok(())
+    }
+}
+
+/// Open the database and bring its schema up to date
+fn connect(path: &Path) -> Result<Connection> {
+    let mut connection = Connection::open(path)
+        .with_context(|| format!("Could not open database '{}'", path.display()))?;
+    migrate(&mut connection)?;
+    Ok(connection)
+}
+
+/// Apply the migrations that were not applied to the database yet
+fn migrate(connection: &mut Connection) -> Result<()> {
+    let version: usize = connection.pragma_query_value(None, "user_version", |row| row.get(0))?;
+    if version > MIGRATIONS.len() {
+        return Err(anyhow!(
+            "Database schema version {} is newer than the supported version {}",
+            version,
+            MIGRATIONS.len()
+        ));
+    }
+
+    let transaction = connection.transaction()?;
+    for (i, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+        transaction
+            .execute_batch(migration)
+            .with_context(|| format!("Could not apply migration {}", i + 1))?;
+        transaction.pragma_update(None, "user_version", i + 1)?;
+    }
+    transaction.commit()?;
+    Ok(())
+}
+
+/// Read all the memos and their tags from the database, with the ids of the memos that must be
+/// written back: memos written before the uuid column was added, given a new UUID, and those
+/// whose UUID is used by another memo.
+fn read_memos(connection: &Connection) -> Result<(MemoData, HashSet<u32>)> {
+    let mut data = MemoData::new();
+    let mut repaired = HashSet::new();
+    let mut statement = connection.prepare(
+        "SELECT id, text, created, due, done, priority, updated, uuid, every, snoozed FROM memos",
+    )?;
+    let mut rows = statement.query([])?;
+    while let Some(row) = rows.next()? {
+        let id: u32 = row.get(0)?;
+        let text: String = row.get(1)?;
+        let created = parse_date_time(row.get(2)?)?
+            .ok_or_else(|| anyhow!("Missing creation date for id '{}'", id))?;
+
+        let mut content = Content::new(&text, created);
+        content.due = parse_date_time(row.get(3)?)?;
+        content.done = parse_date_time(row.get(4)?)?;
+        content.priority = row
+            .get::<_, Option<String>>(5)?
+            .map(|priority| Priority::from_str(&priority))
+            .transpose()?;
+        content.updated_at = parse_date_time(row.get(6)?)?;
+        match row.get::<_, Option<String>>(7)? {
+            Some(uuid) => {
+                content.uuid = Uuid::parse_str(&uuid)
+                    .with_context(|| format!("Invalid uuid '{}' for id '{}'", uuid, id))?
+            }
+            None => {
+                repaired.insert(id);
+            }
+        }
+        content.recurrence = row
+            .get::<_, Option<String>>(8)?
+            .map(|every| Recurrence::from_str(&every))
+            .transpose()?;
+        content.snoozed = row.get(9)?;
+        data.contents.insert(id, content);
+    }
+
+    let mut statement = connection.prepare("SELECT memo_id, tag FROM tags ORDER BY rowid")?;
+    let mut rows = statement.query([])?;
+    while let Some(row) = rows.next()? {
+        let id: u32 = row.get(0)?;
+        if let Some(content) = data.contents.get_mut(&id) {
+            content.tags.push(row.get(1)?);
+        }
+    }
+
+    let mut statement = connection.prepare("SELECT memo FROM trash ORDER BY rowid")?;
+    let mut rows = statement.query([])?;
+    while let Some(row) = rows.next()? {
+        let memo: String = row.get(0)?;
+        data.trash
+            .push(jsonl::parse_trashed(&memo).context("Invalid memo in the trash")?);
+    }
+    repaired.extend(data.repair_uuids());
+    Ok((data, repaired))
+}
+
+/// Write the changed and removed memos, and the trash if it changed, to the database
+/// in a single transaction
+fn write_memos(
+    connection: &mut Connection,
+    data: &MemoData,
+    changed: &HashSet<u32>,
+    removed: &HashSet<u32>,
+    trash_changed: bool,
+) -> Result<()> {
+    let transaction = connection.transaction()?;
+
+    for id in removed {
+        transaction.execute("DELETE FROM tags WHERE memo_id = ?1", params![id])?;
+        transaction.execute("DELETE FROM memos WHERE id = ?1", params![id])?;
+    }
+
+    for id in changed {
+        let Some(content) = data.get(*id) else {
+            continue;
+        };
+        transaction.execute(
+            "INSERT OR REPLACE INTO memos (id, text, created, due, done, priority, updated, uuid, every, snoozed)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            params![
+                id,
+                content.text,
+                content.date_time.format(DATE_TIME_FORMAT).to_string(),
+                format_date_time(content.due),
+                format_date_time(content.done),
+                content.priority.map(|priority| priority.to_string()),
+                format_date_time(content.updated_at),
+                content.uuid.to_string(),
+                content
+                    .recurrence
+                    .as_ref()
+                    .map(|recurrence| recurrence.to_string()),
+                content.snoozed,
+            ],
+        )?;
+        transaction.execute("DELETE FROM tags WHERE memo_id = ?1", params![id])?;
+        for tag in &content.tags {
+            transaction.execute(
+                "INSERT OR IGNORE INTO tags (memo_id, tag) VALUES (?1, ?2)",
+                params![id, tag],
+            )?;
+        }
+    }
+
+    if trash_changed {
+        transaction.execute("DELETE FROM trash", [])?;
+        for trashed in &data.trash {
+            transaction.execute(
+                "INSERT INTO trash (memo) VALUES (?1)",
+                params![jsonl::trashed_to_string(trashed)?],
+            )?;
+        }
+    }
+    transaction.commit()?;
+    Ok(())
+}
+
+/// Parse a date time stored in the database
+fn parse_date_time(value: Option<String>) -> Result<Option<NaiveDateTime>> {
+    value
+        .map(|value| {
+            NaiveDateTime::parse_from_str(&value, DATE_TIME_FORMAT)
+                .with_context(|| format!("invalid date time '{}'", value))
+        })
+        .transpose()
+}
+
+/// Format a date time to be stored in the database
+fn format_date_time(value: Option<NaiveDateTime>) -> Option<String> {
+    value.map(|value| value.format(DATE_TIME_FORMAT).to_string())
+}
+
+/// Implement Default trait for SqliteData
+impl Default for SqliteData {
+    /// Create a new SqliteData
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Implement DataFile trait for SqliteData
+impl DataFile for SqliteData {
+    /// Load data from the database
+    fn load(&mut self, app: &app::AppConfig) -> Result<()> {
+        let path = app.data_file_path();
+        file_exist(&path)?;
+        let (data, repaired) = read_memos(self.connection(&path)?)?;
+        self.data = data;
+        self.changed = repaired;
+        self.removed.clear();
+        self.trash_changed = false;
+        Ok(())
+    }
+
+    /// Write the memos that changed to the database in a single transaction.
+    /// The database is not copied to rotating backups, a failed transaction leaves it as it was.
+    fn save(&mut self, app: &app::AppConfig) -> Result<()> {
+        let data = std::mem::take(&mut self.data);
+        let changed = std::mem::take(&mut self.changed);
+        let removed = std::mem::take(&mut self.removed);
+        let trash_changed = std::mem::take(&mut self.trash_changed);
+
+        let result = self
+            .connection(&app.data_file_path())
+            .and_then(|connection| {
+                write_memos(connection, &data, &changed, &removed, trash_changed)
+            });
+
+        self.data = data;
+        if result.is_err() {
+            // Keep track of the changes so saving can be retried
+            self.changed = changed;
+            self.removed = removed;
+            self.trash_changed = trash_changed;
+        }
+        result
+    }
+
+    /// Return sorted ids of items in SqliteData
+    fn sorted_ids(&self) -> Vec<u32> {
+        self.data.sorted_ids()
+    }
+
+    /// Return item of SqliteData given its id
+    fn get(&self, id: u32) -> Option<&Content> {
+        self.data.get(id)
+    }
+
+    /// Add item to SqliteData
+    fn add(&mut self, id: u32, name: &str) -> Result<()> {
+        let result = self.data.add(id, name);
+        self.track(id, result)
+    }
+
+    /// Set or clear the due date of an item in SqliteData
+    fn set_due(&mut self, id: u32, due: Option<NaiveDateTime>) -> Result<()> {
+        let result = self.data.set_due(id, due);
+        self.track(id, result)
+    }
+
+    /// Mark an item in SqliteData as done or as not done
+    fn set_done(&mut self, id: u32, done: bool) -> Result<()> {
+        let result = self.data.set_done(id, done);
+        self.track(id, result)
+    }
+
+    /// Set the tags of an item in SqliteData
+    fn set_tags(&mut self, id: u32, tags: Vec<String>) -> Result<()> {
+        let result = self.data.set_tags(id, tags);
+        self.track(id, result)
+    }
+
+    /// Set or clear the priority of an item in SqliteData
+    fn set_priority(&mut self, id: u32, priority: Option<Priority>) -> Result<()> {
+        let result = self.data.set_priority(id, priority);
+        self.track(id, result)
+    }
+
+    /// Put off an item in SqliteData to a new due date, counting the snooze
+    fn snooze(&mut self, id: u32, due: NaiveDateTime) -> Result<()> {
+        let result = self.data.snooze(id, due);
+        self.track(id, result)
+    }
+
+    /// Set or clear the recurrence rule of an item in SqliteData
+    fn set_recurrence(&mut self, id: u32, recurrence: Option<Recurrence>) -> Result<()> {
+        let result = self.data.set_recurrence(id, recurrence);
+        self.track(id, result)
+    }
+
+    /// Replace the text of an item in SqliteData, recording when it was edited
+    fn update(&mut self, id: u32, text: &str) -> Result<()> {
+        let result = self.data.update(id, text);
+        self.track(id, result)
+    }
+
+    /// Insert an item in SqliteData as it is, replacing the item with the same id.
+    /// A copy of the item in the trash, with the same id and creation time, is dropped.
+    fn insert(&mut self, id: u32, content: Content) -> Result<()> {
+        let result = self.data.insert(id, content);
+        self.trash_changed = true;
+        self.track(id, result)
+    }
+
+    /// Move item from SqliteData to the trash
+    fn remove(&mut self, id: u32) -> Result<()> {
+        self.data.remove(id)?;
+        self.changed.remove(&id);
+        self.removed.insert(id);
+        self.trash_changed = true;
+        Ok(())
+    }
+
+    /// Give items new ids at once, as (old id, new id) pairs
+    fn renumber(&mut self, moves: &[(u32, u32)]) -> Result<()> {
+        self.data.renumber(moves)?;
+        for (from, _) in moves {
+            self.changed.remove(from);
+            self.removed.insert(*from);
+        }
+        self.changed.extend(moves.iter().map(|(_, to)| *to));
+        Ok(())
+    }
+
+    /// Return the items in the trash of SqliteData, oldest removal first
+    fn trash(&self) -> &[Trashed] {
+        self.data.trash()
+    }
+
+    /// Move the last removed item with the id back from the trash, returning its new id
+    fn restore(&mut self, id: u32) -> Result<u32> {
+        let new_id = self.data.restore(id)?;
+        self.trash_changed = true;
+        self.changed.insert(new_id);
+        Ok(new_id)
+    }
+
+    /// Permanently delete the items removed before the given time, or all of them,
+    /// from the trash of SqliteData
+    fn purge(&mut self, removed_before: Option<NaiveDateTime>) -> Result<usize> {
+        let count = self.data.purge(removed_before)?;
+        self.trash_changed |= count > 0;
+        Ok(count)
+    }
+
+    /// Search the text of SqliteData
+    fn search(&self, query: &Query, ranked: bool) -> Result<String> {
+        self.data.search(query, ranked)
+    }
+
+    /// Render SqliteData in the given layout and output format
+    fn render(&self, mode: DisplayMode, filter: &Filter, format: OutputFormat) -> Result<String> {
+        self.data.render(mode, filter, format)
+    }
+}
+
+/// Implement Display trait for SqliteData
+impl fmt::Display for SqliteData {
+    /// Format SqliteData for display
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.data)
+    }
+}
+
+/// Returns true if the file at `path` is an SQLite database
+pub fn is_database(path: &Path) -> bool {
+    let mut header = [0; 16];
+    std::fs::File::open(path)
+        .and_then(|mut file| std::io::Read::read_exact(&mut file, &mut header))
+        .is_ok_and(|_| &header == b"SQLite format 3\0")
+}
+
+/// Returns the schema version of the database at `path`, None if it has no schema yet
+pub fn schema_version(path: &Path) -> Result<Option<usize>> {
+    let connection = Connection::open(path)?;
+    let version: Option<usize> = connection
+        .query_row("PRAGMA user_version", [], |row| row.get(0))
+        .optional()?;
+    Ok(version.filter(|version| *version > 0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn app_config(dir: &Path) -> app::AppConfig {
+        let mut app_config = app::AppConfig::new("memo", app::Backend::Sqlite.data_file());
+        app_config.data_dir = dir.to_path_buf();
+        std::fs::File::create(app_config.data_file_path()).unwrap();
+        app_config
+    }
+
+    #[test]
+    fn test_sqlite_data_load_empty() {
+        let dir = tempdir().unwrap();
+        let app_config = app_config(dir.path());
+
+        let mut d = SqliteData::new();
+        assert!(d.load(&app_config).is_ok());
+        assert!(d.sorted_ids().is_empty());
+        assert_eq!(
+            schema_version(&app_config.data_file_path()).unwrap(),
+            Some(MIGRATIONS.len())
+        );
+        assert!(is_database(&app_config.data_file_path()));
+    }
+
+    #[test]
+    fn test_sqlite_data_load_missing() {
+        let dir = tempdir().unwrap();
+        let mut app_config = app::AppConfig::new("memo", "memo.db");
+        app_config.data_dir = dir.path().to_path_buf();
+
+        let mut d = SqliteData::new();
+        assert!(d.load(&app_config).is_err());
+    }
+
+    #[test]
+    fn test_sqlite_data_save_and_load() {
+        let dir = tempdir().unwrap();
+        let app_config = app_config(dir.path());
+        let due = NaiveDate::from_ymd_opt(2021, 1, 2)
+            .unwrap()
+            .and_hms_opt(9, 0, 0)
+            .unwrap();
+
+        let mut d = SqliteData::new();
+        d.load(&app_config).unwrap();
+        d.add(1, "one").unwrap();
+        d.add(2, "two").unwrap();
+        d.add(3, "three").unwrap();
+        d.set_due(1, Some(due)).unwrap();
+        d.set_done(2, true).unwrap();
+        d.set_tags(1, vec!["work".to_string(), "home".to_string()])
+            .unwrap();
+        d.set_priority(1, Priority::new('A')).unwrap();
+        d.set_recurrence(1, Some(Recurrence::from_str("weekday").unwrap()))
+            .unwrap();
+        d.snooze(1, due).unwrap();
+        d.update(2, "two edited").unwrap();
+        d.remove(3).unwrap();
+        d.save(&app_config).unwrap();
+
+        let mut d = SqliteData::new();
+        d.load(&app_config).unwrap();
+        assert_eq!(d.sorted_ids(), vec![1, 2]);
+        let one = d.get(1).unwrap();
+        assert_eq!(one.text, "one");
+        assert_eq!(one.due, Some(due));
+        assert_eq!(one.tags, vec!["work", "home"]);
+        assert_eq!(one.priority, Priority::new('A'));
+        assert_eq!(one.recurrence.as_ref().unwrap().describe(), "every weekday");
+        assert_eq!(one.snoozed, 1);
+        assert!(d.get(2).unwrap().recurrence.is_none());
+        assert!(d.get(2).unwrap().done.is_some());
+        assert_eq!(d.get(2).unwrap().text, "two edited");
+        assert!(d.get(2).unwrap().updated_at.is_some());
+        assert!(one.updated_at.is_none());
+
+        assert_eq!(d.trash().len(), 1);
+        assert_eq!(d.trash()[0].content.text, "three");
+
+        d.remove(1).unwrap();
+        d.save(&app_config).unwrap();
+
+        let mut d = SqliteData::new();
+        d.load(&app_config).unwrap();
+        assert_eq!(d.sorted_ids(), vec![2]);
+        assert_eq!(d.trash().len(), 2);
+
+        assert_eq!(d.restore(3).unwrap(), 3);
+        assert_eq!(d.purge(None).unwrap(), 1);
+        d.save(&app_config).unwrap();
+
+        let mut d = SqliteData::new();
+        d.load(&app_config).unwrap();
+        assert_eq!(d.sorted_ids(), vec![2, 3]);
+        assert!(d.trash().is_empty());
+    }
+
+    #[test]
+    fn test_sqlite_data_save_without_backups() {
+        let dir = tempdir().unwrap();
+        let app_config = app_config(dir.path());
+
+        let mut d = SqliteData::new();
+        d.load(&app_config).unwrap();
+        d.add(1, "one").unwrap();
+        d.save(&app_config).unwrap();
+        d.add(2, "two").unwrap();
+        d.save(&app_config).unwrap();
+        assert!(crate::data::rotated_backups(&app_config.data_file_path()).is_empty());
+    }
+
+    #[test]
+    fn test_sqlite_data_from_memo_data() {
+        let dir = tempdir().unwrap();
+        let app_config = app_config(dir.path());
+
+        let mut memo_data = MemoData::new();
+        memo_data.add(1, "one").unwrap();
+        memo_data.add(5, "five").unwrap();
+
+        let mut d = SqliteData::from_memo_data(memo_data);
+        d.save(&app_config).unwrap();
+
+        let mut d = SqliteData::new();
+        d.load(&app_config).unwrap();
+        assert_eq!(d.into_memo_data().sorted_ids(), vec![1, 5]);
+    }
+
+    #[test]
+    fn test_sqlite_data_upgrade_schema() {
+        let dir = tempdir().unwrap();
+        let app_config = app_config(dir.path());
+        let connection = Connection::open(app_config.data_file_path()).unwrap();
+        connection.execute_batch(MIGRATIONS[0]).unwrap();
+        connection.pragma_update(None, "user_version", 1).unwrap();
+        connection
+            .execute(
+                "INSERT INTO memos (id, text, created) VALUES (1, 'one', '2001-01-01 01:01:01')",
+                [],
+            )
+            .unwrap();
+        drop(connection);
+
+        let mut d = SqliteData::new();
+        d.load(&app_config).unwrap();
+        assert_eq!(d.get(1).unwrap().text, "one");
+        assert_eq!(
+            schema_version(&app_config.data_file_path()).unwrap(),
+            Some(MIGRATIONS.len())
+        );
+        let uuid = d.get(1).unwrap().uuid;
+        assert_eq!(uuid.get_version_num(), 4);
+
+        // The secondary indexes of the first schema are dropped, memos are looked up in memory
+        let connection = Connection::open(app_config.data_file_path()).unwrap();
+        let indexes: usize = connection
+            .query_row(
+                "SELECT COUNT(*) FROM sqlite_master WHERE type = 'index' AND sql IS NOT NULL",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(indexes, 0);
+        drop(connection);
+
+        // The new UUID is stored the next time the database is written
+        d.save(&app_config).unwrap();
+        let connection = Connection::open(app_config.data_file_path()).unwrap();
+        let stored: String = connection
+            .query_row("SELECT uuid FROM memos WHERE id = 1", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(stored, uuid.to_string());
+    }
+}
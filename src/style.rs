@@ -1,16 +1,157 @@
+use anyhow::{anyhow, Result};
+use chrono::format::{Item, StrftimeItems};
+use chrono::NaiveDateTime;
 use colored::{ColoredString, Colorize};
+use std::sync::RwLock;
+use std::{fmt, str::FromStr};
+
+/// Date time format used to show dates to the user when none is configured
+pub const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d %H:%M";
 
 pub enum Options {
     Title,
     Error,
     Muted,
+    Overdue,
+    DueToday,
+    Done,
+    Tag,
+    HighPriority,
+    Highlight,
+}
+
+/// Color theme of the terminal output
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Theme {
+    /// Colors and text effects
+    #[default]
+    Default,
+    /// Text effects only, for terminals with a custom palette
+    Mono,
+    /// Plain text
+    None,
+}
+
+impl FromStr for Theme {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "default" => Ok(Theme::Default),
+            "mono" => Ok(Theme::Mono),
+            "none" => Ok(Theme::None),
+            _ => Err(anyhow!(
+                "Invalid theme '{}'. Expected: default, mono or none",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Theme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Theme::Default => write!(f, "default"),
+            Theme::Mono => write!(f, "mono"),
+            Theme::None => write!(f, "none"),
+        }
+    }
+}
+
+/// Display settings shared by every output of the application
+struct Settings {
+    theme: Theme,
+    date_format: Option<String>,
+}
+
+static SETTINGS: RwLock<Settings> = RwLock::new(Settings {
+    theme: Theme::Default,
+    date_format: None,
+});
+
+/// Set the color theme of the terminal output
+pub fn set_theme(theme: Theme) {
+    if let Ok(mut settings) = SETTINGS.write() {
+        settings.theme = theme;
+    }
+}
+
+/// Set the format used to show dates, see `validate_date_format`
+pub fn set_date_format(date_format: &str) -> Result<()> {
+    validate_date_format(date_format)?;
+    if let Ok(mut settings) = SETTINGS.write() {
+        settings.date_format = Some(date_format.to_string());
+    }
+    Ok(())
+}
+
+/// Check that a date format only has valid `strftime` specifiers, e.g. `%d/%m/%Y %H:%M`
+pub fn validate_date_format(date_format: &str) -> Result<()> {
+    if date_format.is_empty() || StrftimeItems::new(date_format).any(|item| item == Item::Error) {
+        return Err(anyhow!("Invalid date format '{}'", date_format));
+    }
+    Ok(())
+}
+
+/// Format a date time to show it to the user
+pub fn date_time(date_time: NaiveDateTime) -> String {
+    let settings = SETTINGS.read();
+    let date_format = settings
+        .as_ref()
+        .ok()
+        .and_then(|settings| settings.date_format.as_deref())
+        .unwrap_or(DEFAULT_DATE_FORMAT);
+    date_time.format(date_format).to_string()
 }
 
 /// Prints a title in the terminal
 pub fn str(text: &str, option: Options) -> ColoredString {
-    match option {
-        Options::Title => text.green().bold(),
-        Options::Error => text.red(),
-        Options::Muted => text.dimmed(),
+    let theme = SETTINGS
+        .read()
+        .map(|settings| settings.theme)
+        .unwrap_or_default();
+    match theme {
+        Theme::Default => match option {
+            Options::Title => text.green().bold(),
+            Options::Error => text.red(),
+            Options::Muted => text.dimmed(),
+            Options::Overdue => text.red().bold(),
+            Options::DueToday => text.yellow(),
+            Options::Done => text.dimmed().strikethrough(),
+            Options::Tag => text.cyan(),
+            Options::HighPriority => text.magenta().bold(),
+            Options::Highlight => text.black().on_yellow(),
+        },
+        Theme::Mono => match option {
+            Options::Title | Options::Error | Options::HighPriority => text.bold(),
+            Options::Muted => text.dimmed(),
+            Options::Overdue => text.bold().underline(),
+            Options::DueToday => text.underline(),
+            Options::Done => text.dimmed().strikethrough(),
+            Options::Tag => text.italic(),
+            Options::Highlight => text.reversed(),
+        },
+        Theme::None => text.normal(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_theme_from_str() {
+        assert_eq!(Theme::from_str("Mono").unwrap(), Theme::Mono);
+        assert_eq!(Theme::from_str("none").unwrap(), Theme::None);
+        assert!(Theme::from_str("dark").is_err());
+        assert_eq!(Theme::default().to_string(), "default");
+    }
+
+    #[test]
+    fn test_validate_date_format() {
+        assert!(validate_date_format("%d/%m/%Y %H:%M").is_ok());
+        assert!(validate_date_format("%Q").is_err());
+        assert!(validate_date_format("").is_err());
+        assert!(set_date_format("%Q").is_err());
     }
 }